@@ -1263,6 +1263,12 @@ onmessage = function (e) {
             ' hitRate=' + (wasmResult.ttHits * 100 / Math.max(1, wasmResult.ttProbes)).toFixed(1) + '%' +
             ' cutoffRate=' + (wasmResult.ttCutoffs * 100 / Math.max(1, wasmResult.ttProbes)).toFixed(1) + '%');
         }
+        // Engine self-check: the move was legal, but something disagreed
+        if (wasmResult.warnings) {
+          wasmResult.warnings.forEach(function(w) {
+            console.warn('[WASM check] ' + w.code + ': ' + w.message, w);
+          });
+        }
         postMessage(wasmResult);
         return;
       }
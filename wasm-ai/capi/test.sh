@@ -0,0 +1,17 @@
+#!/bin/bash
+# Build the C API and run the C test program against it.
+# Run from wasm-ai/
+
+set -e
+
+cargo build --features capi
+cbindgen --config cbindgen.toml --output target/dominos_ai.h src/capi.rs
+if ! diff -q include/dominos_ai.h target/dominos_ai.h >/dev/null; then
+    echo "include/dominos_ai.h is out of date; regenerate it with:"
+    echo "  cbindgen --config cbindgen.toml --output include/dominos_ai.h src/capi.rs"
+    exit 1
+fi
+
+cc -std=c99 -Wall -Wextra -Werror -I include capi/test_capi.c \
+    -L target/debug -ldominos_ai -Wl,-rpath,"$PWD/target/debug" -o target/test_capi
+./target/test_capi
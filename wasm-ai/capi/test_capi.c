@@ -0,0 +1,128 @@
+/* Exercises the C API against the cdylib. Run capi/test.sh from wasm-ai/. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "dominos_ai.h"
+
+static int failures = 0;
+
+/* AI [6-6] [0-1] vs human [5-6], AI to lead: leading [0-1] locks for -23,
+ * [6-6] lets the human out for -1 */
+#define SIX_OR_LOCK "56/0166 - a 0 -"
+
+#define CHECK(cond)                                                        \
+    do {                                                                   \
+        if (!(cond)) {                                                     \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,         \
+                    __LINE__, #cond);                                      \
+            failures++;                                                    \
+        }                                                                  \
+    } while (0)
+
+static void test_choose_from_tiles(void) {
+    DominosEngine *engine = dominos_engine_new();
+    DominosTile mine[] = {{6, 6}, {1, 0}};
+    DominosTile opp[] = {{5, 6}};
+    DominosMove mv;
+
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_ERR_STATE);
+    CHECK(dominos_engine_set_position(engine, mine, 2, opp, 1, -1, -1) == DOMINOS_OK);
+    CHECK(dominos_engine_set_budget(engine, 200) == DOMINOS_OK);
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_OK);
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_OK);
+    CHECK(mv.low == 6 && mv.high == 6 && mv.score == -1);
+
+    /* Nothing on the board matches [1-2]: a pass */
+    DominosTile stuck[] = {{1, 2}};
+    CHECK(dominos_engine_set_position(engine, stuck, 1, opp, 1, 6, 6) == DOMINOS_OK);
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_OK);
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_OK);
+    CHECK(mv.end == DOMINOS_PASS);
+
+    dominos_engine_free(engine);
+}
+
+static void test_errors(void) {
+    DominosEngine *engine = dominos_engine_new();
+    DominosTile bad[] = {{7, 1}};
+
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_ERR_STATE);
+    CHECK(dominos_engine_set_position(engine, bad, 1, NULL, 0, -1, -1) == DOMINOS_ERR_INVALID);
+    CHECK(strcmp(dominos_engine_last_error(engine), "bad tile [7-1]") == 0);
+    CHECK(dominos_engine_set_level(engine, 11) == DOMINOS_ERR_INVALID);
+    CHECK(dominos_engine_set_rules(engine, "ghost13=maybe") == DOMINOS_ERR_INVALID);
+    CHECK(dominos_engine_set_notation(engine, "not a position") == DOMINOS_ERR_INVALID);
+    CHECK(dominos_engine_set_budget(NULL, 100) == DOMINOS_ERR_NULL);
+
+    dominos_engine_free(engine);
+    dominos_engine_free(NULL);
+}
+
+static void test_notation_and_json(void) {
+    DominosEngine *engine = dominos_engine_new();
+    DominosMove mv;
+
+    /* SIX_OR_LOCK with the human to move */
+    CHECK(dominos_engine_set_notation(engine, "0166/56 - h 0 -") == DOMINOS_OK);
+    CHECK(dominos_engine_set_level(engine, 10) == DOMINOS_OK);
+    CHECK(dominos_engine_set_budget(engine, 200) == DOMINOS_OK);
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_OK);
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_OK);
+    CHECK(mv.low == 6 && mv.high == 6);
+    dominos_engine_free(engine);
+
+    char *out = dominos_choose_move_json(SIX_OR_LOCK);
+    CHECK(out != NULL && strncmp(out, "{\"tileId\":\"6-6\"", 15) == 0);
+    dominos_string_free(out);
+    CHECK(dominos_choose_move_json(NULL) == NULL);
+
+    /* A pip out of range is reported, not a crash */
+    out = dominos_choose_move_json(
+        "{\"aiTiles\":[{\"low\":9,\"high\":1}],\"humanTiles\":[{\"low\":5,\"high\":6}],"
+        "\"boardEmpty\":true}");
+    CHECK(out != NULL && strstr(out, "\"badInput\"") != NULL);
+    dominos_string_free(out);
+}
+
+static void test_rules_and_bad_notation(void) {
+    DominosEngine *engine = dominos_engine_new();
+    DominosMove mv;
+
+    /* Ghost 13 charges a stranded [0-0] as 13: get rid of it first */
+    CHECK(dominos_engine_set_notation(engine,
+        "12/00011144 0225511665533666644333322226600555544003311442 a 0 h24@02,a@04 0-0") == DOMINOS_OK);
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_OK);
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_OK);
+    CHECK(mv.low == 0 && mv.high == 0 && mv.score == -11);
+    CHECK(dominos_engine_set_rules(engine, "ghost13=off") == DOMINOS_OK);
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_OK);
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_OK);
+    CHECK(mv.low == 0 && mv.high == 1 && mv.score == -10);
+    CHECK(dominos_engine_set_rules(engine, NULL) == DOMINOS_ERR_NULL);
+
+    /* A bad position leaves the last good one in place */
+    CHECK(dominos_engine_set_notation(engine, "01/01 - a 0 -") == DOMINOS_ERR_INVALID);
+    CHECK(strcmp(dominos_engine_last_error(engine), "a tile is in both hands") == 0);
+    CHECK(dominos_engine_set_notation(engine, "01/66 - a 0") == DOMINOS_ERR_INVALID);
+    CHECK(strcmp(dominos_engine_last_error(engine), "expected 5 or 6 fields, got 4") == 0);
+    CHECK(dominos_engine_set_notation(engine, NULL) == DOMINOS_ERR_NULL);
+    CHECK(dominos_engine_choose_move(engine) == DOMINOS_OK);
+    CHECK(dominos_engine_result(engine, &mv) == DOMINOS_OK);
+    CHECK(mv.low == 0 && mv.high == 1);
+
+    dominos_engine_free(engine);
+}
+
+int main(void) {
+    test_choose_from_tiles();
+    test_errors();
+    test_notation_and_json();
+    test_rules_and_bad_notation();
+    if (failures) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return 1;
+    }
+    printf("capi: all checks passed\n");
+    return 0;
+}
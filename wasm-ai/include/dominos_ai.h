@@ -0,0 +1,148 @@
+/* dominos-ai C API. Ownership rules and error codes: see src/capi.rs */
+
+#ifndef DOMINOS_AI_H
+#define DOMINOS_AI_H
+
+/* Generated by cbindgen from src/capi.rs; do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define DOMINOS_OK 0
+
+// A required pointer was null
+#define DOMINOS_ERR_NULL -1
+
+// Bad tiles, board ends, notation or setting
+#define DOMINOS_ERR_INVALID -2
+
+// No position set, or the hand is already over
+#define DOMINOS_ERR_STATE -3
+
+// `end` of a pass in `DominosMove`
+#define DOMINOS_PASS -1
+
+// Opaque engine handle: a position, settings and the last result.
+typedef struct DominosEngine DominosEngine;
+
+// A tile as two pips, 0-6, in either order.
+typedef struct DominosTile {
+  uint8_t low;
+  uint8_t high;
+} DominosTile;
+
+// The engine's move and its search result.
+typedef struct DominosMove {
+  // Tile to play, `low <= high`; both 0 for a pass
+  uint8_t low;
+  uint8_t high;
+  // 0 = left, 1 = right, `DOMINOS_PASS`; 0 on the empty board
+  int8_t end;
+  // Hand points for the side to move (match equity with
+  // `dominos_engine_set_objective(engine, 1)`)
+  int32_t score;
+  int32_t depth;
+  uint32_t nodes;
+} DominosMove;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// New engine with no position, a 1000 ms budget, level 10, points
+// objective and standard rules. Free it with `dominos_engine_free`.
+struct DominosEngine *dominos_engine_new(void);
+
+// Release an engine from `dominos_engine_new`. Null is ignored.
+//
+// # Safety
+// `engine` must be null or a live engine; it is invalid afterwards.
+void dominos_engine_free(struct DominosEngine *engine);
+
+// Set the position with the engine's side to move: its own tiles, the
+// opponent's, and the board ends (`left < 0` for the empty board). Clears
+// the puppeteer history and match score.
+//
+// # Safety
+// `engine` must be a live engine; each tile pointer must point at `count`
+// tiles (it may be null when the count is 0).
+int32_t dominos_engine_set_position(struct DominosEngine *engine,
+                                    const struct DominosTile *my_tiles,
+                                    size_t my_count,
+                                    const struct DominosTile *opp_tiles,
+                                    size_t opp_count,
+                                    int8_t left,
+                                    int8_t right);
+
+// Set the position from a one-line `notation` string; either side may be
+// to move.
+//
+// # Safety
+// `engine` must be a live engine and `text` a NUL-terminated string.
+int32_t dominos_engine_set_notation(struct DominosEngine *engine, const char *text);
+
+// Search time per move in ms (default 1000).
+//
+// # Safety
+// `engine` must be a live engine.
+int32_t dominos_engine_set_budget(struct DominosEngine *engine, uint32_t budget_ms);
+
+// Strength 1-10 (default 10, the full engine).
+//
+// # Safety
+// `engine` must be a live engine.
+int32_t dominos_engine_set_level(struct DominosEngine *engine, uint8_t level);
+
+// 0 plays for hand points (default), 1 for match-win probability from
+// the notation's match score.
+//
+// # Safety
+// `engine` must be a live engine.
+int32_t dominos_engine_set_objective(struct DominosEngine *engine, int32_t equity);
+
+// Rules in DGN form, e.g. `ghost13=off,target=150`; unnamed rules keep
+// their standard values.
+//
+// # Safety
+// `engine` must be a live engine and `rules` a NUL-terminated string.
+int32_t dominos_engine_set_rules(struct DominosEngine *engine, const char *rules);
+
+// Search the position for the side to move; blocks for up to the budget.
+// Read the move with `dominos_engine_result`.
+//
+// # Safety
+// `engine` must be a live engine.
+int32_t dominos_engine_choose_move(struct DominosEngine *engine);
+
+// Copy the last chosen move into `*out`.
+//
+// # Safety
+// `engine` must be a live engine and `out` writable.
+int32_t dominos_engine_result(struct DominosEngine *engine, struct DominosMove *out);
+
+// Message of the last failed call on `engine` ("" if none). Owned by the
+// engine and valid until its next call.
+//
+// # Safety
+// `engine` must be null or a live engine.
+const char *dominos_engine_last_error(const struct DominosEngine *engine);
+
+// `wasm_choose_move` for C: `SearchInput` JSON (or notation) in,
+// `SearchOutput` JSON out. The caller owns the result and releases it with
+// `dominos_string_free`; null if `input` is null or not UTF-8.
+//
+// # Safety
+// `input` must be null or a NUL-terminated string.
+char *dominos_choose_move_json(const char *input);
+
+// Release a string returned by `dominos_choose_move_json`. Null is ignored.
+//
+// # Safety
+// `s` must be null or a string from this library not yet freed.
+void dominos_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DOMINOS_AI_H */
@@ -0,0 +1,309 @@
+//! Post-game analysis: replay a finished hand and re-solve every decision
+//! point for both players. Port of analyze-game.js on top of the Rust search.
+//!
+//! Each decision is searched from the mover's perspective (hands and
+//! puppeteer history swapped when the human moves). The played move, when it
+//! differs from the engine's choice, is scored by searching the position it
+//! leads to, so "points lost" compares two exact values rather than a PVS
+//! bound.
+
+use crate::equity::Objective;
+use crate::game::HandState;
+use crate::lookup::{NUM_TILES, TILE_HIGH, TILE_LOW};
+use crate::rules::RuleSet;
+use crate::search::SearchLimits;
+
+pub use crate::game::{AI, HUMAN};
+
+/// One entry of a game's move list. `tile == -1` is a pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameMove {
+    pub who: i8,
+    pub tile: i8,
+    /// 0 = left, 1 = right (ignored on the empty board)
+    pub end: i8,
+}
+
+impl GameMove {
+    pub fn is_pass(&self) -> bool {
+        self.tile < 0
+    }
+}
+
+/// A saved hand: the deal plus every move in order.
+pub struct GameRecord {
+    pub leader: i8,
+    pub human_hand: i32,
+    pub ai_hand: i32,
+    pub moves: Vec<GameMove>,
+}
+
+/// Verdict on a single move.
+#[derive(Clone, Debug)]
+pub struct MoveReview {
+    pub index: usize,
+    pub played: GameMove,
+    /// Number of legal moves (0 = pass, 1 = forced)
+    pub legal: usize,
+    pub best_tile: i8,
+    pub best_end: i8,
+    /// Hand result of the best move, from the mover's perspective
+    pub best_score: i32,
+    pub played_score: i32,
+    /// `best_score - played_score`, never negative
+    pub loss: i32,
+    pub depth: i32,
+    /// Both searches reached the end of the hand
+    pub solved: bool,
+}
+
+impl MoveReview {
+    /// A real choice (two or more legal moves).
+    pub fn is_decision(&self) -> bool {
+        self.legal >= 2
+    }
+}
+
+/// Per-player totals over decision points.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerSummary {
+    pub decisions: u32,
+    pub perfect: u32,
+    pub points_lost: i32,
+}
+
+impl PlayerSummary {
+    /// Share of decisions that matched the engine's value, in percent.
+    pub fn accuracy(&self) -> f64 {
+        if self.decisions == 0 {
+            100.0
+        } else {
+            100.0 * self.perfect as f64 / self.decisions as f64
+        }
+    }
+}
+
+pub struct GameAnalysis {
+    pub moves: Vec<MoveReview>,
+    pub human: PlayerSummary,
+    pub ai: PlayerSummary,
+}
+
+/// Grade a move by points lost (same bands as analyze-game.js).
+pub fn grade(loss: i32) -> &'static str {
+    if loss <= 0 {
+        "Perfect"
+    } else if loss <= 4 {
+        "Good"
+    } else if loss <= 14 {
+        "Inaccuracy"
+    } else if loss <= 29 {
+        "Mistake"
+    } else {
+        "Blunder"
+    }
+}
+
+/// Replay `game` and review every move. Fails on an inconsistent record
+/// (wrong turn, tile not in hand, illegal placement or pass).
+pub fn analyze_game(game: &GameRecord, time_budget: f64, rules: &RuleSet) -> Result<GameAnalysis, String> {
+    if game.human_hand & game.ai_hand != 0 {
+        return Err("hands share a tile".to_string());
+    }
+    let mut pos = HandState::new(game.human_hand, game.ai_hand, game.leader);
+    let mut reviews = Vec::with_capacity(game.moves.len());
+    let mut summary = [PlayerSummary::default(); 2];
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+
+    for (i, &mv) in game.moves.iter().enumerate() {
+        // JS records an immediate lock without passes; older records may
+        // still carry the pass-pass, which is accepted below.
+        let to_move = pos.to_move;
+        if !mv.is_pass() && pos.result_for(to_move, rules).is_some() {
+            return Err(format!("move {}: hand is already over", i + 1));
+        }
+        if mv.who != to_move {
+            return Err(format!("move {}: expected {} to move", i + 1, side_name(to_move)));
+        }
+        let n = pos.legal_moves(&mut buf);
+
+        if mv.is_pass() {
+            if n > 0 {
+                return Err(format!("move {}: pass with {} legal moves", i + 1, n));
+            }
+            reviews.push(MoveReview {
+                index: i, played: mv, legal: 0,
+                best_tile: -1, best_end: -1, best_score: 0, played_score: 0,
+                loss: 0, depth: 0, solved: true,
+            });
+            pos.pass();
+            continue;
+        }
+
+        let Some((tile, end)) = pos.find_legal(mv.tile, mv.end) else {
+            return Err(format!("move {}: {}-{} is not legal for {}", i + 1,
+                               TILE_LOW[mv.tile.clamp(0, 27) as usize],
+                               TILE_HIGH[mv.tile.clamp(0, 27) as usize],
+                               side_name(to_move)));
+        };
+
+        let mut review = MoveReview {
+            index: i, played: GameMove { who: to_move, tile, end }, legal: n,
+            best_tile: tile, best_end: end, best_score: 0, played_score: 0,
+            loss: 0, depth: 0, solved: true,
+        };
+
+        if n >= 2 {
+            let best = pos.search_limited(to_move, time_budget, rules, Objective::Points, 0, &SearchLimits::SOLVE);
+            review.best_tile = best.best_tile_idx;
+            review.best_end = best.best_end;
+            review.best_score = best.best_score;
+            review.depth = best.depth;
+            review.solved = best.depth >= pos.tiles_left();
+
+            let same = best.best_tile_idx == tile
+                && (best.best_end == end || pos.left == 7 || pos.left == pos.right);
+            if same {
+                review.played_score = best.best_score;
+            } else {
+                let mut after = pos;
+                after.place(tile, end);
+                let (sc, solved) = after.value_after_move(to_move, time_budget, rules, 0, &SearchLimits::NONE);
+                review.played_score = sc;
+                review.solved &= solved;
+            }
+            review.loss = (review.best_score - review.played_score).max(0);
+
+            let s = &mut summary[to_move as usize];
+            s.decisions += 1;
+            s.points_lost += review.loss;
+            if review.loss == 0 {
+                s.perfect += 1;
+            }
+        }
+
+        reviews.push(review);
+        pos.place(tile, end);
+    }
+
+    Ok(GameAnalysis { moves: reviews, human: summary[0], ai: summary[1] })
+}
+
+pub fn side_name(who: i8) -> &'static str {
+    if who == AI { "ai" } else { "human" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{dealt, six_or_lock};
+    use crate::lookup::tile_id_to_index;
+
+    fn six_or_lock_game(moves: Vec<GameMove>) -> GameRecord {
+        let [human_hand, ai_hand] = six_or_lock().hands;
+        GameRecord { leader: AI, human_hand, ai_hand, moves }
+    }
+
+    fn play(who: i8, lo: i8, hi: i8, end: i8) -> GameMove {
+        GameMove { who, tile: tile_id_to_index(lo, hi) as i8, end }
+    }
+
+    fn pass(who: i8) -> GameMove {
+        GameMove { who, tile: -1, end: 0 }
+    }
+
+    #[test]
+    fn test_blocking_lead_is_a_mistake() {
+        let game = six_or_lock_game(vec![play(AI, 0, 1, 0)]);
+        let a = analyze_game(&game, 1000.0, &RuleSet::STANDARD).unwrap();
+        let first = &a.moves[0];
+        assert_eq!(first.legal, 2);
+        assert_eq!(first.best_tile, tile_id_to_index(6, 6) as i8);
+        assert_eq!(first.best_score, -1);
+        assert_eq!(first.played_score, -23);
+        assert_eq!(first.loss, 22);
+        assert_eq!(grade(first.loss), "Mistake");
+        assert!(first.solved);
+        assert_eq!(a.ai.decisions, 1);
+        assert_eq!(a.ai.accuracy(), 0.0);
+        assert_eq!(a.human.decisions, 0);
+        assert_eq!(a.human.accuracy(), 100.0);
+
+        // A record that spells out the pass-pass is reviewed the same way
+        let mut with_passes = game;
+        with_passes.moves.extend([pass(HUMAN), pass(AI)]);
+        let b = analyze_game(&with_passes, 1000.0, &RuleSet::STANDARD).unwrap();
+        assert_eq!(b.moves.len(), 3);
+        assert_eq!(b.moves[0].loss, 22);
+    }
+
+    #[test]
+    fn test_opening_lead_is_searched() {
+        // The book's suit-profile table covers this leader but has no solve
+        // of the deal; its average must not stand in for the best move
+        let [human, ai] = crate::deal::deal(1_000_000);
+        assert!(crate::book::probe_hand(ai).is_some() && crate::book::probe(ai, human).is_none());
+        let lead = (0..NUM_TILES as i8).find(|&t| ai & 1 << t != 0).unwrap();
+        let game = GameRecord { leader: AI, ai_hand: ai, human_hand: human, moves: vec![GameMove { who: AI, tile: lead, end: 0 }] };
+        let a = analyze_game(&game, 200.0, &RuleSet::STANDARD).unwrap();
+        assert!(a.moves[0].depth > 0);
+    }
+
+    #[test]
+    fn test_best_line_is_perfect() {
+        let game = six_or_lock_game(vec![play(AI, 6, 6, 0), play(HUMAN, 5, 6, 1)]);
+        let a = analyze_game(&game, 1000.0, &RuleSet::STANDARD).unwrap();
+        assert_eq!(a.moves[0].loss, 0);
+        assert_eq!(a.moves[1].legal, 1);
+        assert_eq!(a.ai.accuracy(), 100.0);
+        assert_eq!(a.ai.points_lost, 0);
+    }
+
+    #[test]
+    fn test_review_of_a_dealt_hand() {
+        // Deal 6 played out with the first listed move each turn
+        let start = dealt(6, 0).state;
+        let mut moves = Vec::new();
+        let mut pos = start;
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        while pos.hand_end().is_none() {
+            let who = pos.to_move;
+            if pos.legal_moves(&mut buf) == 0 {
+                moves.push(pass(who));
+                pos.pass();
+            } else {
+                moves.push(GameMove { who, tile: buf[0].0, end: buf[0].1 });
+                pos.place(buf[0].0, buf[0].1);
+            }
+        }
+        let game = GameRecord { leader: start.to_move, human_hand: start.hands[0], ai_hand: start.hands[1], moves };
+        let a = analyze_game(&game, 50.0, &RuleSet::STANDARD).unwrap();
+        assert_eq!(a.moves.len(), game.moves.len());
+        for who in [HUMAN, AI] {
+            let decisions = a.moves.iter().filter(|r| r.played.who == who && r.is_decision()).count();
+            let s = if who == AI { a.ai } else { a.human };
+            assert_eq!(s.decisions as usize, decisions);
+            assert_eq!(s.points_lost, a.moves.iter().filter(|r| r.played.who == who).map(|r| r.loss).sum::<i32>());
+        }
+        assert!(a.moves.iter().filter(|r| r.is_decision()).count() > 3);
+        for r in a.moves.iter().filter(|r| r.is_decision() && r.solved) {
+            assert!(r.played_score <= r.best_score, "move {}", r.index);
+        }
+        // The endgame is small enough to solve
+        assert!(a.moves.iter().rev().find(|r| r.is_decision()).unwrap().solved);
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_record() {
+        let base = || six_or_lock_game(vec![]);
+        let mut g = base();
+        g.moves = vec![play(HUMAN, 5, 6, 0)];
+        assert!(analyze_game(&g, 100.0, &RuleSet::STANDARD).is_err());
+        let mut g = base();
+        g.moves = vec![play(AI, 5, 6, 0)];
+        assert!(analyze_game(&g, 100.0, &RuleSet::STANDARD).is_err());
+        let mut g = base();
+        g.moves = vec![play(AI, 6, 6, 0), pass(HUMAN)];
+        assert!(analyze_game(&g, 100.0, &RuleSet::STANDARD).is_err());
+    }
+}
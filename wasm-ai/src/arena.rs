@@ -0,0 +1,465 @@
+//! Engine-vs-engine arena for the Rust engine (replaces compare-engines.js /
+//! tune-weights.js matches against the JS copies).
+//!
+//! Games are played in pairs: the same seeded deal (or deal sequence, for
+//! full matches) is played twice with the engines swapping seats, which
+//! cancels most of the luck of the deal. Statistics are computed over pairs:
+//! win rate, point differential with a 95% interval, an Elo estimate and a
+//! sequential probability ratio test (normal approximation on pair scores).
+
+use crate::book;
+use crate::deal;
+use crate::equity::Objective;
+use crate::eval::{self, EvalParams};
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::{NUM_TILES, TILE_PIPS};
+use crate::rules::RuleSet;
+use crate::skill::{self, SkillLevel};
+
+/// Hands per match before it is abandoned as a draw (zero-point hands
+/// otherwise never end it).
+const MAX_MATCH_HANDS: u32 = 200;
+
+/// How an engine picks its move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineKind {
+    /// `choose_move`
+    Search,
+    /// Heaviest playable tile, no search (baseline)
+    Greedy,
+}
+
+/// One side of the arena.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineConfig {
+    pub name: String,
+    pub kind: EngineKind,
+    /// Search time per move in ms
+    pub time_budget: f64,
+    /// Search for match-win probability instead of hand points (match play)
+    pub match_equity: bool,
+    /// Evaluation parameters for this side (`None` = built-in defaults)
+    pub params: Option<EvalParams>,
+    /// Strength level 1–10 (`skill`); 10 is the full engine
+    pub level: u8,
+    /// Consult the opening book for first leads
+    pub book: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            name: "search".to_string(),
+            kind: EngineKind::Search,
+            time_budget: 100.0,
+            match_equity: false,
+            params: None,
+            level: skill::MAX_LEVEL,
+            book: true,
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Parse `key=value,...` with keys `name`, `engine` (search|greedy),
+    /// `budget` (ms), `objective` (points|equity), `params` (path of an
+    /// eval params file), `level` (1–10) and `book` (on|off). Missing keys
+    /// keep the defaults.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut cfg = EngineConfig::default();
+        let mut named = false;
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = item.split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", item))?;
+            match key {
+                "name" => {
+                    cfg.name = value.to_string();
+                    named = true;
+                }
+                "engine" => cfg.kind = match value {
+                    "search" => EngineKind::Search,
+                    "greedy" => EngineKind::Greedy,
+                    _ => return Err(format!("unknown engine '{}'", value)),
+                },
+                "budget" => cfg.time_budget = value.parse()
+                    .map_err(|_| format!("bad budget '{}'", value))?,
+                "objective" => cfg.match_equity = match value {
+                    "points" => false,
+                    "equity" => true,
+                    _ => return Err(format!("unknown objective '{}'", value)),
+                },
+                "level" => cfg.level = match value.parse() {
+                    Ok(l) if (skill::MIN_LEVEL..=skill::MAX_LEVEL).contains(&l) => l,
+                    _ => return Err(format!("bad level '{}'", value)),
+                },
+                "book" => cfg.book = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("bad book setting '{}'", value)),
+                },
+                "params" => {
+                    let text = std::fs::read_to_string(value)
+                        .map_err(|e| format!("{}: {}", value, e))?;
+                    cfg.params = Some(EvalParams::parse(&text).map_err(|e| format!("{}: {}", value, e))?);
+                }
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+        if !named {
+            cfg.name = spec.to_string();
+        }
+        Ok(cfg)
+    }
+
+    /// Pick a move for the side to move in `pos`. `my_score`/`opp_score`
+    /// are the running match scores (0 for single hands).
+    pub fn choose(&self, pos: &HandState, rules: &RuleSet, my_score: i32, opp_score: i32) -> (i8, i8) {
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        let n = pos.legal_moves(&mut buf);
+        debug_assert!(n > 0);
+        if n == 1 || self.kind == EngineKind::Greedy {
+            return *buf[..n].iter().max_by_key(|&&(t, _)| TILE_PIPS[t as usize]).unwrap();
+        }
+        let objective = if self.match_equity {
+            Objective::MatchEquity { ai_score: my_score, human_score: opp_score }
+        } else {
+            Objective::Points
+        };
+        eval::set_params(self.params.as_ref().unwrap_or(&EvalParams::DEFAULT));
+        book::set_enabled(self.book);
+        // Seeded from the position, so replays of a pair match
+        let seed = (pos.hands[0] as u32) ^ (pos.hands[1] as u32).rotate_left(11)
+            ^ ((pos.left as u32) << 28) ^ ((pos.right as u32) << 24);
+        let r = skill::choose_move_at_level(pos, &SkillLevel::new(self.level), self.time_budget,
+                                            rules, objective, my_score - opp_score, seed);
+        pos.find_legal(r.best_tile_idx, r.best_end).unwrap_or(buf[0])
+    }
+}
+
+/// Single hands or full matches to `rules.target_score`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaFormat {
+    Hand,
+    Match,
+}
+
+/// SPRT hypotheses (Elo) and error rates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SprtBounds {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for SprtBounds {
+    fn default() -> Self {
+        SprtBounds { elo0: 0.0, elo1: 10.0, alpha: 0.05, beta: 0.05 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtVerdict {
+    /// Engine A is at least `elo1` stronger
+    AcceptH1,
+    /// Engine A is not more than `elo0` stronger
+    AcceptH0,
+    Continue,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArenaOptions {
+    /// Number of deal pairs (two games each)
+    pub pairs: u32,
+    pub seed: u32,
+    pub format: ArenaFormat,
+    pub rules: RuleSet,
+    pub sprt: SprtBounds,
+    /// Stop as soon as the SPRT reaches a verdict
+    pub stop_on_sprt: bool,
+}
+
+impl Default for ArenaOptions {
+    fn default() -> Self {
+        ArenaOptions {
+            pairs: 100,
+            seed: 1,
+            format: ArenaFormat::Hand,
+            rules: RuleSet::STANDARD,
+            sprt: SprtBounds::default(),
+            stop_on_sprt: false,
+        }
+    }
+}
+
+/// One game from engine A's point of view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameResult {
+    /// 1 win, 0.5 draw, 0 loss
+    pub score: f64,
+    /// Hand points (single hands) or final match margin
+    pub points: i32,
+}
+
+/// Play one hand from `hands` ([human seat, ai seat]) with `a` in `a_seat`.
+/// Returns the hand result for A. `scores` are the running match scores
+/// indexed by seat.
+pub fn play_hand(
+    a: &EngineConfig, b: &EngineConfig, hands: [i32; 2], a_seat: i8, leader: i8,
+    rules: &RuleSet, scores: [i32; 2],
+) -> i32 {
+    let mut pos = HandState::new(hands[HUMAN as usize], hands[AI as usize], leader);
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    loop {
+        if let Some(result) = pos.result_for(a_seat, rules) {
+            return result;
+        }
+        if pos.legal_moves(&mut buf) == 0 {
+            pos.pass();
+            continue;
+        }
+        let me = pos.to_move;
+        let engine = if me == a_seat { a } else { b };
+        let (tile, end) = engine.choose(&pos, rules, scores[me as usize], scores[1 - me as usize]);
+        pos.place(tile, end);
+    }
+}
+
+/// Deal for hand `hand` of pair `pair`.
+fn pair_deal(seed: u32, pair: u32, hand: u32) -> [i32; 2] {
+    deal::deal(seed.wrapping_add(pair.wrapping_mul(0x0001_0000)).wrapping_add(hand))
+}
+
+/// Play game `game_idx` (0 or 1) of pair `pair`: A sits in the AI seat in
+/// game 0 and the human seat in game 1. The first leader seat alternates by
+/// pair; in matches the previous hand's winner leads the next one.
+pub fn play_game(a: &EngineConfig, b: &EngineConfig, opts: &ArenaOptions, pair: u32, game_idx: u32) -> GameResult {
+    let a_seat = if game_idx == 0 { AI } else { HUMAN };
+    let mut leader = if pair & 1 == 0 { AI } else { HUMAN };
+    let rules = &opts.rules;
+
+    if opts.format == ArenaFormat::Hand {
+        let pts = play_hand(a, b, pair_deal(opts.seed, pair, 0), a_seat, leader, rules, [0, 0]);
+        return GameResult { score: outcome(pts), points: pts };
+    }
+
+    let mut scores = [0i32; 2]; // by seat
+    for hand in 0..MAX_MATCH_HANDS {
+        let pts = play_hand(a, b, pair_deal(opts.seed, pair, hand), a_seat, leader, rules, scores);
+        if pts > 0 {
+            scores[a_seat as usize] += pts;
+            leader = a_seat;
+        } else if pts < 0 {
+            scores[1 - a_seat as usize] -= pts;
+            leader = 1 - a_seat;
+        }
+        if scores.iter().any(|&s| s >= rules.target_score) {
+            break;
+        }
+    }
+    let margin = scores[a_seat as usize] - scores[1 - a_seat as usize];
+    let done = scores.iter().any(|&s| s >= rules.target_score);
+    GameResult { score: if done { outcome(margin) } else { 0.5 }, points: margin }
+}
+
+fn outcome(points: i32) -> f64 {
+    if points > 0 { 1.0 } else if points < 0 { 0.0 } else { 0.5 }
+}
+
+/// Accumulated results, one entry per deal pair.
+#[derive(Clone, Debug, Default)]
+pub struct ArenaStats {
+    pub pairs: Vec<[GameResult; 2]>,
+}
+
+impl ArenaStats {
+    pub fn games(&self) -> usize {
+        self.pairs.len() * 2
+    }
+
+    /// (wins, draws, losses) for engine A.
+    pub fn wdl(&self) -> (u32, u32, u32) {
+        let mut w = (0, 0, 0);
+        for g in self.pairs.iter().flatten() {
+            if g.score == 1.0 {
+                w.0 += 1;
+            } else if g.score == 0.5 {
+                w.1 += 1;
+            } else {
+                w.2 += 1;
+            }
+        }
+        w
+    }
+
+    /// Mean pair score (0..1) and its sample variance.
+    fn pair_scores(&self) -> (f64, f64) {
+        mean_var(self.pairs.iter().map(|p| (p[0].score + p[1].score) / 2.0))
+    }
+
+    /// Score rate of A, draws counting half.
+    pub fn score(&self) -> f64 {
+        self.pair_scores().0
+    }
+
+    /// Mean points per game for A and the 95% half-width.
+    pub fn points(&self) -> (f64, f64) {
+        let (m, v) = mean_var(self.pairs.iter().map(|p| (p[0].points + p[1].points) as f64 / 2.0));
+        (m, 1.96 * (v / self.pairs.len().max(1) as f64).sqrt())
+    }
+
+    /// Elo difference of A over B with a 95% interval (low, high).
+    pub fn elo(&self) -> (f64, f64, f64) {
+        let (s, v) = self.pair_scores();
+        let se = (v / self.pairs.len().max(1) as f64).sqrt();
+        (elo_from_score(s), elo_from_score(s - 1.96 * se), elo_from_score(s + 1.96 * se))
+    }
+
+    /// Log-likelihood ratio of H1 (`elo1`) against H0 (`elo0`).
+    pub fn llr(&self, sprt: &SprtBounds) -> f64 {
+        let (s, v) = self.pair_scores();
+        if self.pairs.len() < 2 || v <= 0.0 {
+            return 0.0;
+        }
+        let s0 = score_from_elo(sprt.elo0);
+        let s1 = score_from_elo(sprt.elo1);
+        self.pairs.len() as f64 * (s1 - s0) * (2.0 * s - s0 - s1) / (2.0 * v)
+    }
+
+    pub fn verdict(&self, sprt: &SprtBounds) -> SprtVerdict {
+        let llr = self.llr(sprt);
+        let (lower, upper) = sprt_bounds(sprt);
+        if llr >= upper {
+            SprtVerdict::AcceptH1
+        } else if llr <= lower {
+            SprtVerdict::AcceptH0
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+/// (lower, upper) LLR bounds for the given error rates.
+pub fn sprt_bounds(sprt: &SprtBounds) -> (f64, f64) {
+    ((sprt.beta / (1.0 - sprt.alpha)).ln(), ((1.0 - sprt.beta) / sprt.alpha).ln())
+}
+
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Elo difference for a score rate, clamped away from 0 and 1.
+pub fn elo_from_score(score: f64) -> f64 {
+    let s = score.clamp(1e-3, 1.0 - 1e-3);
+    -400.0 * (1.0 / s - 1.0).log10()
+}
+
+fn mean_var(xs: impl Iterator<Item = f64>) -> (f64, f64) {
+    let v: Vec<f64> = xs.collect();
+    if v.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = v.len() as f64;
+    let mean = v.iter().sum::<f64>() / n;
+    let var = if v.len() > 1 {
+        v.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0)
+    } else {
+        0.0
+    };
+    (mean, var)
+}
+
+/// Play up to `opts.pairs` pairs, calling `progress` after each pair.
+pub fn run(
+    a: &EngineConfig, b: &EngineConfig, opts: &ArenaOptions,
+    mut progress: impl FnMut(&ArenaStats),
+) -> ArenaStats {
+    let mut stats = ArenaStats::default();
+    for pair in 0..opts.pairs {
+        let g0 = play_game(a, b, opts, pair, 0);
+        let g1 = play_game(a, b, opts, pair, 1);
+        stats.pairs.push([g0, g1]);
+        progress(&stats);
+        if opts.stop_on_sprt && stats.verdict(&opts.sprt) != SprtVerdict::Continue {
+            break;
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn greedy() -> EngineConfig {
+        EngineConfig::parse("engine=greedy").unwrap()
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let c = EngineConfig::parse("name=eq,budget=250,objective=equity").unwrap();
+        assert_eq!(c.name, "eq");
+        assert_eq!(c.time_budget, 250.0);
+        assert!(c.match_equity);
+        assert_eq!(c.kind, EngineKind::Search);
+        assert_eq!(EngineConfig::parse("engine=greedy").unwrap().name, "engine=greedy");
+        assert!(EngineConfig::parse("budget=fast").is_err());
+        assert_eq!(EngineConfig::parse("level=3").unwrap().level, 3);
+        assert!(EngineConfig::parse("level=11").is_err());
+        assert!(!EngineConfig::parse("book=off").unwrap().book);
+        assert!(EngineConfig::parse("book=maybe").is_err());
+        assert!(EngineConfig::parse("depth=3").is_err());
+    }
+
+    #[test]
+    fn test_mirror_pairs_cancel() {
+        // Identical deterministic engines: each pair is a mirror image
+        let opts = ArenaOptions { pairs: 20, ..Default::default() };
+        let stats = run(&greedy(), &greedy(), &opts, |_| {});
+        assert_eq!(stats.games(), 40);
+        for p in &stats.pairs {
+            assert_eq!(p[0].points, -p[1].points);
+        }
+        assert_eq!(stats.points().0, 0.0);
+        assert!((stats.score() - 0.5).abs() < 1e-12);
+        assert_eq!(stats.elo().0, 0.0);
+    }
+
+    #[test]
+    fn test_match_format_reaches_target() {
+        let opts = ArenaOptions { pairs: 2, format: ArenaFormat::Match, ..Default::default() };
+        let stats = run(&greedy(), &greedy(), &opts, |_| {});
+        for g in stats.pairs.iter().flatten() {
+            assert!(g.score == 1.0 || g.score == 0.0);
+            assert_ne!(g.points, 0);
+        }
+    }
+
+    #[test]
+    fn test_elo_and_sprt() {
+        assert_eq!(elo_from_score(0.5), 0.0);
+        assert!((elo_from_score(0.75) - 190.85).abs() < 0.01);
+        assert!((score_from_elo(elo_from_score(0.6)) - 0.6).abs() < 1e-12);
+
+        let win = GameResult { score: 1.0, points: 10 };
+        let draw = GameResult { score: 0.5, points: 0 };
+        let loss = GameResult { score: 0.0, points: -10 };
+        let sprt = SprtBounds::default();
+
+        // A wins three pairs in four: H1 accepted
+        let strong = ArenaStats {
+            pairs: (0..400).map(|i| if i % 4 == 0 { [draw, loss] } else { [win, draw] }).collect(),
+        };
+        assert!((strong.elo().0 - 88.7).abs() < 0.1); // score 0.625
+        assert_eq!(strong.verdict(&sprt), SprtVerdict::AcceptH1);
+
+        // Dead even: H0 accepted
+        let even = ArenaStats {
+            pairs: (0..2000).map(|i| if i % 2 == 0 { [win, draw] } else { [loss, draw] }).collect(),
+        };
+        assert_eq!(even.verdict(&sprt), SprtVerdict::AcceptH0);
+
+        let few = ArenaStats { pairs: vec![[win, loss], [win, draw]] };
+        assert_eq!(few.verdict(&sprt), SprtVerdict::Continue);
+    }
+}
@@ -0,0 +1,100 @@
+//! Batch post-game report: native counterpart of analyze-game.js.
+//!
+//! Usage: analyze_game <game.json>... [--time-budget N] [--json]
+//!   --time-budget N   search time per position in ms (default 30000)
+//!   --json            print the raw `wasm_analyze_game` output, one line per game
+
+use serde_json::Value;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut files = Vec::new();
+    let mut time_budget = 30000.0;
+    let mut json = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--time-budget" => {
+                i += 1;
+                time_budget = args.get(i).and_then(|v| v.parse().ok()).unwrap_or_else(|| usage());
+            }
+            "--json" => json = true,
+            a if a.starts_with("--") => usage(),
+            a => files.push(a.to_string()),
+        }
+        i += 1;
+    }
+    if files.is_empty() {
+        usage();
+    }
+
+    let mut failed = false;
+    for file in &files {
+        let mut game: Value = match std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+        {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                failed = true;
+                continue;
+            }
+        };
+        game["timeBudget"] = time_budget.into();
+        let out = dominos_ai::wasm_analyze_game(&game.to_string());
+        if json {
+            println!("{}", out);
+            continue;
+        }
+        let report: Value = serde_json::from_str(&out).unwrap_or(Value::Null);
+        if let Some(err) = report["error"].as_str() {
+            eprintln!("{}: {}", file, err);
+            failed = true;
+            continue;
+        }
+        print_report(file, &report);
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn print_report(file: &str, report: &Value) {
+    println!("=== {} ===", file);
+    println!("Move  Player  Actual        Best          Loss  Grade");
+    println!("----  ------  ------------  ------------  ----  ----------");
+    let empty = Vec::new();
+    for m in report["moves"].as_array().unwrap_or(&empty) {
+        let played = if m["pass"].as_bool() == Some(true) {
+            "PASS".to_string()
+        } else {
+            format!("{} {}", m["tileId"].as_str().unwrap_or(""), m["end"].as_str().unwrap_or(""))
+        };
+        let forced = m["forced"].as_bool() == Some(true);
+        let best = if forced {
+            "-".to_string()
+        } else {
+            format!("{} {}", m["bestTileId"].as_str().unwrap_or(""), m["bestEnd"].as_str().unwrap_or(""))
+        };
+        println!("{:>4}  {:<6}  {:<12}  {:<12}  {:>4}  {}{}",
+                 m["moveNum"].as_u64().unwrap_or(0), m["player"].as_str().unwrap_or(""), played, best,
+                 if forced { "-".to_string() } else { m["loss"].to_string() },
+                 if forced { "forced" } else { m["grade"].as_str().unwrap_or("") },
+                 if !forced && m["solved"].as_bool() == Some(false) { " (not solved)" } else { "" });
+    }
+    println!();
+    for side in ["human", "ai"] {
+        let s = &report[side];
+        println!("{:<6} accuracy {:>5.1}%  ({} of {} decisions perfect, {} points lost)",
+                 side, s["accuracy"].as_f64().unwrap_or(0.0),
+                 s["perfect"], s["decisions"], s["pointsLost"]);
+    }
+    println!();
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: analyze_game <game.json>... [--time-budget N] [--json]");
+    eprintln!("Game JSON: {{\"leader\", \"humanTiles\", \"aiTiles\", \"moves\"}} as for analyze-game.js");
+    std::process::exit(2);
+}
@@ -0,0 +1,94 @@
+//! Self-play arena: two engine configs on paired seeded deals.
+//!
+//! Usage: arena [options]
+//!   --a SPEC / --b SPEC   engine configs, `key=value,...` with keys
+//!                         name, engine (search|greedy), budget (ms),
+//!                         objective (points|equity), params (eval params
+//!                         file), level (1-10), book (on|off);
+//!                         default budget=100
+//!   --pairs N             deal pairs to play (default 100)
+//!   --seed N              first deal seed (default 1)
+//!   --match               full matches to the target instead of single hands
+//!   --target N            match target (default 100)
+//!   --elo0 X --elo1 X     SPRT hypotheses (default 0 / 10)
+//!   --alpha X --beta X    SPRT error rates (default 0.05 / 0.05)
+//!   --sprt-stop           stop as soon as the SPRT concludes
+
+use dominos_ai::arena::{self, ArenaFormat, ArenaOptions, ArenaStats, EngineConfig, SprtVerdict};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut a = EngineConfig::default();
+    let mut b = EngineConfig::default();
+    let mut opts = ArenaOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        let mut value = || {
+            i += 1;
+            args.get(i).cloned().unwrap_or_else(|| usage(&format!("{} needs a value", flag)))
+        };
+        match flag {
+            "--a" => a = EngineConfig::parse(&value()).unwrap_or_else(|e| usage(&e)),
+            "--b" => b = EngineConfig::parse(&value()).unwrap_or_else(|e| usage(&e)),
+            "--pairs" => opts.pairs = num(&value()),
+            "--seed" => opts.seed = num(&value()),
+            "--target" => opts.rules.target_score = num(&value()),
+            "--elo0" => opts.sprt.elo0 = num(&value()),
+            "--elo1" => opts.sprt.elo1 = num(&value()),
+            "--alpha" => opts.sprt.alpha = num(&value()),
+            "--beta" => opts.sprt.beta = num(&value()),
+            "--match" => opts.format = ArenaFormat::Match,
+            "--sprt-stop" => opts.stop_on_sprt = true,
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+        i += 1;
+    }
+    if a.name == b.name {
+        a.name = format!("A:{}", a.name);
+        b.name = format!("B:{}", b.name);
+    }
+
+    println!("{} vs {} — {} pairs of {}, seed {}", a.name, b.name, opts.pairs,
+             if opts.format == ArenaFormat::Match { "matches" } else { "hands" }, opts.seed);
+    let stats = arena::run(&a, &b, &opts, |s| {
+        if s.pairs.len() % 10 == 0 {
+            let (w, d, l) = s.wdl();
+            eprintln!("  {:>5} games  +{} ={} -{}  elo {:+.1}  llr {:.2}",
+                      s.games(), w, d, l, s.elo().0, s.llr(&opts.sprt));
+        }
+    });
+    report(&a, &opts, &stats);
+}
+
+fn report(a: &EngineConfig, opts: &ArenaOptions, stats: &ArenaStats) {
+    let (w, d, l) = stats.wdl();
+    let (pts, pts_ci) = stats.points();
+    let (elo, elo_lo, elo_hi) = stats.elo();
+    let (lower, upper) = arena::sprt_bounds(&opts.sprt);
+    println!();
+    println!("Games      {} ({} pairs)", stats.games(), stats.pairs.len());
+    println!("{:<10} +{} ={} -{}  score {:.1}%", a.name, w, d, l, 100.0 * stats.score());
+    println!("Points     {:+.2} ± {:.2} per game for {}", pts, pts_ci, a.name);
+    println!("Elo        {:+.1} [{:+.1}, {:+.1}]", elo, elo_lo, elo_hi);
+    println!("SPRT       elo0={} elo1={} α={} β={}  llr {:.2} [{:.2}, {:.2}]  → {}",
+             opts.sprt.elo0, opts.sprt.elo1, opts.sprt.alpha, opts.sprt.beta,
+             stats.llr(&opts.sprt), lower, upper,
+             match stats.verdict(&opts.sprt) {
+                 SprtVerdict::AcceptH1 => format!("H1 accepted ({} is stronger)", a.name),
+                 SprtVerdict::AcceptH0 => format!("H0 accepted ({} is not stronger)", a.name),
+                 SprtVerdict::Continue => "inconclusive".to_string(),
+             });
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("arena: {}", msg);
+    eprintln!("Usage: arena [--a SPEC] [--b SPEC] [--pairs N] [--seed N] [--match] [--target N]");
+    eprintln!("             [--elo0 X] [--elo1 X] [--alpha X] [--beta X] [--sprt-stop]");
+    eprintln!("SPEC: key=value,... with name, engine (search|greedy), budget (ms), objective (points|equity)");
+    std::process::exit(2);
+}
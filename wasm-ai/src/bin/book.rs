@@ -0,0 +1,196 @@
+//! Opening-book builder.
+//!
+//! Usage:
+//!   book build [--from N] [--count N] [--budget MS] [--merge FILE] --out FILE
+//!       Solve seeded deals (both leaders) into the deal table; unsolved
+//!       leads are skipped. --merge keeps the entries of an existing book.
+//!   book hands [--from N] [--count N] [--nodes N] [--min-votes N] [--merge FILE] --out FILE
+//!       Search seeded deals (both leaders, node-capped, so the output is
+//!       the same on any machine) and keep, per leader suit profile, the
+//!       lead a majority of at least --min-votes samples chose. Replaces the
+//!       hand table; --merge keeps the deal table of an existing book.
+//!   book dump [FILE]
+//!       List a book (default: the embedded one).
+//!
+//! The shipped book is wasm-ai/book/opening.bin; rebuild the crate after
+//! replacing it.
+
+use std::collections::BTreeMap;
+
+use dominos_ai::book::{self, Book, BookEntry, HandEntry};
+use dominos_ai::deal::{deal, evaluate_deal};
+use dominos_ai::equity::Objective;
+use dominos_ai::game::{HandState, AI, HUMAN};
+use dominos_ai::lookup::{TILE_HIGH, TILE_LOW};
+use dominos_ai::rules::RuleSet;
+use dominos_ai::search::SearchLimits;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("build") => build(&args[1..]),
+        Some("hands") => hands(&args[1..]),
+        Some("dump") => dump(args.get(1)),
+        _ => usage("expected a subcommand: build, hands or dump"),
+    }
+}
+
+fn build(args: &[String]) {
+    let mut from: u32 = 1;
+    let mut count: u32 = 10;
+    let mut budget = 120_000.0;
+    let mut merge = None;
+    let mut out = None;
+    for pair in args.chunks(2) {
+        let [flag, value] = pair else { usage(&format!("{} needs a value", pair[0])) };
+        match flag.as_str() {
+            "--from" => from = num(value),
+            "--count" => count = num(value),
+            "--budget" => budget = num(value),
+            "--merge" => merge = Some(value.clone()),
+            "--out" => out = Some(value.clone()),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+    }
+    let out = out.unwrap_or_else(|| usage("build needs --out"));
+    let mut book = match merge {
+        Some(path) => read(&path),
+        None => Book::default(),
+    };
+
+    // Solve from scratch, not from the book being rebuilt
+    book::set_enabled(false);
+    for seed in from..from.saturating_add(count) {
+        let [human, ai] = deal(seed);
+        let e = evaluate_deal([human, ai], budget, &RuleSet::STANDARD);
+        for (o, leader, opp, sign) in [(e.ai_leads, ai, human, 1), (e.human_leads, human, ai, -1)] {
+            if !o.solved {
+                eprintln!("seed {}: unsolved within budget, skipped", seed);
+                continue;
+            }
+            book.deals.push(BookEntry {
+                leader_hand: leader,
+                opponent_hand: opp,
+                tile: o.best_tile,
+                score: (sign * o.score) as i16,
+            });
+        }
+        eprintln!("seed {}: ai leads {:+}, human leads {:+}", seed, e.ai_leads.score, e.human_leads.score);
+    }
+
+    write(&out, &book);
+}
+
+fn hands(args: &[String]) {
+    let mut from: u32 = 1;
+    let mut count: u32 = 1000;
+    let mut nodes: u32 = 200_000;
+    let mut min_votes: u32 = 3;
+    let mut merge = None;
+    let mut out = None;
+    for pair in args.chunks(2) {
+        let [flag, value] = pair else { usage(&format!("{} needs a value", pair[0])) };
+        match flag.as_str() {
+            "--from" => from = num(value),
+            "--count" => count = num(value),
+            "--nodes" => nodes = num(value),
+            "--min-votes" => min_votes = num(value),
+            "--merge" => merge = Some(value.clone()),
+            "--out" => out = Some(value.clone()),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+    }
+    let out = out.unwrap_or_else(|| usage("hands needs --out"));
+    let mut book = match merge {
+        Some(path) => read(&path),
+        None => Book::default(),
+    };
+
+    let mut tally: BTreeMap<u32, Tally> = BTreeMap::new();
+    let limits = SearchLimits { max_nodes: nodes, fresh: true, ..SearchLimits::NONE };
+    for seed in from..from.saturating_add(count) {
+        let [human, ai] = deal(seed);
+        for leader in [AI, HUMAN] {
+            let pos = HandState::new(human, ai, leader);
+            let r = pos.search_limited(leader, 1e9, &RuleSet::STANDARD, Objective::Points, 0, &limits);
+            let hand = pos.hands[leader as usize];
+            let (key, suits) = book::hand_key(hand);
+            let rank = |pip: i8| suits.iter().position(|&s| s == pip).unwrap();
+            let t = r.best_tile_idx as usize;
+            let lead = HandEntry::lead_code(rank(TILE_LOW[t]), rank(TILE_HIGH[t]));
+            let t = tally.entry(key).or_default();
+            *t.votes.entry(lead).or_default() += 1;
+            t.samples += 1;
+            t.score_sum += r.best_score as i64;
+        }
+        if seed % 100 == 0 {
+            eprintln!("seed {}: {} profiles", seed, tally.len());
+        }
+    }
+
+    book.hands = tally.iter().filter_map(|(&key, t)| {
+        let (&lead, &votes) = t.votes.iter().max_by_key(|&(&l, &v)| (v, std::cmp::Reverse(l)))?;
+        (votes >= min_votes && 2 * votes > t.samples).then(|| HandEntry {
+            key,
+            lead,
+            votes: votes.min(255) as u8,
+            score: (t.score_sum as f64 / t.samples as f64).round() as i16,
+        })
+    }).collect();
+    eprintln!("{} of {} profiles have a clear lead", book.hands.len(), tally.len());
+    write(&out, &book);
+}
+
+/// Searches of one leader suit profile.
+#[derive(Default)]
+struct Tally {
+    /// Lead (by suit rank) -> searches that chose it
+    votes: BTreeMap<u8, u32>,
+    samples: u32,
+    score_sum: i64,
+}
+
+fn write(path: &str, book: &Book) {
+    let bytes = book::encode(book);
+    std::fs::write(path, &bytes).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    let back = book::decode(&bytes).unwrap_or_default();
+    eprintln!("{} deal and {} hand entries ({} bytes) written to {}", back.deals.len(), back.hands.len(),
+              bytes.len(), path);
+}
+
+fn dump(path: Option<&String>) {
+    let book = match path {
+        Some(p) => read(p),
+        None => book::embedded(),
+    };
+    for e in &book.deals {
+        println!("{:07x} {:07x}  lead {}-{}  {:+}", e.leader_hand, e.opponent_hand,
+                 TILE_LOW[e.tile as usize], TILE_HIGH[e.tile as usize], e.score);
+    }
+    for e in &book.hands {
+        println!("profile {:07x}  lead ranks {}-{}  {} votes  {:+}", e.key, e.lead / 7, e.lead % 7, e.votes, e.score);
+    }
+    eprintln!("{} deal and {} hand entries", book.deals.len(), book.hands.len());
+}
+
+fn read(path: &str) -> Book {
+    let bytes = std::fs::read(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    book::decode(&bytes).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)))
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("book: {}", msg);
+    std::process::exit(1);
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("book: {}", msg);
+    eprintln!("usage: book build [--from N] [--count N] [--budget MS] [--merge FILE] --out FILE");
+    eprintln!("       book hands [--from N] [--count N] [--nodes N] [--min-votes N] [--merge FILE] --out FILE");
+    eprintln!("       book dump [FILE]");
+    std::process::exit(2);
+}
@@ -0,0 +1,83 @@
+//! Solve seeded deals for both leaders, to pick fair deal sets.
+//!
+//! Usage: deals [options]
+//!   --from N          first seed (default 1)
+//!   --count N         seeds to evaluate (default 10)
+//!   --budget MS       search time per leader (default 120000)
+//!   --margin X        balanced if |expected| <= X (default 10)
+//!   --balanced-only   print only the seeds of solved, balanced deals
+
+use dominos_ai::deal::{deal, evaluate_deal};
+use dominos_ai::lookup::{TILE_HIGH, TILE_LOW};
+use dominos_ai::rules::RuleSet;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut from: u32 = 1;
+    let mut count: u32 = 10;
+    let mut budget = 120_000.0;
+    let mut margin = 10.0;
+    let mut balanced_only = false;
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        let mut value = || {
+            i += 1;
+            args.get(i).cloned().unwrap_or_else(|| usage(&format!("{} needs a value", flag)))
+        };
+        match flag {
+            "--from" => from = num(&value()),
+            "--count" => count = num(&value()),
+            "--budget" => budget = num(&value()),
+            "--margin" => margin = num(&value()),
+            "--balanced-only" => balanced_only = true,
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+        i += 1;
+    }
+
+    if !balanced_only {
+        println!("{:>8}  {:>12}  {:>9}  {:>8}  verdict", "seed", "human leads", "ai leads", "expected");
+    }
+    for seed in from..from.saturating_add(count) {
+        let hands = deal(seed);
+        let e = evaluate_deal(hands, budget, &RuleSet::STANDARD);
+        let balanced = e.solved() && e.is_balanced(margin);
+        if balanced_only {
+            if balanced {
+                println!("{}", seed);
+            }
+            continue;
+        }
+        let verdict = if !e.solved() {
+            "unsolved"
+        } else if balanced {
+            "balanced"
+        } else if e.expected() > 0.0 {
+            "favours ai"
+        } else {
+            "favours human"
+        };
+        println!("{:>8}  {:>12}  {:>9}  {:>8.1}  {}", seed, e.human_leads.score, e.ai_leads.score,
+                 e.expected(), verdict);
+        println!("          human: {}", tiles(hands[0]));
+        println!("          ai:    {}", tiles(hands[1]));
+    }
+}
+
+fn tiles(hand: i32) -> String {
+    (0..28).filter(|&t| hand & (1 << t) != 0)
+        .map(|t| format!("{}-{}", TILE_LOW[t], TILE_HIGH[t]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("deals: {}", msg);
+    eprintln!("usage: deals [--from N] [--count N] [--budget MS] [--margin X] [--balanced-only]");
+    std::process::exit(2);
+}
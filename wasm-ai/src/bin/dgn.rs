@@ -0,0 +1,85 @@
+//! DGN game records: convert and check.
+//!
+//! Usage:
+//!   dgn from-json <game.json>...   game.js hand JSON (deal + moveHistory) to DGN
+//!   dgn to-json <file.dgn>         DGN to game JSON, one object per game
+//!   dgn check <file.dgn>...        replay every game and print its result
+
+use dominos_ai::dgn;
+use serde_json::Value;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(cmd) = args.first() else { usage() };
+    let files = &args[1..];
+    if files.is_empty() {
+        usage();
+    }
+    let mut failed = false;
+    for file in files {
+        let text = match std::fs::read_to_string(file) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                failed = true;
+                continue;
+            }
+        };
+        let ok = match cmd.as_str() {
+            "from-json" => convert(file, &dominos_ai::wasm_dgn_from_history(&text), "dgn"),
+            "to-json" => convert(file, &dominos_ai::wasm_dgn_to_history(&text), "games"),
+            "check" => check(file, &text),
+            _ => usage(),
+        };
+        failed |= !ok;
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Print `field` of a wasm output, or its error.
+fn convert(file: &str, out: &str, field: &str) -> bool {
+    let v: Value = serde_json::from_str(out).unwrap_or(Value::Null);
+    if let Some(err) = v["error"].as_str() {
+        eprintln!("{}: {}", file, err);
+        return false;
+    }
+    match &v[field] {
+        Value::String(s) => print!("{}", s),
+        other => println!("{}", serde_json::to_string_pretty(other).unwrap_or_default()),
+    }
+    true
+}
+
+fn check(file: &str, text: &str) -> bool {
+    let games = match dgn::parse_all(text) {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("{}: {}", file, e);
+            return false;
+        }
+    };
+    let mut ok = true;
+    for (i, g) in games.iter().enumerate() {
+        match g.validate() {
+            Ok(r) => println!("{} game {}: {} moves, {}", file, i + 1, g.moves.len(), match r.result {
+                None => "unfinished".to_string(),
+                Some(sc) if sc < 0 => format!("human {}", -sc),
+                Some(sc) => format!("ai {}", sc),
+            }),
+            Err(e) => {
+                eprintln!("{} game {}: {}", file, i + 1, e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn usage() -> ! {
+    eprintln!("usage: dgn from-json <game.json>...");
+    eprintln!("       dgn to-json <file.dgn>");
+    eprintln!("       dgn check <file.dgn>...");
+    std::process::exit(2);
+}
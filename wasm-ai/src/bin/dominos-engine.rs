@@ -0,0 +1,300 @@
+//! Line protocol for driving the engine from other programs (UCI-style):
+//! one command per line on stdin, replies on stdout.
+//!
+//! Commands:
+//!   engine                    identify: `id name ...`, one `option` line per
+//!                             option, then `engineok`
+//!   isready                   `readyok` (answered even while searching)
+//!   newgame                   clear the search state between games
+//!   setoption name <N> value <V>
+//!                             Budget (ms per move, default 1000), Level (1-10),
+//!                             Book (on|off), Objective (points|equity),
+//!                             Rules (DGN rules, e.g. `ghost13=off,target=150`),
+//!                             Params (eval params file)
+//!   position <notation> [moves <m>...]
+//!   position deal <seed> [ai|human] [moves <m>...]
+//!                             a `notation` line, or a seeded 14/14 deal (leader
+//!                             default ai), then moves as DGN tokens (`a:35L`,
+//!                             `h:pass`) or bare (`35L`, `pass`) for the side
+//!                             to move
+//!   go [budget=MS] [nodes=N] [depth=D]
+//!                             search the side to move in the background
+//!   stop                      end the search early; it still answers `bestmove`
+//!   show                      `info string <notation>` of the current position
+//!   quit
+//!
+//! Replies:
+//!   info depth D score S nodes N time MS pv <move>
+//!                             after each iteration; `score` is hand points
+//!                             (or equity) for the side to move
+//!   bestmove <move>           `35L`, `35R`, `pass`, or `none` if the hand is over
+//!   info string <text>        errors and notes
+
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+
+use dominos_ai::dgn::{self, Action, DgnMove};
+use dominos_ai::equity::Objective;
+use dominos_ai::eval::{self, EvalParams};
+use dominos_ai::game::{HandState, AI, HUMAN};
+use dominos_ai::lookup::NUM_TILES;
+use dominos_ai::notation::Position;
+use dominos_ai::rules::RuleSet;
+use dominos_ai::search::{self, IterationInfo, SearchLimits, SearchResult};
+use dominos_ai::skill::{self, SkillLevel};
+use dominos_ai::{book, deal, tt};
+
+const DEFAULT_BUDGET: f64 = 1000.0;
+
+/// Whether the position being searched has an empty board, for `print_info`
+static EMPTY_BOARD: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone)]
+struct Settings {
+    budget: f64,
+    level: u8,
+    equity: bool,
+    rules: RuleSet,
+}
+
+struct Engine {
+    settings: Settings,
+    pos: Option<Position>,
+    search: Option<JoinHandle<()>>,
+}
+
+fn main() {
+    let mut engine = Engine {
+        settings: Settings { budget: DEFAULT_BUDGET, level: skill::MAX_LEVEL, equity: false, rules: RuleSet::STANDARD },
+        pos: None,
+        search: None,
+    };
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&cmd, args)) = words.split_first() else { continue };
+        match cmd {
+            "engine" => identify(),
+            "isready" => println!("readyok"),
+            "newgame" => {
+                engine.finish_search();
+                engine.pos = None;
+                let _guard = search::lock_engine();
+                tt::tt_clear();
+            }
+            "setoption" => {
+                engine.finish_search();
+                if let Err(e) = engine.set_option(args) {
+                    println!("info string {}", e);
+                }
+            }
+            "position" => {
+                engine.finish_search();
+                match parse_position(args) {
+                    Ok(pos) => engine.pos = Some(pos),
+                    Err(e) => println!("info string {}", e),
+                }
+            }
+            "go" => {
+                if let Err(e) = engine.go(args) {
+                    println!("info string {}", e);
+                }
+            }
+            "stop" => engine.finish_search(),
+            "show" => match &engine.pos {
+                Some(pos) => println!("info string {}", pos.to_text()),
+                None => println!("info string no position"),
+            },
+            "quit" => break,
+            _ => println!("info string unknown command '{}'", cmd),
+        }
+    }
+    engine.finish_search();
+}
+
+fn identify() {
+    println!("id name dominos-ai {}", env!("CARGO_PKG_VERSION"));
+    println!("option name Budget type spin default {} min 1 max 600000", DEFAULT_BUDGET);
+    println!("option name Level type spin default {} min {} max {}", skill::MAX_LEVEL, skill::MIN_LEVEL,
+             skill::MAX_LEVEL);
+    println!("option name Book type check default on");
+    println!("option name Objective type combo default points var points var equity");
+    println!("option name Rules type string default {}", dgn::rules_text(&RuleSet::STANDARD));
+    println!("option name Params type string default <built-in>");
+    println!("engineok");
+}
+
+impl Engine {
+    /// Stop the running search, if any, and wait for its `bestmove`.
+    fn finish_search(&mut self) {
+        if let Some(handle) = self.search.take() {
+            search::set_stop(true);
+            let _ = handle.join();
+        }
+    }
+
+    fn set_option(&mut self, args: &[&str]) -> Result<(), String> {
+        let (name, value) = match args {
+            ["name", name, "value", value @ ..] if !value.is_empty() => (*name, value.join(" ")),
+            _ => return Err("usage: setoption name <name> value <value>".to_string()),
+        };
+        let s = &mut self.settings;
+        match name.to_ascii_lowercase().as_str() {
+            "budget" => s.budget = value.parse().ok().filter(|&b: &f64| b > 0.0)
+                .ok_or_else(|| format!("bad budget '{}'", value))?,
+            "level" => s.level = value.parse().ok()
+                .filter(|l| (skill::MIN_LEVEL..=skill::MAX_LEVEL).contains(l))
+                .ok_or_else(|| format!("bad level '{}'", value))?,
+            "book" => book::set_enabled(match value.as_str() {
+                "on" | "true" => true,
+                "off" | "false" => false,
+                _ => return Err(format!("bad book setting '{}'", value)),
+            }),
+            "objective" => s.equity = match value.as_str() {
+                "points" => false,
+                "equity" => true,
+                _ => return Err(format!("unknown objective '{}'", value)),
+            },
+            "rules" => s.rules = dgn::parse_rules(&value)?,
+            "params" => {
+                let text = std::fs::read_to_string(&value).map_err(|e| format!("{}: {}", value, e))?;
+                eval::set_params(&EvalParams::parse(&text).map_err(|e| format!("{}: {}", value, e))?);
+            }
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[&str]) -> Result<(), String> {
+        if self.search.as_ref().is_some_and(|h| !h.is_finished()) {
+            return Err("already searching".to_string());
+        }
+        self.finish_search();
+        let pos = self.pos.clone().ok_or("no position")?;
+        let mut settings = self.settings.clone();
+        let mut limits = SearchLimits::NONE;
+        for arg in args {
+            let (key, value) = arg.split_once('=').ok_or_else(|| format!("expected key=value, got '{}'", arg))?;
+            let bad = || format!("bad {} '{}'", key, value);
+            match key {
+                "budget" => settings.budget = value.parse().map_err(|_| bad())?,
+                "nodes" => limits.max_nodes = value.parse().map_err(|_| bad())?,
+                "depth" => limits.max_depth = value.parse().map_err(|_| bad())?,
+                _ => return Err(format!("unknown go parameter '{}'", key)),
+            }
+        }
+        search::set_stop(false);
+        self.search = Some(std::thread::spawn(move || {
+            println!("bestmove {}", best_move(&pos, &settings, &limits));
+        }));
+        Ok(())
+    }
+}
+
+/// Search `pos` for the side to move; the move as a bare token.
+fn best_move(pos: &Position, settings: &Settings, limits: &SearchLimits) -> String {
+    let state = &pos.state;
+    let mover = state.to_move;
+    if state.result_for(mover, &settings.rules).is_some() {
+        return "none".to_string();
+    }
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let n = state.legal_moves(&mut buf);
+    if n == 0 {
+        return "pass".to_string();
+    }
+
+    let (me, opp) = (pos.score[mover as usize], pos.score[1 - mover as usize]);
+    let objective = if settings.equity {
+        Objective::MatchEquity { ai_score: me, human_score: opp }
+    } else {
+        Objective::Points
+    };
+    let skill = SkillLevel::new(settings.level);
+    let start = std::time::Instant::now();
+    // Weaker levels run many short searches; only full strength reports iterations
+    search::set_info_hook(if skill.is_full_strength() { Some(print_info) } else { None });
+    EMPTY_BOARD.store(state.left == 7, Ordering::Relaxed);
+    let r: SearchResult = if skill.is_full_strength() {
+        state.search_limited(mover, settings.budget, &settings.rules, objective, me - opp, limits)
+    } else {
+        // Seeded from the position, so a replay picks the same moves
+        let seed = (state.hands[0] as u32) ^ (state.hands[1] as u32).rotate_left(11)
+            ^ ((state.left as u32) << 28) ^ ((state.right as u32) << 24);
+        skill::choose_move_at_level(state, &skill, settings.budget, &settings.rules, objective, me - opp, seed)
+    };
+    let (tile, end) = state.find_legal(r.best_tile_idx, r.best_end).unwrap_or(buf[0]);
+    let token = move_token(state, tile, end);
+    if !skill.is_full_strength() {
+        println!("info depth {} score {} nodes {} time {} pv {}", r.depth, r.best_score, r.nodes,
+                 start.elapsed().as_millis(), token);
+    }
+    token
+}
+
+fn print_info(info: &IterationInfo) {
+    let end = if EMPTY_BOARD.load(Ordering::Relaxed) { -1 } else { info.best_end };
+    let t = DgnMove { who: AI, action: Action::Place { tile: info.best_tile_idx, end } }.to_text();
+    println!("info depth {} score {} nodes {} time {} pv {}", info.depth, info.score, info.nodes,
+             info.elapsed_ms, &t[2..]);
+}
+
+/// `35L` / `35R`; no end on the empty board.
+fn move_token(state: &HandState, tile: i8, end: i8) -> String {
+    let end = if state.left == 7 { -1 } else { end };
+    let text = DgnMove { who: state.to_move, action: Action::Place { tile, end } }.to_text();
+    text[2..].to_string()
+}
+
+fn parse_position(args: &[&str]) -> Result<Position, String> {
+    let split = args.iter().position(|&a| a == "moves").unwrap_or(args.len());
+    let (setup, moves) = (&args[..split], args.get(split + 1..).unwrap_or(&[]));
+    let mut pos = match setup {
+        ["deal", seed, rest @ ..] => {
+            let seed: u32 = seed.parse().map_err(|_| format!("bad seed '{}'", seed))?;
+            let leader = match rest {
+                [] | ["ai"] => AI,
+                ["human"] => HUMAN,
+                _ => return Err("usage: position deal <seed> [ai|human]".to_string()),
+            };
+            let [human, ai] = deal::deal(seed);
+            Position::from_state(HandState::new(human, ai, leader))
+        }
+        _ => Position::parse(&setup.join(" "))?,
+    };
+    for &m in moves {
+        apply(&mut pos, m).map_err(|e| format!("move {}: {}", m, e))?;
+    }
+    Ok(pos)
+}
+
+fn apply(pos: &mut Position, token: &str) -> Result<(), String> {
+    let to_move = pos.state.to_move;
+    let mv = if token.contains(':') {
+        DgnMove::parse(token)?
+    } else {
+        DgnMove::parse(&format!("{}:{}", if to_move == AI { 'a' } else { 'h' }, token))?
+    };
+    if mv.who != to_move {
+        return Err("not that side's turn".to_string());
+    }
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let n = pos.state.legal_moves(&mut buf);
+    match mv.action {
+        Action::Pass if n == 0 => pos.pass(),
+        Action::Pass => return Err(format!("pass with {} legal moves", n)),
+        Action::Draw { .. } => return Err("no boneyard in this variant".to_string()),
+        Action::Place { tile, end } => {
+            let s = &pos.state;
+            let found = if end >= 0 {
+                s.find_legal(tile, end).map(|(t, _)| (t, end))
+            } else {
+                s.find_legal(tile, 0).or_else(|| s.find_legal(tile, 1))
+            };
+            let (t, e) = found.ok_or("illegal placement")?;
+            pos.place(t, e);
+        }
+    }
+    Ok(())
+}
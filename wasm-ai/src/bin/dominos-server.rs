@@ -0,0 +1,48 @@
+//! Local HTTP/JSON engine server; endpoints are described in `server.rs`.
+//!
+//! Usage: dominos-server [options]
+//!   --addr HOST:PORT      listen address (default 127.0.0.1:8765)
+//!   --timeout MS          per-request limit (default 30000)
+//!   --concurrent N        requests in progress at once (default 4)
+//!
+//!   cargo run --release --features server --bin dominos-server
+//!   curl -d '56/0166 - a 0 -' localhost:8765/choose-move
+
+use dominos_ai::server::{self, Config};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut config = Config::default();
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        let mut value = || {
+            i += 1;
+            args.get(i).cloned().unwrap_or_else(|| usage(&format!("{} needs a value", flag)))
+        };
+        match flag {
+            "--addr" => config.addr = value(),
+            "--timeout" => config.timeout_ms = num(&value()),
+            "--concurrent" => config.max_concurrent = num(&value()),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+        i += 1;
+    }
+
+    eprintln!("listening on {} (timeout {} ms, {} concurrent)", config.addr, config.timeout_ms,
+              config.max_concurrent);
+    if let Err(e) = server::run(&config) {
+        eprintln!("dominos-server: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("dominos-server: {}", msg);
+    eprintln!("Usage: dominos-server [--addr HOST:PORT] [--timeout MS] [--concurrent N]");
+    std::process::exit(2);
+}
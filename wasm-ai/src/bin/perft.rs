@@ -0,0 +1,135 @@
+//! Move-generation check: count move sequences from a position.
+//!
+//! Usage: perft [options]
+//!   --seed N            position from deal N (default 1); ignored with --ai/--human
+//!   --ai TILES          AI hand, e.g. 6-6,0-1,3-5
+//!   --human TILES       human hand
+//!   --ends L-R          board ends (default: empty board)
+//!   --to-move ai|human  side to move (default ai)
+//!   --depth N           plies to count, 1..=N in turn (default: to the end of the hand)
+//!   --divide            split the last count by root move
+//!   --check             also run the naive reference and fail on a mismatch
+
+use std::time::Instant;
+
+use dominos_ai::deal::deal;
+use dominos_ai::game::{HandState, AI, HUMAN};
+use dominos_ai::lookup::{tile_id_to_index, TILE_HIGH, TILE_LOW};
+use dominos_ai::perft::{perft, perft_divide, perft_naive, PerftCounts};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut seed = 1;
+    let mut hands: [Option<i32>; 2] = [None, None];
+    let mut ends = (7, 7);
+    let mut to_move = AI;
+    let mut depth = None;
+    let mut divide = false;
+    let mut check = false;
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        let mut value = || {
+            i += 1;
+            args.get(i).cloned().unwrap_or_else(|| usage(&format!("{} needs a value", flag)))
+        };
+        match flag {
+            "--seed" => seed = num(&value()),
+            "--ai" => hands[AI as usize] = Some(parse_hand(&value())),
+            "--human" => hands[HUMAN as usize] = Some(parse_hand(&value())),
+            "--ends" => ends = parse_tile(&value()),
+            "--to-move" => to_move = match value().as_str() {
+                "ai" => AI,
+                "human" => HUMAN,
+                v => usage(&format!("unknown side '{}'", v)),
+            },
+            "--depth" => depth = Some(num(&value())),
+            "--divide" => divide = true,
+            "--check" => check = true,
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+        i += 1;
+    }
+
+    let [human, ai] = match hands {
+        [None, None] => deal(seed),
+        [Some(h), Some(a)] => [h, a],
+        _ => usage("--ai and --human go together"),
+    };
+    if human & ai != 0 {
+        usage("hands share a tile");
+    }
+    let mut pos = HandState::new(human, ai, to_move);
+    pos.left = ends.0;
+    pos.right = ends.1;
+
+    let depths: Vec<Option<u32>> = match depth {
+        Some(d) => (1..=d).map(Some).collect(),
+        None => vec![None],
+    };
+    let mut failed = false;
+    for &d in &depths {
+        let start = Instant::now();
+        let c = perft(&pos, d);
+        let ms = start.elapsed().as_secs_f64() * 1000.0;
+        let label = d.map_or("end".to_string(), |d| d.to_string());
+        println!("depth {:>3}  {}  {:.1} ms", label, counts(&c), ms);
+        if check {
+            let naive = perft_naive(&pos, d);
+            if naive != c {
+                println!("  MISMATCH naive {}", counts(&naive));
+                failed = true;
+            }
+        }
+    }
+
+    if divide {
+        let d = depths.last().copied().flatten();
+        for ((t, e), c) in perft_divide(&pos, d) {
+            let mv = if t < 0 {
+                "pass".to_string()
+            } else {
+                format!("{}-{} {}", TILE_LOW[t as usize], TILE_HIGH[t as usize],
+                        if e == 1 { "right" } else { "left" })
+            };
+            println!("  {:<10} {}", mv, counts(&c));
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn counts(c: &PerftCounts) -> String {
+    format!("leaves {:>12}  placements {:>12}  passes {:>10}  dominoes {:>10}  blocks {:>10}",
+            c.leaves, c.placements, c.passes, c.dominoes, c.blocks)
+}
+
+fn parse_tile(s: &str) -> (i8, i8) {
+    let pip = |p: &str| match p.trim().parse::<i8>() {
+        Ok(v) if (0..=6).contains(&v) => v,
+        _ => usage(&format!("bad tile '{}'", s)),
+    };
+    match s.split_once('-') {
+        Some((a, b)) => (pip(a), pip(b)),
+        None => usage(&format!("bad tile '{}'", s)),
+    }
+}
+
+fn parse_hand(s: &str) -> i32 {
+    s.split(',').filter(|t| !t.trim().is_empty()).fold(0, |h, t| {
+        let (a, b) = parse_tile(t);
+        h | 1 << tile_id_to_index(a, b)
+    })
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("perft: {}", msg);
+    eprintln!("usage: perft [--seed N | --ai TILES --human TILES] [--ends L-R] [--to-move ai|human]");
+    eprintln!("             [--depth N] [--divide] [--check]");
+    std::process::exit(2);
+}
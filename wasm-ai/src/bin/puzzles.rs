@@ -0,0 +1,58 @@
+//! Generate "find the only good move" puzzles from seeded deals; one JSON
+//! object per line on stdout (see `puzzle`), progress on stderr.
+//!
+//! Usage: puzzles [options]
+//!   --seed N          first deal seed (default 1)
+//!   --deals N         deals to play (default 100)
+//!   --margin N        points every alternative must lose (default 10)
+//!   --max-tiles N     only positions with at most N tiles left (default 14)
+//!   --nodes N         node cap per solve (default 20000000)
+//!   --play-nodes N    node cap per move while playing deals (default 20000)
+//!   --rules RULES     DGN rules, e.g. `ghost13=off,target=150`
+
+use dominos_ai::dgn;
+use dominos_ai::puzzle::{self, PuzzleOptions};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut opts = PuzzleOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        let mut value = || {
+            i += 1;
+            args.get(i).cloned().unwrap_or_else(|| usage(&format!("{} needs a value", flag)))
+        };
+        match flag {
+            "--seed" => opts.seed = num(&value()),
+            "--deals" => opts.deals = num(&value()),
+            "--margin" => opts.min_margin = num(&value()),
+            "--max-tiles" => opts.max_tiles = num(&value()),
+            "--nodes" => opts.solve_nodes = num(&value()),
+            "--play-nodes" => opts.play_nodes = num(&value()),
+            "--rules" => opts.rules = dgn::parse_rules(&value()).unwrap_or_else(|e| usage(&e)),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+        i += 1;
+    }
+
+    let mut count = 0;
+    puzzle::generate(&opts, |p| {
+        count += 1;
+        eprintln!("  seed {:>6}  {:<9}  difficulty {}  margin {}", p.seed, p.theme.name(), p.difficulty,
+                  p.margin);
+        println!("{}", p.to_json(&opts.rules));
+    });
+    eprintln!("{} puzzles from {} deals", count, opts.deals);
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("puzzles: {}", msg);
+    eprintln!("usage: puzzles [--seed N] [--deals N] [--margin N] [--max-tiles N] [--nodes N] \
+               [--play-nodes N] [--rules RULES]");
+    std::process::exit(2);
+}
@@ -0,0 +1,104 @@
+//! Export self-play positions labelled with exact values for training
+//! learned evaluations (see `training` for the record layout).
+//!
+//! Usage: training --out FILE [options]
+//!   --format bin|jsonl    32-byte binary records or JSON lines (default jsonl)
+//!   --seed N              dataset seed (default 1)
+//!   --deals N             deals over all shards (default 1000)
+//!   --shard K/N           play shard K of N (default 0/1); run N processes
+//!                         with the same seed and deals and concatenate
+//!   --per-deal N          positions sampled per deal (default 4)
+//!   --min-tiles N         fewest tiles left in both hands (default 4)
+//!   --max-tiles N         most tiles left in both hands (default 16)
+//!   --random PCT          self-play moves picked at random (default 10)
+//!   --play-nodes N        node cap per self-play move (default 20000)
+//!   --solve-nodes N       node cap per solve (default 20000000)
+//!   --rules RULES         DGN rules, e.g. `ghost13=off,target=150`
+
+use std::fs;
+use std::io::{BufWriter, Write};
+
+use dominos_ai::dgn;
+use dominos_ai::training::{self, TrainingOptions};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut opts = TrainingOptions::default();
+    let mut out = None;
+    let mut binary = false;
+    for (flag, value) in pairs(&args) {
+        match flag {
+            "--out" => out = Some(value.to_string()),
+            "--format" => binary = match value {
+                "bin" => true,
+                "jsonl" => false,
+                _ => usage(&format!("unknown format '{}'", value)),
+            },
+            "--seed" => opts.seed = num(value),
+            "--deals" => opts.deals = num(value),
+            "--shard" => {
+                let (k, n) = value.split_once('/').unwrap_or_else(|| usage("--shard needs K/N"));
+                (opts.shard, opts.shards) = (num(k), num(n));
+            }
+            "--per-deal" => opts.per_deal = num(value),
+            "--min-tiles" => opts.min_tiles = num(value),
+            "--max-tiles" => opts.max_tiles = num(value),
+            "--random" => opts.random_pct = num(value),
+            "--play-nodes" => opts.play_nodes = num(value),
+            "--solve-nodes" => opts.solve_nodes = num(value),
+            "--rules" => opts.rules = dgn::parse_rules(value).unwrap_or_else(|e| usage(&e)),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+    }
+    let out = out.unwrap_or_else(|| usage("needs --out"));
+    if opts.shards == 0 || opts.shard >= opts.shards {
+        usage("--shard K/N needs K < N");
+    }
+    if opts.min_tiles > opts.max_tiles {
+        usage("--min-tiles is above --max-tiles");
+    }
+
+    let file = fs::File::create(&out).unwrap_or_else(|e| fail(&format!("{}: {}", out, e)));
+    let mut w = BufWriter::new(file);
+    let mut n = 0;
+    let unsolved = training::generate(&opts, |s| {
+        let written = if binary {
+            w.write_all(&s.to_bytes())
+        } else {
+            writeln!(w, "{}", s.to_json())
+        };
+        written.unwrap_or_else(|e| fail(&format!("{}: {}", out, e)));
+        n += 1;
+        if n % 1000 == 0 {
+            eprintln!("  {} positions", n);
+        }
+    });
+    w.flush().unwrap_or_else(|e| fail(&format!("{}: {}", out, e)));
+    eprintln!("{} positions written to {} ({} unsolved dropped)", n, out, unsolved);
+}
+
+/// `--flag value` pairs.
+fn pairs(args: &[String]) -> Vec<(&str, &str)> {
+    args.chunks(2).map(|c| match c {
+        [flag, value] => (flag.as_str(), value.as_str()),
+        [flag] => usage(&format!("{} needs a value", flag)),
+        _ => unreachable!(),
+    }).collect()
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("training: {}", msg);
+    std::process::exit(1);
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("training: {}", msg);
+    eprintln!("usage: training --out FILE [--format bin|jsonl] [--seed N] [--deals N] [--shard K/N] \
+               [--per-deal N] [--min-tiles N] [--max-tiles N] [--random PCT] [--play-nodes N] \
+               [--solve-nodes N] [--rules RULES]");
+    std::process::exit(2);
+}
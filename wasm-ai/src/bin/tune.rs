@@ -0,0 +1,128 @@
+//! Evaluation-weight tuner.
+//!
+//! Usage:
+//!   tune gen --out FILE [--positions N] [--seed N] [--min-tiles N]
+//!            [--max-tiles N] [--budget MS]
+//!       Label seeded mid-game positions with full-depth search scores.
+//!   tune fit --data FILE [--start PARAMS] [--iters N] [--out PARAMS]
+//!       Fit the eval weights to a dataset and write a params file
+//!       (stdout without --out), loadable by `arena params=FILE` and
+//!       `wasm_set_eval_params`.
+
+use std::fs;
+use std::io::Write;
+
+use dominos_ai::eval::EvalParams;
+use dominos_ai::rules::RuleSet;
+use dominos_ai::tune::{self, DatasetOptions, TuneSample};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("gen") => gen(&args[1..]),
+        Some("fit") => fit(&args[1..]),
+        _ => usage("expected a subcommand: gen or fit"),
+    }
+}
+
+fn gen(args: &[String]) {
+    let mut opts = DatasetOptions::default();
+    let mut out = None;
+    for (flag, value) in pairs(args) {
+        match flag {
+            "--out" => out = Some(value.to_string()),
+            "--positions" => opts.positions = num(value),
+            "--seed" => opts.seed = num(value),
+            "--min-tiles" => opts.min_tiles = num(value),
+            "--max-tiles" => opts.max_tiles = num(value),
+            "--budget" => opts.time_budget = num(value),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+    }
+    let out = out.unwrap_or_else(|| usage("gen needs --out"));
+    if opts.min_tiles > opts.max_tiles {
+        usage("--min-tiles is above --max-tiles");
+    }
+
+    let mut file = fs::File::create(&out).unwrap_or_else(|e| fail(&format!("{}: {}", out, e)));
+    let mut n = 0;
+    let data = tune::generate_dataset(&opts, |s| {
+        writeln!(file, "{}", s.to_line()).unwrap_or_else(|e| fail(&format!("{}: {}", out, e)));
+        n += 1;
+        if n % 100 == 0 {
+            eprintln!("  {} positions", n);
+        }
+    });
+    eprintln!("{} positions written to {}", data.len(), out);
+}
+
+fn fit(args: &[String]) {
+    let mut data_path = None;
+    let mut start = EvalParams::DEFAULT;
+    let mut iters = 200;
+    let mut out = None;
+    for (flag, value) in pairs(args) {
+        match flag {
+            "--data" => data_path = Some(value.to_string()),
+            "--start" => start = read_params(value),
+            "--iters" => iters = num(value),
+            "--out" => out = Some(value.to_string()),
+            _ => usage(&format!("unknown option {}", flag)),
+        }
+    }
+    let data_path = data_path.unwrap_or_else(|| usage("fit needs --data"));
+    let text = fs::read_to_string(&data_path).unwrap_or_else(|e| fail(&format!("{}: {}", data_path, e)));
+    let data: Vec<TuneSample> = text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| TuneSample::parse_line(l)
+            .unwrap_or_else(|e| fail(&format!("{}:{}: {}", data_path, i + 1, e))))
+        .collect();
+    if data.is_empty() {
+        fail(&format!("{}: no positions", data_path));
+    }
+
+    let rules = RuleSet::STANDARD;
+    eprintln!("{} positions, start mse {:.2}", data.len(), tune::mse(&start, &data, &rules));
+    let fitted = tune::fit(&start, &data, &rules, iters, |pass, err| {
+        if pass % 10 == 0 {
+            eprintln!("  pass {:>4}  mse {:.2}", pass, err);
+        }
+    });
+    eprintln!("final mse {:.2}", tune::mse(&fitted, &data, &rules));
+
+    match out {
+        Some(path) => fs::write(&path, fitted.to_text()).unwrap_or_else(|e| fail(&format!("{}: {}", path, e))),
+        None => print!("{}", fitted.to_text()),
+    }
+}
+
+fn read_params(path: &str) -> EvalParams {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    EvalParams::parse(&text).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)))
+}
+
+/// `--flag value` pairs.
+fn pairs(args: &[String]) -> Vec<(&str, &str)> {
+    args.chunks(2).map(|c| match c {
+        [flag, value] => (flag.as_str(), value.as_str()),
+        [flag] => usage(&format!("{} needs a value", flag)),
+        _ => unreachable!(),
+    }).collect()
+}
+
+fn num<T: std::str::FromStr>(s: &str) -> T {
+    s.parse().unwrap_or_else(|_| usage(&format!("bad number '{}'", s)))
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("tune: {}", msg);
+    std::process::exit(1);
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("tune: {}", msg);
+    eprintln!("usage: tune gen --out FILE [--positions N] [--seed N] [--min-tiles N] [--max-tiles N] [--budget MS]");
+    eprintln!("       tune fit --data FILE [--start PARAMS] [--iters N] [--out PARAMS]");
+    std::process::exit(2);
+}
@@ -0,0 +1,430 @@
+//! Opening book: first leads of a 14/14 deal, embedded in the binary.
+//!
+//! Two tables:
+//!
+//! - Deals: with no boneyard the leader's 14 tiles also fix the opponent's
+//!   hand, so a deal is keyed exactly by (leader hand, opponent hand).
+//!   Entries are full-depth solves under the standard rules (`book build`,
+//!   from seeded deals); a hit is the perfect-information answer. Random
+//!   deals rarely hit; seeded suites, arena runs and replays do.
+//! - Hands: keyed by the leader's suit profile (`hand_key`): for every suit,
+//!   how many of its tiles the leader holds and whether the double is one,
+//!   with the suits ranked by that profile. About a thousand profiles cover
+//!   nearly every deal. Entries are the lead most node-capped searches chose
+//!   over sampled deals with that profile (`book hands`), stored by suit
+//!   rank, so a hit is a strong heuristic, not an exact result.
+//!
+//! Format (little-endian):
+//!
+//! ```text
+//! magic   "DOMBOOK2"
+//! deals   u32
+//! hands   u32
+//! deal entries, deals × 10 bytes, sorted by key:
+//!   key   7 bytes  leader hand | opponent hand << 28
+//!   tile  u8       best lead (tile index)
+//!   score i16      hand result for the leader with perfect play
+//! hand entries, hands × 8 bytes, sorted by key:
+//!   key   u32      `hand_key` of the leader's hand
+//!   lead  u8       lead by suit rank: low rank * 7 + high rank
+//!   votes u8       sampled deals that chose it
+//!   score i16      mean hand result for the leader over the samples
+//! ```
+
+use crate::equity::Objective;
+use crate::lookup::{tile_id_to_index, NUM_TILES, TILE_HIGH, TILE_LOW};
+use crate::rules::RuleSet;
+use crate::search::{lock_engine, SearchLimits};
+
+const MAGIC: &[u8; 8] = b"DOMBOOK2";
+const HEADER_LEN: usize = 16;
+const ENTRY_LEN: usize = 10;
+const HAND_ENTRY_LEN: usize = 8;
+
+/// The embedded book.
+static BOOK: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/book/opening.bin"));
+
+/// Consulted by `choose_move` unless switched off.
+static mut G_BOOK_ENABLED: bool = true;
+
+/// Turn book lookups in `choose_move` on or off. Takes the engine lock.
+pub fn set_enabled(enabled: bool) {
+    let _guard = lock_engine();
+    unsafe {
+        G_BOOK_ENABLED = enabled;
+    }
+}
+
+pub fn is_enabled() -> bool {
+    unsafe { G_BOOK_ENABLED }
+}
+
+/// A solved deal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    pub leader_hand: i32,
+    pub opponent_hand: i32,
+    pub tile: i8,
+    /// Leader's perspective
+    pub score: i16,
+}
+
+impl BookEntry {
+    fn key(&self) -> u64 {
+        key(self.leader_hand, self.opponent_hand)
+    }
+}
+
+fn key(leader_hand: i32, opponent_hand: i32) -> u64 {
+    leader_hand as u64 | (opponent_hand as u64) << 28
+}
+
+/// The lead for every leader hand with one suit profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandEntry {
+    /// `hand_key` of the leader's hand
+    pub key: u32,
+    /// Suit ranks of the lead: low rank * 7 + high rank
+    pub lead: u8,
+    /// Sampled deals that chose `lead`
+    pub votes: u8,
+    /// Mean over the samples, leader's perspective
+    pub score: i16,
+}
+
+impl HandEntry {
+    /// `lead` for a pair of suit ranks (in either order).
+    pub fn lead_code(rank_a: usize, rank_b: usize) -> u8 {
+        (rank_a.min(rank_b) * 7 + rank_a.max(rank_b)) as u8
+    }
+
+    /// The tile `lead` names in `hand`, with the suits ranked as in its
+    /// `hand_key`, if the hand holds it.
+    pub fn tile_in(&self, hand: i32) -> Option<i8> {
+        let (_, suits) = hand_key(hand);
+        let (a, b) = (self.lead as usize / 7, self.lead as usize % 7);
+        if a > b {
+            return None;
+        }
+        let tile = tile_id_to_index(suits[a], suits[b]);
+        (hand & 1 << tile != 0).then_some(tile as i8)
+    }
+}
+
+/// Canonical suit profile of a 14-tile hand and the suits in rank order.
+/// Suits are ranked by how many of their tiles the hand holds, then by
+/// holding the double, then by pip value (heavier first); the key packs
+/// (count | double << 3) per rank, 4 bits each, rank 0 lowest.
+pub fn hand_key(hand: i32) -> (u32, [i8; 7]) {
+    let mut count = [0u32; 7];
+    let mut double = [0u32; 7];
+    for t in (0..NUM_TILES).filter(|&t| hand & 1 << t != 0) {
+        let (a, b) = (TILE_LOW[t] as usize, TILE_HIGH[t] as usize);
+        count[a] += 1;
+        if a == b {
+            double[a] = 1;
+        } else {
+            count[b] += 1;
+        }
+    }
+    let mut suits: [i8; 7] = core::array::from_fn(|p| p as i8);
+    suits.sort_by_key(|&p| std::cmp::Reverse((count[p as usize], double[p as usize], p)));
+    let key = suits.iter().enumerate()
+        .fold(0, |k, (rank, &p)| k | (count[p as usize] | double[p as usize] << 3) << (4 * rank));
+    (key, suits)
+}
+
+/// Both tables of a book file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Book {
+    pub deals: Vec<BookEntry>,
+    pub hands: Vec<HandEntry>,
+}
+
+/// Serialize `book` (any order; later duplicates win).
+pub fn encode(book: &Book) -> Vec<u8> {
+    let mut deals = book.deals.clone();
+    deals.reverse();
+    deals.sort_by_key(BookEntry::key);
+    deals.dedup_by_key(|e| e.key());
+    let mut hands = book.hands.clone();
+    hands.reverse();
+    hands.sort_by_key(|e| e.key);
+    hands.dedup_by_key(|e| e.key);
+
+    let mut out = Vec::with_capacity(HEADER_LEN + deals.len() * ENTRY_LEN + hands.len() * HAND_ENTRY_LEN);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(deals.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(hands.len() as u32).to_le_bytes());
+    for e in &deals {
+        out.extend_from_slice(&e.key().to_le_bytes()[..7]);
+        out.push(e.tile as u8);
+        out.extend_from_slice(&e.score.to_le_bytes());
+    }
+    for e in &hands {
+        out.extend_from_slice(&e.key.to_le_bytes());
+        out.push(e.lead);
+        out.push(e.votes);
+        out.extend_from_slice(&e.score.to_le_bytes());
+    }
+    out
+}
+
+/// Parse and validate a book file.
+pub fn decode(bytes: &[u8]) -> Result<Book, String> {
+    let (deals, hands) = check(bytes)?;
+    Ok(Book {
+        deals: (0..deals).map(|i| entry_at(bytes, i)).collect(),
+        hands: (0..hands).map(|i| hand_entry_at(bytes, deals, i)).collect(),
+    })
+}
+
+/// Header check; returns the entry counts (deals, hands).
+fn check(bytes: &[u8]) -> Result<(usize, usize), String> {
+    if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+        return Err("not an opening book".to_string());
+    }
+    let count = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize;
+    let (deals, hands) = (count(8), count(12));
+    if bytes.len() != HEADER_LEN + deals * ENTRY_LEN + hands * HAND_ENTRY_LEN {
+        return Err(format!("book size does not match {} deal and {} hand entries", deals, hands));
+    }
+    Ok((deals, hands))
+}
+
+fn entry_at(bytes: &[u8], i: usize) -> BookEntry {
+    let b = &bytes[HEADER_LEN + i * ENTRY_LEN..][..ENTRY_LEN];
+    let mut k = [0u8; 8];
+    k[..7].copy_from_slice(&b[..7]);
+    let k = u64::from_le_bytes(k);
+    BookEntry {
+        leader_hand: (k & 0x0fff_ffff) as i32,
+        opponent_hand: (k >> 28) as i32,
+        tile: b[7] as i8,
+        score: i16::from_le_bytes([b[8], b[9]]),
+    }
+}
+
+fn hand_entry_at(bytes: &[u8], deals: usize, i: usize) -> HandEntry {
+    let b = &bytes[HEADER_LEN + deals * ENTRY_LEN + i * HAND_ENTRY_LEN..][..HAND_ENTRY_LEN];
+    HandEntry {
+        key: u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        lead: b[4],
+        votes: b[5],
+        score: i16::from_le_bytes([b[6], b[7]]),
+    }
+}
+
+/// Index of `want` among `count` entries sorted by `key_at`.
+fn search(count: usize, want: u64, key_at: impl Fn(usize) -> u64) -> Option<usize> {
+    let (mut lo, mut hi) = (0, count);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        match key_at(mid).cmp(&want) {
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+            std::cmp::Ordering::Equal => return Some(mid),
+        }
+    }
+    None
+}
+
+/// Binary search `bytes` (a valid book) for a deal.
+fn find(bytes: &[u8], leader_hand: i32, opponent_hand: i32) -> Option<BookEntry> {
+    let (deals, _) = check(bytes).ok()?;
+    search(deals, key(leader_hand, opponent_hand), |i| entry_at(bytes, i).key()).map(|i| entry_at(bytes, i))
+}
+
+/// Binary search `bytes` (a valid book) for a leader's suit profile.
+fn find_hand(bytes: &[u8], key: u32) -> Option<HandEntry> {
+    let (deals, hands) = check(bytes).ok()?;
+    search(hands, key as u64, |i| hand_entry_at(bytes, deals, i).key as u64).map(|i| hand_entry_at(bytes, deals, i))
+}
+
+/// The embedded entry for this deal, if any.
+pub fn probe(leader_hand: i32, opponent_hand: i32) -> Option<BookEntry> {
+    find(BOOK, leader_hand, opponent_hand)
+}
+
+/// The embedded entry for the leader's suit profile, if any.
+pub fn probe_hand(leader_hand: i32) -> Option<HandEntry> {
+    find_hand(BOOK, hand_key(leader_hand).0)
+}
+
+/// Number of embedded deal entries.
+pub fn len() -> usize {
+    check(BOOK).map_or(0, |(deals, _)| deals)
+}
+
+/// All embedded deal entries.
+pub fn entries() -> Vec<BookEntry> {
+    decode(BOOK).map(|b| b.deals).unwrap_or_default()
+}
+
+/// The whole embedded book.
+pub fn embedded() -> Book {
+    decode(BOOK).unwrap_or_default()
+}
+
+/// A book answer for a `choose_move` root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BookMove {
+    pub tile: i8,
+    /// Leader's perspective; exact for a deal entry, a mean for a hand entry
+    pub score: i16,
+    /// From the deal table (a perfect-play result)
+    pub exact: bool,
+}
+
+/// Book move for a `choose_move` root: only the opening lead of a full
+/// 14/14 deal, searched for points under the standard rules at full
+/// strength, with the book enabled. The deal table answers first, then the
+/// leader's suit profile; a `SearchLimits::SOLVE` search only takes the
+/// deal table, whose entries are solves. Caller holds the engine lock.
+pub(crate) fn root_move(
+    ai_hand: i32, human_hand: i32, left: i8, cons_pass: i32,
+    rules: &RuleSet, objective: Objective, limits: &SearchLimits,
+) -> Option<BookMove> {
+    let applies = is_enabled()
+        && left == 7
+        && cons_pass == 0
+        && ai_hand.count_ones() == 14
+        && human_hand.count_ones() == 14
+        && *rules == RuleSet::STANDARD
+        && objective == Objective::Points
+        && (*limits == SearchLimits::NONE || *limits == SearchLimits::SOLVE);
+    if !applies {
+        return None;
+    }
+    if let Some(e) = probe(ai_hand, human_hand) {
+        return Some(BookMove { tile: e.tile, score: e.score, exact: true });
+    }
+    if *limits == SearchLimits::SOLVE {
+        return None;
+    }
+    let e = probe_hand(ai_hand)?;
+    Some(BookMove { tile: e.tile_in(ai_hand)?, score: e.score, exact: false })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::deal;
+
+    fn entry(seed: u32, tile: i8, score: i16) -> BookEntry {
+        let [h, a] = deal(seed);
+        BookEntry { leader_hand: a, opponent_hand: h, tile, score }
+    }
+
+    #[test]
+    fn test_encode_decode_find() {
+        let deals = vec![entry(3, 5, -12), entry(1, 27, 40), entry(2, 0, 7), entry(1, 26, 41)];
+        let hands = vec![
+            HandEntry { key: 0x1234, lead: 8, votes: 3, score: 5 },
+            HandEntry { key: 0x0042, lead: 0, votes: 9, score: -2 },
+        ];
+        let bytes = encode(&Book { deals: deals.clone(), hands });
+        assert_eq!(bytes.len(), HEADER_LEN + 3 * ENTRY_LEN + 2 * HAND_ENTRY_LEN);
+        let back = decode(&bytes).unwrap();
+        assert_eq!((back.deals.len(), back.hands.len()), (3, 2));
+        assert!(back.deals.windows(2).all(|w| w[0].key() < w[1].key()));
+        assert_eq!(back.hands[0].key, 0x0042);
+        // The later duplicate replaced the earlier one
+        let e = deals[3];
+        assert_eq!(find(&bytes, e.leader_hand, e.opponent_hand), Some(e));
+        assert_eq!(find(&bytes, e.opponent_hand, e.leader_hand), None);
+        assert_eq!(find_hand(&bytes, 0x1234).map(|e| e.lead), Some(8));
+        assert_eq!(find_hand(&bytes, 0x1235), None);
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode(b"nonsense").is_err());
+    }
+
+    #[test]
+    fn test_hand_key() {
+        let [human, ai] = deal(5);
+        for hand in [human, ai] {
+            let (key, suits) = hand_key(hand);
+            let nibble = |rank: usize| key >> (4 * rank) & 0xf;
+            let doubles = (0..7).filter(|&p| hand & 1 << tile_id_to_index(p, p) != 0).count() as u32;
+            assert_eq!((0..7).map(|r| nibble(r) & 7).sum::<u32>(), 28 - doubles);
+            let profile = |rank: usize| (nibble(rank) & 7, nibble(rank) >> 3);
+            assert!((1..7).all(|r| profile(r - 1) >= profile(r)));
+            // Tiles round-trip through their suit ranks, if in the hand
+            let rank = |pip: i8| suits.iter().position(|&s| s == pip).unwrap();
+            for t in 0..NUM_TILES {
+                let e = HandEntry { key, lead: HandEntry::lead_code(rank(TILE_LOW[t]), rank(TILE_HIGH[t])), votes: 1, score: 0 };
+                assert_eq!(e.tile_in(hand), (hand & 1 << t != 0).then_some(t as i8));
+            }
+        }
+        // Swapping two suits relabels them, but leaves the profile alone
+        let swap = |hand: i32, a: i8, b: i8| (0..NUM_TILES).filter(|&t| hand & 1 << t != 0).fold(0, |h, t| {
+            let s = |p: i8| if p == a { b } else if p == b { a } else { p };
+            h | 1 << tile_id_to_index(s(TILE_LOW[t]), s(TILE_HIGH[t]))
+        });
+        let (key, suits) = hand_key(ai);
+        assert_eq!(hand_key(swap(ai, suits[0], suits[6])).0, key);
+    }
+
+    #[test]
+    fn test_embedded_book_is_valid() {
+        let book = decode(BOOK).unwrap();
+        assert_eq!(book.deals.len(), len());
+        for e in &book.deals {
+            assert_eq!(e.leader_hand & e.opponent_hand, 0);
+            assert_ne!(e.leader_hand & (1 << e.tile), 0);
+        }
+        assert!(book.hands.windows(2).all(|w| w[0].key < w[1].key));
+        assert!(book.hands.iter().all(|e| e.lead / 7 <= e.lead % 7 && e.votes > 0));
+    }
+
+    #[test]
+    fn test_hand_table_covers_unseen_deals() {
+        // The hand table was built from seeds above 1_000_000; most deals
+        // outside that range still find their leader's profile
+        let hits = (1..=200).filter(|&seed| {
+            let [human, ai] = deal(seed);
+            [human, ai].iter().all(|&hand| probe_hand(hand).and_then(|e| e.tile_in(hand)).is_some())
+        }).count();
+        assert!(hits >= 100, "{} of 200", hits);
+    }
+
+    #[test]
+    fn test_root_move_gating() {
+        let _guard = lock_engine();
+        let Some(e) = decode(BOOK).unwrap().deals.first().copied() else { return };
+        let (a, h) = (e.leader_hand, e.opponent_hand);
+        let std = RuleSet::STANDARD;
+        let none = SearchLimits::NONE;
+        let exact = Some(BookMove { tile: e.tile, score: e.score, exact: true });
+        assert_eq!(root_move(a, h, 7, 0, &std, Objective::Points, &none), exact);
+        assert_eq!(root_move(a, h, 3, 0, &std, Objective::Points, &none), None);
+        let eq = Objective::MatchEquity { ai_score: 0, human_score: 0 };
+        assert_eq!(root_move(a, h, 7, 0, &std, eq, &none), None);
+        let capped = SearchLimits { max_depth: 4, ..SearchLimits::NONE };
+        assert_eq!(root_move(a, h, 7, 0, &std, Objective::Points, &capped), None);
+        unsafe { G_BOOK_ENABLED = false; }
+        assert_eq!(root_move(a, h, 7, 0, &std, Objective::Points, &none), None);
+        unsafe { G_BOOK_ENABLED = true; }
+
+        assert_eq!(root_move(a, h, 7, 0, &std, Objective::Points, &SearchLimits::SOLVE), exact);
+
+        // Not in the deal table: the leader's profile answers, inexactly,
+        // and never a solve
+        let [human, ai] = deal(1_000_000);
+        if let Some(tile) = probe_hand(ai).and_then(|e| e.tile_in(ai)) {
+            let m = root_move(ai, human, 7, 0, &std, Objective::Points, &none).unwrap();
+            assert_eq!((m.tile, m.exact), (tile, false));
+            assert_eq!(root_move(ai, human, 7, 0, &std, Objective::Points, &SearchLimits::SOLVE), None);
+        }
+    }
+
+    #[test]
+    fn test_choose_move_plays_book_lead() {
+        let Some(e) = entries().first().copied() else { return };
+        let r = crate::search::choose_move(
+            e.leader_hand, e.opponent_hand, 7, 7, 0, 0, -1, 0, 0, -1, -1, 0, 0,
+            100.0, &RuleSet::STANDARD, Objective::Points, &SearchLimits::NONE,
+        );
+        assert_eq!((r.best_tile_idx, r.best_score, r.nodes, r.depth), (e.tile, e.score as i32, 0, 28));
+    }
+}
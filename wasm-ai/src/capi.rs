@@ -0,0 +1,434 @@
+//! C ABI (feature `capi`) for native shells that embed the engine without a
+//! WebView. The header `include/dominos_ai.h` is generated from this file:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/dominos_ai.h src/capi.rs
+//! ```
+//!
+//! `cargo build --release --features capi` gives the shared library; for a
+//! static one (iOS), `cargo rustc --release --features capi --crate-type
+//! staticlib`. `capi/test.sh` builds it and runs `capi/test_capi.c`.
+//!
+//! Ownership rules:
+//! - `dominos_engine_new` returns an engine the caller owns; release it with
+//!   `dominos_engine_free` exactly once. Engines are independent, but
+//!   searches are serialized process-wide.
+//! - Tile arrays and strings passed in are only read during the call.
+//! - `dominos_engine_last_error` returns a string owned by the engine, valid
+//!   until the next call on that engine; do not free it.
+//! - `dominos_choose_move_json` returns a string the caller owns; release it
+//!   with `dominos_string_free`.
+//!
+//! Functions returning `int32_t` return `DOMINOS_OK` (0) or a negative
+//! `DOMINOS_ERR_*` code, with the message in `dominos_engine_last_error`.
+
+use std::ffi::{c_char, CStr, CString};
+
+use crate::equity::Objective;
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::{tile_id_to_index, NUM_TILES, TILE_HIGH, TILE_LOW};
+use crate::notation::Position;
+use crate::rules::RuleSet;
+use crate::skill::{self, SkillLevel};
+
+pub const DOMINOS_OK: i32 = 0;
+/// A required pointer was null
+pub const DOMINOS_ERR_NULL: i32 = -1;
+/// Bad tiles, board ends, notation or setting
+pub const DOMINOS_ERR_INVALID: i32 = -2;
+/// No position set, or the hand is already over
+pub const DOMINOS_ERR_STATE: i32 = -3;
+
+/// `end` of a pass in `DominosMove`
+pub const DOMINOS_PASS: i8 = -1;
+
+/// A tile as two pips, 0-6, in either order.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct DominosTile {
+    pub low: u8,
+    pub high: u8,
+}
+
+/// The engine's move and its search result.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DominosMove {
+    /// Tile to play, `low <= high`; both 0 for a pass
+    pub low: u8,
+    pub high: u8,
+    /// 0 = left, 1 = right, `DOMINOS_PASS`; 0 on the empty board
+    pub end: i8,
+    /// Hand points for the side to move (match equity with
+    /// `dominos_engine_set_objective(engine, 1)`)
+    pub score: i32,
+    pub depth: i32,
+    pub nodes: u32,
+}
+
+/// Opaque engine handle: a position, settings and the last result.
+pub struct DominosEngine {
+    pos: Option<Position>,
+    budget: f64,
+    level: u8,
+    equity: bool,
+    rules: RuleSet,
+    result: Option<DominosMove>,
+    error: CString,
+}
+
+impl DominosEngine {
+    fn fail(&mut self, code: i32, msg: &str) -> i32 {
+        self.error = CString::new(msg.replace('\0', " ")).unwrap_or_default();
+        code
+    }
+
+    fn choose(&mut self) -> Result<DominosMove, (i32, String)> {
+        let pos = self.pos.as_ref().ok_or((DOMINOS_ERR_STATE, "no position set".to_string()))?;
+        let state = &pos.state;
+        let mover = state.to_move;
+        if state.result_for(mover, &self.rules).is_some() {
+            return Err((DOMINOS_ERR_STATE, "the hand is over".to_string()));
+        }
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        if state.legal_moves(&mut buf) == 0 {
+            return Ok(DominosMove { end: DOMINOS_PASS, ..DominosMove::default() });
+        }
+
+        let (me, opp) = (pos.score[mover as usize], pos.score[1 - mover as usize]);
+        let objective = if self.equity {
+            Objective::MatchEquity { ai_score: me, human_score: opp }
+        } else {
+            Objective::Points
+        };
+        let skill = SkillLevel::new(self.level);
+        let r = if skill.is_full_strength() {
+            state.search(mover, self.budget, &self.rules, objective, me - opp)
+        } else {
+            let seed = crate::search::now_ms() as u64 as u32;
+            skill::choose_move_at_level(state, &skill, self.budget, &self.rules, objective, me - opp, seed)
+        };
+        let (tile, end) = state.find_legal(r.best_tile_idx, r.best_end).unwrap_or(buf[0]);
+        Ok(DominosMove {
+            low: TILE_LOW[tile as usize] as u8,
+            high: TILE_HIGH[tile as usize] as u8,
+            end: if state.left == 7 { 0 } else { end },
+            score: r.best_score,
+            depth: r.depth,
+            nodes: r.nodes,
+        })
+    }
+}
+
+/// Hand bitmask of `count` tiles at `tiles`.
+unsafe fn hand_from(tiles: *const DominosTile, count: usize) -> Result<i32, String> {
+    if count == 0 {
+        return Ok(0);
+    }
+    if tiles.is_null() {
+        return Err("null tile array".to_string());
+    }
+    let mut hand = 0;
+    for t in std::slice::from_raw_parts(tiles, count) {
+        if t.low > 6 || t.high > 6 {
+            return Err(format!("bad tile [{}-{}]", t.low, t.high));
+        }
+        let bit = 1 << tile_id_to_index(t.low as i8, t.high as i8);
+        if hand & bit != 0 {
+            return Err(format!("[{}-{}] appears twice", t.low.min(t.high), t.low.max(t.high)));
+        }
+        hand |= bit;
+    }
+    Ok(hand)
+}
+
+/// New engine with no position, a 1000 ms budget, level 10, points
+/// objective and standard rules. Free it with `dominos_engine_free`.
+#[no_mangle]
+pub extern "C" fn dominos_engine_new() -> *mut DominosEngine {
+    Box::into_raw(Box::new(DominosEngine {
+        pos: None,
+        budget: 1000.0,
+        level: skill::MAX_LEVEL,
+        equity: false,
+        rules: RuleSet::STANDARD,
+        result: None,
+        error: CString::default(),
+    }))
+}
+
+/// Release an engine from `dominos_engine_new`. Null is ignored.
+///
+/// # Safety
+/// `engine` must be null or a live engine; it is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_free(engine: *mut DominosEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Set the position with the engine's side to move: its own tiles, the
+/// opponent's, and the board ends (`left < 0` for the empty board). Clears
+/// the puppeteer history and match score.
+///
+/// # Safety
+/// `engine` must be a live engine; each tile pointer must point at `count`
+/// tiles (it may be null when the count is 0).
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_set_position(
+    engine: *mut DominosEngine,
+    my_tiles: *const DominosTile, my_count: usize,
+    opp_tiles: *const DominosTile, opp_count: usize,
+    left: i8, right: i8,
+) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    let hands = match (hand_from(my_tiles, my_count), hand_from(opp_tiles, opp_count)) {
+        (Ok(mine), Ok(opp)) if mine & opp == 0 => [opp, mine],
+        (Err(e), _) | (_, Err(e)) => return engine.fail(DOMINOS_ERR_INVALID, &e),
+        _ => return engine.fail(DOMINOS_ERR_INVALID, "a tile is in both hands"),
+    };
+    let mut state = HandState::new(hands[HUMAN as usize], hands[AI as usize], AI);
+    if left >= 0 {
+        if left > 6 || !(0..=6).contains(&right) {
+            return engine.fail(DOMINOS_ERR_INVALID, "board ends must be 0-6");
+        }
+        state.left = left;
+        state.right = right;
+    }
+    engine.pos = Some(Position::from_state(state));
+    engine.result = None;
+    DOMINOS_OK
+}
+
+/// Set the position from a one-line `notation` string; either side may be
+/// to move.
+///
+/// # Safety
+/// `engine` must be a live engine and `text` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_set_notation(engine: *mut DominosEngine, text: *const c_char) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    if text.is_null() {
+        return engine.fail(DOMINOS_ERR_NULL, "null notation");
+    }
+    let parsed = CStr::from_ptr(text).to_str().map_err(|e| e.to_string()).and_then(Position::parse);
+    match parsed {
+        Ok(pos) => {
+            engine.pos = Some(pos);
+            engine.result = None;
+            DOMINOS_OK
+        }
+        Err(e) => engine.fail(DOMINOS_ERR_INVALID, &e),
+    }
+}
+
+/// Search time per move in ms (default 1000).
+///
+/// # Safety
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_set_budget(engine: *mut DominosEngine, budget_ms: u32) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    if budget_ms == 0 {
+        return engine.fail(DOMINOS_ERR_INVALID, "budget must be positive");
+    }
+    engine.budget = budget_ms as f64;
+    DOMINOS_OK
+}
+
+/// Strength 1-10 (default 10, the full engine).
+///
+/// # Safety
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_set_level(engine: *mut DominosEngine, level: u8) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    if !(skill::MIN_LEVEL..=skill::MAX_LEVEL).contains(&level) {
+        return engine.fail(DOMINOS_ERR_INVALID, "level must be 1-10");
+    }
+    engine.level = level;
+    DOMINOS_OK
+}
+
+/// 0 plays for hand points (default), 1 for match-win probability from
+/// the notation's match score.
+///
+/// # Safety
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_set_objective(engine: *mut DominosEngine, equity: i32) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    engine.equity = equity != 0;
+    DOMINOS_OK
+}
+
+/// Rules in DGN form, e.g. `ghost13=off,target=150`; unnamed rules keep
+/// their standard values.
+///
+/// # Safety
+/// `engine` must be a live engine and `rules` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_set_rules(engine: *mut DominosEngine, rules: *const c_char) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    if rules.is_null() {
+        return engine.fail(DOMINOS_ERR_NULL, "null rules");
+    }
+    let parsed = CStr::from_ptr(rules).to_str().map_err(|e| e.to_string()).and_then(crate::dgn::parse_rules);
+    match parsed {
+        Ok(r) => {
+            engine.rules = r;
+            DOMINOS_OK
+        }
+        Err(e) => engine.fail(DOMINOS_ERR_INVALID, &e),
+    }
+}
+
+/// Search the position for the side to move; blocks for up to the budget.
+/// Read the move with `dominos_engine_result`.
+///
+/// # Safety
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_choose_move(engine: *mut DominosEngine) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    match engine.choose() {
+        Ok(mv) => {
+            engine.result = Some(mv);
+            DOMINOS_OK
+        }
+        Err((code, msg)) => engine.fail(code, &msg),
+    }
+}
+
+/// Copy the last chosen move into `*out`.
+///
+/// # Safety
+/// `engine` must be a live engine and `out` writable.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_result(engine: *mut DominosEngine, out: *mut DominosMove) -> i32 {
+    let Some(engine) = engine.as_mut() else { return DOMINOS_ERR_NULL };
+    if out.is_null() {
+        return engine.fail(DOMINOS_ERR_NULL, "null result pointer");
+    }
+    match engine.result {
+        Some(mv) => {
+            *out = mv;
+            DOMINOS_OK
+        }
+        None => engine.fail(DOMINOS_ERR_STATE, "no move chosen for this position"),
+    }
+}
+
+/// Message of the last failed call on `engine` ("" if none). Owned by the
+/// engine and valid until its next call.
+///
+/// # Safety
+/// `engine` must be null or a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_engine_last_error(engine: *const DominosEngine) -> *const c_char {
+    match engine.as_ref() {
+        Some(engine) => engine.error.as_ptr(),
+        None => c"null engine".as_ptr(),
+    }
+}
+
+/// `wasm_choose_move` for C: `SearchInput` JSON (or notation) in,
+/// `SearchOutput` JSON out. The caller owns the result and releases it with
+/// `dominos_string_free`; null if `input` is null or not UTF-8.
+///
+/// # Safety
+/// `input` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_choose_move_json(input: *const c_char) -> *mut c_char {
+    if input.is_null() {
+        return std::ptr::null_mut();
+    }
+    match CStr::from_ptr(input).to_str() {
+        Ok(text) => CString::new(crate::wasm_choose_move(text)).map_or(std::ptr::null_mut(), CString::into_raw),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Release a string returned by `dominos_choose_move_json`. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a string from this library not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn dominos_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SIX_OR_LOCK;
+
+    #[test]
+    fn test_engine_round_trip() {
+        unsafe {
+            let engine = dominos_engine_new();
+            let mine = [DominosTile { low: 6, high: 6 }, DominosTile { low: 1, high: 0 }];
+            let opp = [DominosTile { low: 5, high: 6 }];
+            let mut mv = DominosMove::default();
+            assert_eq!(dominos_engine_result(engine, &mut mv), DOMINOS_ERR_STATE);
+            assert_eq!(dominos_engine_set_position(engine, mine.as_ptr(), 2, opp.as_ptr(), 1, -1, -1), DOMINOS_OK);
+            assert_eq!(dominos_engine_set_budget(engine, 200), DOMINOS_OK);
+            assert_eq!(dominos_engine_choose_move(engine), DOMINOS_OK);
+            assert_eq!(dominos_engine_result(engine, &mut mv), DOMINOS_OK);
+            assert_eq!((mv.low, mv.high, mv.score), (6, 6, -1));
+
+            // Both players hold [5-6]
+            assert_eq!(dominos_engine_set_position(engine, opp.as_ptr(), 1, opp.as_ptr(), 1, -1, -1),
+                       DOMINOS_ERR_INVALID);
+            assert_eq!(CStr::from_ptr(dominos_engine_last_error(engine)).to_str(), Ok("a tile is in both hands"));
+            dominos_engine_free(engine);
+
+            let text = CString::new(SIX_OR_LOCK).unwrap();
+            let out = dominos_choose_move_json(text.as_ptr());
+            assert!(CStr::from_ptr(out).to_str().unwrap().starts_with(r#"{"tileId":"6-6""#));
+            dominos_string_free(out);
+        }
+    }
+
+    #[test]
+    fn test_notation_and_rules() {
+        unsafe {
+            let engine = dominos_engine_new();
+            let error = || CStr::from_ptr(dominos_engine_last_error(engine)).to_str().unwrap().to_string();
+            let mut mv = DominosMove::default();
+
+            // Ghost 13 charges a stranded [0-0] as 13: get rid of it first
+            let ghost = c"12/00011144 0225511665533666644333322226600555544003311442 a 0 h24@02,a@04 0-0";
+            assert_eq!(dominos_engine_set_notation(engine, ghost.as_ptr()), DOMINOS_OK);
+            assert_eq!(dominos_engine_choose_move(engine), DOMINOS_OK);
+            assert_eq!(dominos_engine_result(engine, &mut mv), DOMINOS_OK);
+            assert_eq!((mv.low, mv.high, mv.end, mv.score), (0, 0, 0, -11));
+            assert_eq!(dominos_engine_set_rules(engine, c"ghost13=off".as_ptr()), DOMINOS_OK);
+            assert_eq!(dominos_engine_choose_move(engine), DOMINOS_OK);
+            assert_eq!(dominos_engine_result(engine, &mut mv), DOMINOS_OK);
+            assert_eq!((mv.low, mv.high, mv.end, mv.score), (0, 1, 0, -10));
+
+            assert_eq!(dominos_engine_set_rules(engine, c"ghost13=maybe".as_ptr()), DOMINOS_ERR_INVALID);
+            assert_eq!(error(), "bad rule value 'ghost13=maybe'");
+            assert_eq!(dominos_engine_set_rules(engine, std::ptr::null()), DOMINOS_ERR_NULL);
+            assert_eq!(error(), "null rules");
+
+            // A bad position leaves the last good one in place
+            assert_eq!(dominos_engine_set_notation(engine, c"01/01 - a 0 -".as_ptr()), DOMINOS_ERR_INVALID);
+            assert_eq!(error(), "a tile is in both hands");
+            assert_eq!(dominos_engine_set_notation(engine, c"01/66 - a 0".as_ptr()), DOMINOS_ERR_INVALID);
+            assert_eq!(error(), "expected 5 or 6 fields, got 4");
+            assert_eq!(dominos_engine_set_notation(engine, c"01/66 - a 0 - \xff".as_ptr()), DOMINOS_ERR_INVALID);
+            assert!(error().contains("utf-8"));
+            assert_eq!(dominos_engine_set_notation(engine, std::ptr::null()), DOMINOS_ERR_NULL);
+            assert_eq!(error(), "null notation");
+            assert_eq!(dominos_engine_choose_move(engine), DOMINOS_OK);
+            assert_eq!(dominos_engine_result(engine, &mut mv), DOMINOS_OK);
+            assert_eq!((mv.low, mv.high), (0, 1));
+            dominos_engine_free(engine);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Seeded 14/14 deals and exact deal evaluation.
+//!
+//! The deal for a seed is fully specified so any port reproduces it:
+//!
+//! 1. State = `seed × 0x9E3779B9` (wrapping u32), with the low bit forced
+//!    on (xorshift has a fixed point at 0).
+//!    JS: `(Math.imul(seed, 0x9E3779B9) | 1) >>> 0`.
+//! 2. `next()` is xorshift32: `x ^= x << 13; x ^= x >>> 17; x ^= x << 5`.
+//! 3. The 28 tiles start in `createTileSet()` order (0-0, 0-1, … 6-6, the
+//!    same as tile index order) and are Fisher–Yates shuffled from the top:
+//!    for i = 27 down to 1, swap i with `next() % (i + 1)`.
+//! 4. The first 14 tiles go to the human, the rest to the AI, as in
+//!    `GameEngine.dealHand`.
+//!
+//! `wasm_deal` exposes the same deal to the browser; `advise_lead` (and
+//! `wasm_advise_lead`) answers the first-hand lead choice.
+
+use crate::equity::Objective;
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::NUM_TILES;
+use crate::rules::RuleSet;
+use crate::search::SearchLimits;
+use crate::zobrist::Xorshift32;
+
+/// Tiles per hand in the two-player game.
+pub const HAND_SIZE: usize = 14;
+
+/// PRNG state for `seed` (step 1 above).
+fn seed_state(seed: u32) -> u32 {
+    seed.wrapping_mul(0x9E37_79B9) | 1
+}
+
+/// Deal for `seed` (any value, including 0). Returns [human, ai].
+pub fn deal(seed: u32) -> [i32; 2] {
+    deal_from_rng(&mut Xorshift32::new(seed_state(seed)))
+}
+
+/// Shuffle the 28 tiles and deal 14/14 from an existing stream, for callers
+/// that draw many deals in a row. Returns [human, ai].
+pub(crate) fn deal_from_rng(rng: &mut Xorshift32) -> [i32; 2] {
+    let mut tiles: [usize; NUM_TILES] = core::array::from_fn(|i| i);
+    for i in (1..NUM_TILES).rev() {
+        let j = (rng.next() % (i as u32 + 1)) as usize;
+        tiles.swap(i, j);
+    }
+    let mut hands = [0i32; 2];
+    for (k, &t) in tiles.iter().enumerate() {
+        hands[(k >= HAND_SIZE) as usize] |= 1 << t;
+    }
+    hands
+}
+
+/// Exact result of one leader's hand, from the AI's perspective.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeaderOutcome {
+    pub score: i32,
+    /// Best opening tile for the leader
+    pub best_tile: i8,
+    /// The search reached the end of the hand (otherwise `score` is the
+    /// deepest estimate within the budget)
+    pub solved: bool,
+}
+
+/// Both possible leaders of a deal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DealEvaluation {
+    pub human_leads: LeaderOutcome,
+    pub ai_leads: LeaderOutcome,
+}
+
+impl DealEvaluation {
+    /// Expected hand result for the AI with the leader a coin flip.
+    pub fn expected(&self) -> f64 {
+        (self.human_leads.score + self.ai_leads.score) as f64 / 2.0
+    }
+
+    pub fn solved(&self) -> bool {
+        self.human_leads.solved && self.ai_leads.solved
+    }
+
+    /// Neither seat is favoured by more than `margin` points on average.
+    pub fn is_balanced(&self, margin: f64) -> bool {
+        self.expected().abs() <= margin
+    }
+}
+
+/// Solve the hand once with each side leading, both sides playing
+/// perfectly with full knowledge of both hands. `time_budget` (ms) applies
+/// to each of the two searches.
+pub fn evaluate_deal(hands: [i32; 2], time_budget: f64, rules: &RuleSet) -> DealEvaluation {
+    let solve = |leader: i8| {
+        let pos = HandState::new(hands[HUMAN as usize], hands[AI as usize], leader);
+        let r = pos.search_limited(leader, time_budget, rules, Objective::Points, 0, &SearchLimits::SOLVE);
+        LeaderOutcome {
+            score: if leader == AI { r.best_score } else { -r.best_score },
+            best_tile: r.best_tile_idx,
+            solved: r.depth >= pos.tiles_left(),
+        }
+    };
+    DealEvaluation { human_leads: solve(HUMAN), ai_leads: solve(AI) }
+}
+
+/// Whether to lead or defer the first hand (Game_Req §4).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LeadAdvice {
+    /// [human, ai]; the human hand is filled in when only the AI's was given
+    pub hands: [i32; 2],
+    pub evaluation: DealEvaluation,
+    /// Points the leader gains over deferring: `ai_leads - human_leads`
+    /// from the AI's side, and the same number from the human's side, since
+    /// both scores flip sign.
+    pub lead_value: i32,
+}
+
+impl LeadAdvice {
+    /// Advice for whichever side is choosing; false on a tie.
+    pub fn should_lead(&self) -> bool {
+        self.lead_value > 0
+    }
+}
+
+/// Search the deal with each side leading. With no boneyard the human's hand
+/// is the complement of a full 14-tile AI hand, so it may be omitted.
+pub fn advise_lead(
+    ai_hand: i32, human_hand: Option<i32>, time_budget: f64, rules: &RuleSet,
+) -> Result<LeadAdvice, String> {
+    let human = match human_hand {
+        Some(h) => h,
+        None if ai_hand.count_ones() as usize == HAND_SIZE => ai_hand ^ ((1 << NUM_TILES) - 1),
+        None => return Err(format!("need {} AI tiles to infer the human hand", HAND_SIZE)),
+    };
+    if human & ai_hand != 0 {
+        return Err("hands share a tile".to_string());
+    }
+    if human == 0 || ai_hand == 0 {
+        return Err("both hands need tiles".to_string());
+    }
+    let hands = [human, ai_hand];
+    let evaluation = evaluate_deal(hands, time_budget, rules);
+    Ok(LeadAdvice {
+        hands,
+        evaluation,
+        lead_value: evaluation.ai_leads.score - evaluation.human_leads.score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{bit, six_or_lock};
+    use crate::lookup::tile_id_to_index;
+
+    #[test]
+    fn test_deal_partitions_set() {
+        let mut rng = Xorshift32::new(42);
+        let [h, a] = deal_from_rng(&mut rng);
+        assert_eq!(h & a, 0);
+        assert_eq!(h | a, (1 << NUM_TILES) - 1);
+        assert_eq!(h.count_ones(), 14);
+        assert_eq!(deal(0), deal(0));
+        assert_ne!(deal(0), deal(1));
+    }
+
+    #[test]
+    fn test_deal_is_pinned() {
+        // Reference values for ports of the documented algorithm
+        assert_eq!(seed_state(1), 0x9E37_79B9);
+        assert_eq!(seed_state(0), 1);
+        let mut rng = Xorshift32::new(1);
+        assert_eq!(rng.next(), 270_369);
+        assert_eq!(deal(1), [0x85e_f843, 0x7a1_07bc]);
+    }
+
+    #[test]
+    fn test_evaluate_small_deal() {
+        // AI: [6-6] [0-1], human: [5-6].
+        //   AI leads: [6-6], human dominoes with [5-6] → −1
+        //   human leads: dominoes at once → −13
+        let hands = six_or_lock().hands;
+        let e = evaluate_deal(hands, 1000.0, &RuleSet::STANDARD);
+        assert!(e.solved());
+        assert_eq!(e.ai_leads.score, -1);
+        assert_eq!(e.ai_leads.best_tile, tile_id_to_index(6, 6) as i8);
+        assert_eq!(e.human_leads.score, -13);
+        assert_eq!(e.expected(), -7.0);
+        assert!(e.is_balanced(7.0));
+        assert!(!e.is_balanced(6.5));
+    }
+
+    #[test]
+    fn test_evaluate_deal_is_a_solve() {
+        // A deal the book solved from both sides, whose leaders' suit
+        // profiles the hand table also covers: the averages must not answer
+        let book = crate::book::embedded();
+        let e = book.deals.iter().find(|e| {
+            book.deals.iter().any(|o| (o.leader_hand, o.opponent_hand) == (e.opponent_hand, e.leader_hand))
+                && [e.leader_hand, e.opponent_hand].iter().all(|&h| crate::book::probe_hand(h).is_some())
+        }).expect("a deal solved from both sides");
+        let ev = evaluate_deal([e.opponent_hand, e.leader_hand], 100.0, &RuleSet::STANDARD);
+        assert!(ev.solved());
+        assert_eq!((ev.ai_leads.best_tile, ev.ai_leads.score), (e.tile, e.score as i32));
+    }
+
+    #[test]
+    fn test_advise_lead() {
+        let [human, ai] = six_or_lock().hands;
+        let a = advise_lead(ai, Some(human), 1000.0, &RuleSet::STANDARD).unwrap();
+        // Leading costs the AI 1 instead of 13
+        assert_eq!(a.lead_value, 12);
+        assert!(a.should_lead());
+
+        // Human hand inferred from a full AI hand
+        let [human, ai] = deal(3);
+        let a = advise_lead(ai, None, 20.0, &RuleSet::STANDARD).unwrap();
+        assert_eq!(a.hands, [human, ai]);
+        assert!(advise_lead(bit(6, 6), None, 20.0, &RuleSet::STANDARD).is_err());
+        assert!(advise_lead(bit(6, 6), Some(bit(6, 6)), 20.0, &RuleSet::STANDARD).is_err());
+    }
+}
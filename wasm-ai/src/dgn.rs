@@ -0,0 +1,677 @@
+//! DGN, a text game record for one hand (PGN-style tags plus a move list).
+//!
+//! ```text
+//! [DGN "1"]
+//! [Variant "standard"]
+//! [Human "Alice"]
+//! [AI "dominos-ai level 10"]
+//! [Rules "ghost13=on,puppeteer=on,block=double,domino=loserPips,target=100"]
+//! [Seed "17"]
+//! [MatchScore "40-25"]
+//! [Date "2026.10.18"]
+//! [Leader "ai"]
+//! [HumanHand "01 05 ..."]
+//! [AIHand "66 ..."]
+//! [Result "ai 23"]
+//!
+//! a:66 h:56R a:36L h:pass a:13L ...
+//! ```
+//!
+//! - `DGN` (the format version) must come first. Only `Leader` and the two
+//!   hands are required; other tags default to the standard rules, a 0-0
+//!   score and an unfinished result (`*`). Unknown tags are kept.
+//! - `Variant` is `standard` (14 tiles each) or `draw` (9 each and a
+//!   10-tile boneyard, listed in draw order by `Boneyard` when known).
+//! - `MatchScore` is `<human>-<ai>` before the hand; `Result` is the winner
+//!   and the points of the hand.
+//! - Tiles are two pips (`05` = [0-5]). Moves are `<who>:<action>` with
+//!   `who` `h` or `a` and the action a placement (`35L`, `35R`, or `35`
+//!   when only one end fits), `pass`, or `draw:35`.
+//!
+//! A file may hold several games, one after the other.
+
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::{tile_id_to_index, NUM_TILES, TILE_HIGH, TILE_LOW, ZERO_SUIT_NO_00};
+use crate::rules::{BlockScoring, DominoScoring, RuleSet};
+
+pub const VERSION: &str = "1";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// 14 tiles each, no boneyard
+    Standard,
+    /// 9 tiles each; a side with no legal move draws until it can play
+    Draw,
+}
+
+impl Variant {
+    pub fn hand_size(self) -> u32 {
+        match self {
+            Variant::Standard => 14,
+            Variant::Draw => 9,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Draw => "draw",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// `end` 0 = left, 1 = right, -1 = unspecified (only one way to play it)
+    Place { tile: i8, end: i8 },
+    Pass,
+    Draw { tile: i8 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DgnMove {
+    pub who: i8,
+    pub action: Action,
+}
+
+impl DgnMove {
+    /// A move token: `<who>:<action>`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let bad = || format!("bad move '{}'", s);
+        let (who, action) = s.split_once(':').ok_or_else(bad)?;
+        let who = match who {
+            "h" => HUMAN,
+            "a" => AI,
+            _ => return Err(bad()),
+        };
+        let action = if action == "pass" {
+            Action::Pass
+        } else if let Some(t) = action.strip_prefix("draw:") {
+            Action::Draw { tile: parse_tile(t)? }
+        } else {
+            let (tile, end) = match action.as_bytes().last() {
+                Some(b'L') => (&action[..action.len() - 1], 0),
+                Some(b'R') => (&action[..action.len() - 1], 1),
+                _ => (action, -1),
+            };
+            Action::Place { tile: parse_tile(tile).map_err(|_| bad())?, end }
+        };
+        Ok(DgnMove { who, action })
+    }
+
+    pub fn to_text(&self) -> String {
+        let who = if self.who == AI { 'a' } else { 'h' };
+        match self.action {
+            Action::Place { tile, end } => {
+                let end = match end {
+                    0 => "L",
+                    1 => "R",
+                    _ => "",
+                };
+                format!("{}:{}{}", who, tile_text(tile), end)
+            }
+            Action::Pass => format!("{}:pass", who),
+            Action::Draw { tile } => format!("{}:draw:{}", who, tile_text(tile)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DgnGame {
+    pub variant: Variant,
+    /// Player names, `[human, ai]`
+    pub players: [String; 2],
+    pub rules: RuleSet,
+    pub seed: Option<u32>,
+    /// Match score before the hand, `[human, ai]`
+    pub match_score: [i32; 2],
+    pub date: Option<String>,
+    pub leader: i8,
+    /// Dealt hands, `[human, ai]`
+    pub hands: [i32; 2],
+    /// Boneyard in draw order; empty when the order is not recorded
+    pub boneyard: Vec<i8>,
+    pub moves: Vec<DgnMove>,
+    /// Hand result from the AI's side (`None` = unfinished)
+    pub result: Option<i32>,
+    /// Unrecognized tags, in file order
+    pub extra_tags: Vec<(String, String)>,
+}
+
+impl DgnGame {
+    /// An empty standard-rules record for `hands`.
+    pub fn new(hands: [i32; 2], leader: i8) -> Self {
+        DgnGame {
+            variant: Variant::Standard,
+            players: [String::new(), String::new()],
+            rules: RuleSet::STANDARD,
+            seed: None,
+            match_score: [0, 0],
+            date: None,
+            leader,
+            hands,
+            boneyard: Vec::new(),
+            moves: Vec::new(),
+            result: None,
+            extra_tags: Vec::new(),
+        }
+    }
+
+    /// Parse a file holding exactly one game.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut games = parse_all(text)?;
+        match games.len() {
+            1 => Ok(games.remove(0)),
+            n => Err(format!("expected one game, found {}", n)),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut tags = vec![
+            ("DGN".to_string(), VERSION.to_string()),
+            ("Variant".to_string(), self.variant.name().to_string()),
+        ];
+        let mut tag = |name: &str, value: String| tags.push((name.to_string(), value));
+        if !self.players[0].is_empty() {
+            tag("Human", self.players[0].clone());
+        }
+        if !self.players[1].is_empty() {
+            tag("AI", self.players[1].clone());
+        }
+        tag("Rules", rules_text(&self.rules));
+        if let Some(seed) = self.seed {
+            tag("Seed", seed.to_string());
+        }
+        tag("MatchScore", format!("{}-{}", self.match_score[0], self.match_score[1]));
+        if let Some(date) = &self.date {
+            tag("Date", date.clone());
+        }
+        tag("Leader", side(self.leader).to_string());
+        tag("HumanHand", tiles_text((0..NUM_TILES as i8).filter(|&t| self.hands[0] & 1 << t != 0)));
+        tag("AIHand", tiles_text((0..NUM_TILES as i8).filter(|&t| self.hands[1] & 1 << t != 0)));
+        if !self.boneyard.is_empty() {
+            tag("Boneyard", tiles_text(self.boneyard.iter().copied()));
+        }
+        tag("Result", match self.result {
+            None => "*".to_string(),
+            Some(sc) if sc < 0 => format!("human {}", -sc),
+            Some(sc) => format!("ai {}", sc),
+        });
+        tags.extend(self.extra_tags.iter().cloned());
+
+        let mut out = String::new();
+        for (name, value) in &tags {
+            out += &format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""));
+        }
+        out.push('\n');
+        let mut line = String::new();
+        for mv in &self.moves {
+            let token = mv.to_text();
+            if !line.is_empty() && line.len() + 1 + token.len() > 79 {
+                out += &line;
+                out.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += &token;
+        }
+        out += &line;
+        out.push('\n');
+        out
+    }
+
+    /// Replay the moves through the rules: right side to move, tiles held,
+    /// placements legal, passes and draws only without a legal move, nothing
+    /// after the hand ends, and `result` (if set) matching the outcome.
+    pub fn validate(&self) -> Result<Replay, String> {
+        let size = self.variant.hand_size();
+        for (who, &hand) in self.hands.iter().enumerate() {
+            if hand.count_ones() != size {
+                return Err(format!("{} hand has {} tiles, {} expected", side(who as i8),
+                                   hand.count_ones(), size));
+            }
+        }
+        if self.hands[0] & self.hands[1] != 0 {
+            return Err("hands share a tile".to_string());
+        }
+        let all = (1i32 << NUM_TILES) - 1;
+        let mut boneyard = all & !(self.hands[0] | self.hands[1]);
+        if self.variant == Variant::Standard {
+            if !self.boneyard.is_empty() {
+                return Err("the standard variant has no boneyard".to_string());
+            }
+        } else if !self.boneyard.is_empty()
+            && (self.boneyard.len() != boneyard.count_ones() as usize
+                || self.boneyard.iter().fold(0, |m, &t| m | 1 << t) != boneyard)
+        {
+            return Err("boneyard does not hold the undealt tiles".to_string());
+        }
+
+        let mut pos = HandState::new(self.hands[0], self.hands[1], self.leader);
+        let mut result = None;
+        let mut draws = 0;
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        for (i, mv) in self.moves.iter().enumerate() {
+            let err = |msg: String| Err(format!("move {} ({}): {}", i + 1, mv.to_text(), msg));
+            if result.is_some() {
+                return err("the hand is already over".to_string());
+            }
+            if mv.who != pos.to_move {
+                return err(format!("{} is to move", side(pos.to_move)));
+            }
+            let n = pos.legal_moves(&mut buf);
+            match mv.action {
+                Action::Place { tile, end } => {
+                    if pos.hands[mv.who as usize] & 1 << tile == 0 {
+                        return err("tile not in hand".to_string());
+                    }
+                    let played = if end >= 0 {
+                        // Keep the recorded end when both ends match, so
+                        // later L/R refer to the same side
+                        pos.find_legal(tile, end).map(|(t, _)| (t, end))
+                    } else {
+                        match (pos.find_legal(tile, 0), pos.find_legal(tile, 1)) {
+                            (Some(a), Some(b)) if a != b && pos.left != pos.right => {
+                                return err("fits both ends; say L or R".to_string());
+                            }
+                            (a, b) => a.or(b),
+                        }
+                    };
+                    let Some((tile, end)) = played else {
+                        return err("illegal placement".to_string());
+                    };
+                    pos.place(tile, end);
+                    result = self.outcome(&pos, boneyard);
+                }
+                Action::Pass => {
+                    if n > 0 {
+                        return err(format!("pass with {} legal moves", n));
+                    }
+                    if boneyard != 0 && self.variant == Variant::Draw {
+                        return err("pass while the boneyard has tiles".to_string());
+                    }
+                    pos.pass();
+                    if pos.cons_pass >= 2 {
+                        result = self.outcome(&pos, boneyard);
+                    }
+                }
+                Action::Draw { tile } => {
+                    if self.variant != Variant::Draw {
+                        return err("no boneyard in the standard variant".to_string());
+                    }
+                    if n > 0 {
+                        return err(format!("draw with {} legal moves", n));
+                    }
+                    if boneyard & 1 << tile == 0 {
+                        return err("tile not in the boneyard".to_string());
+                    }
+                    if let Some(&next) = self.boneyard.get(draws) {
+                        if next != tile {
+                            return err(format!("the next boneyard tile is {}", tile_text(next)));
+                        }
+                    }
+                    boneyard ^= 1 << tile;
+                    pos.hands[mv.who as usize] |= 1 << tile;
+                    draws += 1;
+                }
+            }
+        }
+
+        match (self.result, result) {
+            (Some(want), Some(got)) if want != got => {
+                Err(format!("result is {} but the moves give {}", want, got))
+            }
+            (Some(_), None) => Err("result given but the hand is not over".to_string()),
+            _ => Ok(Replay { state: pos, boneyard, result }),
+        }
+    }
+
+    /// Hand result from the AI's side once the hand has ended.
+    fn outcome(&self, pos: &HandState, boneyard: i32) -> Option<i32> {
+        let over = pos.hands[0] == 0 || pos.hands[1] == 0 || boneyard == 0;
+        // Ghost 13 counts zero-suit tiles on the board, and scoring treats
+        // every tile outside the hands as played
+        let rules = RuleSet { ghost13: self.rules.ghost13 && boneyard & ZERO_SUIT_NO_00 == 0, ..self.rules };
+        if over { pos.result_for(AI, &rules) } else { None }
+    }
+}
+
+/// End state of a validated record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub state: HandState,
+    /// Tiles still in the boneyard
+    pub boneyard: i32,
+    /// Hand result from the AI's side, if the hand is over
+    pub result: Option<i32>,
+}
+
+/// Parse every game in `text`. A game starts with its tag section; the move
+/// list runs up to the next tag line.
+pub fn parse_all(text: &str) -> Result<Vec<DgnGame>, String> {
+    let mut games = Vec::new();
+    let mut tags: Vec<(String, String)> = Vec::new();
+    let mut moves = String::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if !moves.is_empty() || (line.starts_with("[DGN ") && !tags.is_empty()) {
+                games.push(build(&tags, &moves).map_err(|e| format!("game {}: {}", games.len() + 1, e))?);
+                tags.clear();
+                moves.clear();
+            }
+            tags.push(parse_tag(line).map_err(|e| format!("line {}: {}", n + 1, e))?);
+        } else if !line.is_empty() {
+            moves += line;
+            moves.push(' ');
+        }
+    }
+    if !tags.is_empty() || !moves.is_empty() {
+        games.push(build(&tags, &moves).map_err(|e| format!("game {}: {}", games.len() + 1, e))?);
+    }
+    Ok(games)
+}
+
+fn build(tags: &[(String, String)], moves: &str) -> Result<DgnGame, String> {
+    match tags.first() {
+        Some((k, v)) if k == "DGN" => {
+            if v != VERSION {
+                return Err(format!("unsupported DGN version '{}'", v));
+            }
+        }
+        _ => return Err("missing [DGN] tag".to_string()),
+    }
+    let get = |name: &str| tags.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+    let need = |name: &str| get(name).ok_or_else(|| format!("missing [{}] tag", name));
+
+    let mut game = DgnGame::new([parse_hand(need("HumanHand")?)?, parse_hand(need("AIHand")?)?],
+                                parse_side(need("Leader")?)?);
+    game.variant = match get("Variant").unwrap_or("standard") {
+        "standard" => Variant::Standard,
+        "draw" => Variant::Draw,
+        v => return Err(format!("unknown variant '{}'", v)),
+    };
+    game.players = [get("Human").unwrap_or("").to_string(), get("AI").unwrap_or("").to_string()];
+    if let Some(r) = get("Rules") {
+        game.rules = parse_rules(r)?;
+    }
+    if let Some(s) = get("Seed") {
+        game.seed = Some(s.parse().map_err(|_| format!("bad seed '{}'", s))?);
+    }
+    if let Some(s) = get("MatchScore") {
+        let bad = || format!("bad match score '{}'", s);
+        let (h, a) = s.split_once('-').ok_or_else(bad)?;
+        game.match_score = [h.parse().map_err(|_| bad())?, a.parse().map_err(|_| bad())?];
+    }
+    game.date = get("Date").map(str::to_string);
+    if let Some(b) = get("Boneyard") {
+        game.boneyard = parse_tiles(b)?;
+    }
+    game.result = match get("Result").unwrap_or("*") {
+        "*" => None,
+        r => {
+            let bad = || format!("bad result '{}'", r);
+            let (who, pts) = r.split_once(' ').ok_or_else(bad)?;
+            let pts: i32 = pts.parse().map_err(|_| bad())?;
+            Some(if parse_side(who)? == AI { pts } else { -pts })
+        }
+    };
+    const KNOWN: [&str; 13] = ["DGN", "Variant", "Human", "AI", "Rules", "Seed", "MatchScore", "Date",
+                               "Leader", "HumanHand", "AIHand", "Boneyard", "Result"];
+    game.extra_tags = tags.iter().filter(|(k, _)| !KNOWN.contains(&k.as_str())).cloned().collect();
+    game.moves = moves.split_whitespace().map(DgnMove::parse).collect::<Result<_, _>>()?;
+    Ok(game)
+}
+
+/// `[Name "value"]`
+fn parse_tag(line: &str) -> Result<(String, String), String> {
+    let bad = || format!("bad tag '{}'", line);
+    let inner = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')).ok_or_else(bad)?;
+    let (name, quoted) = inner.split_once(' ').ok_or_else(bad)?;
+    let quoted = quoted.trim();
+    let body = quoted.strip_prefix('"').and_then(|s| s.strip_suffix('"')).ok_or_else(bad)?;
+    let mut value = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        value.push(if c == '\\' { chars.next().ok_or_else(bad)? } else { c });
+    }
+    Ok((name.to_string(), value))
+}
+
+fn parse_side(s: &str) -> Result<i8, String> {
+    match s {
+        "human" => Ok(HUMAN),
+        "ai" => Ok(AI),
+        _ => Err(format!("expected human or ai, got '{}'", s)),
+    }
+}
+
+fn side(who: i8) -> &'static str {
+    if who == AI { "ai" } else { "human" }
+}
+
+fn parse_tile(s: &str) -> Result<i8, String> {
+    match s.as_bytes() {
+        [a @ b'0'..=b'6', b @ b'0'..=b'6'] => Ok(tile_id_to_index((a - b'0') as i8, (b - b'0') as i8) as i8),
+        _ => Err(format!("bad tile '{}'", s)),
+    }
+}
+
+fn tile_text(t: i8) -> String {
+    format!("{}{}", TILE_LOW[t as usize], TILE_HIGH[t as usize])
+}
+
+fn parse_tiles(s: &str) -> Result<Vec<i8>, String> {
+    s.split_whitespace().map(parse_tile).collect()
+}
+
+fn tiles_text(tiles: impl Iterator<Item = i8>) -> String {
+    tiles.map(tile_text).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_hand(s: &str) -> Result<i32, String> {
+    let mut hand = 0;
+    for t in parse_tiles(s)? {
+        if hand & 1 << t != 0 {
+            return Err(format!("{} dealt twice", tile_text(t)));
+        }
+        hand |= 1 << t;
+    }
+    Ok(hand)
+}
+
+/// `Rules` tag value: every switch, `key=value,...`.
+pub fn rules_text(r: &RuleSet) -> String {
+    let on = |b: bool| if b { "on" } else { "off" };
+    format!("ghost13={},puppeteer={},block={},domino={},target={}",
+            on(r.ghost13), on(r.puppeteer),
+            match r.block_scoring {
+                BlockScoring::DoubleOpponent => "double",
+                BlockScoring::AllPips => "allPips",
+            },
+            match r.domino_scoring {
+                DominoScoring::LoserPips => "loserPips",
+                DominoScoring::AllOpponentsRounded => "allOpponentsRounded",
+            },
+            r.target_score)
+}
+
+/// `key=value,...`; missing keys keep the standard rules.
+pub fn parse_rules(s: &str) -> Result<RuleSet, String> {
+    let mut r = RuleSet::STANDARD;
+    for item in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (key, value) = item.split_once('=').ok_or_else(|| format!("bad rule '{}'", item))?;
+        let bad = || format!("bad rule value '{}'", item);
+        let on = || match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(bad()),
+        };
+        match key {
+            "ghost13" => r.ghost13 = on()?,
+            "puppeteer" => r.puppeteer = on()?,
+            "block" => r.block_scoring = match value {
+                "double" => BlockScoring::DoubleOpponent,
+                "allPips" => BlockScoring::AllPips,
+                _ => return Err(bad()),
+            },
+            "domino" => r.domino_scoring = match value {
+                "loserPips" => DominoScoring::LoserPips,
+                "allOpponentsRounded" => DominoScoring::AllOpponentsRounded,
+                _ => return Err(bad()),
+            },
+            "target" => r.target_score = value.parse().map_err(|_| bad())?,
+            _ => return Err(format!("unknown rule '{}'", key)),
+        }
+    }
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::deal;
+    use crate::zobrist::Xorshift32;
+
+    /// Random legal game from a seeded deal, with its result filled in.
+    fn random_game(seed: u32) -> DgnGame {
+        let [human, ai] = deal(seed);
+        let mut game = DgnGame::new([human, ai], AI);
+        game.seed = Some(seed);
+        game.players = ["Alice \"A\"".to_string(), "engine".to_string()];
+        game.match_score = [40, 25];
+        let mut pos = HandState::new(human, ai, AI);
+        let mut rng = Xorshift32::new(seed | 1);
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        while game.validate().unwrap().result.is_none() {
+            let who = pos.to_move;
+            let n = pos.legal_moves(&mut buf);
+            let action = if n == 0 {
+                pos.pass();
+                Action::Pass
+            } else {
+                let (tile, end) = buf[rng.next() as usize % n];
+                pos.place(tile, end);
+                Action::Place { tile, end }
+            };
+            game.moves.push(DgnMove { who, action });
+        }
+        game.result = game.validate().unwrap().result;
+        game
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut text = String::new();
+        let games: Vec<DgnGame> = (1..=5).map(random_game).collect();
+        for g in &games {
+            assert_eq!(DgnGame::parse(&g.to_text()).as_ref(), Ok(g));
+            text += &g.to_text();
+            text.push('\n');
+        }
+        assert_eq!(parse_all(&text), Ok(games));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_records() {
+        let game = random_game(7);
+        assert!(game.validate().is_ok());
+
+        let mut g = game.clone();
+        g.moves.swap(0, 1);
+        assert!(g.validate().unwrap_err().contains("to move"));
+
+        let mut g = game.clone();
+        g.result = Some(game.result.unwrap() + 1);
+        assert!(g.validate().unwrap_err().contains("result"));
+
+        let mut g = game.clone();
+        g.moves.push(DgnMove { who: HUMAN, action: Action::Pass });
+        assert!(g.validate().unwrap_err().contains("over"));
+
+        let mut g = game.clone();
+        g.moves.truncate(3);
+        assert!(g.validate().unwrap_err().contains("not over"));
+        g.result = None;
+        assert_eq!(g.validate().unwrap().result, None);
+
+        let mut g = game;
+        g.moves.insert(1, DgnMove { who: HUMAN, action: Action::Draw { tile: 0 } });
+        assert!(g.validate().unwrap_err().contains("boneyard"));
+    }
+
+    #[test]
+    fn test_draw_variant() {
+        let t = |s: &str| parse_tile(s).unwrap();
+        let hand = |s: &str| parse_hand(s).unwrap();
+        // Human holds nothing with a 6 and must draw [4-6] from the boneyard
+        let text = "[DGN \"1\"]\n[Variant \"draw\"]\n[Leader \"ai\"]\n\
+                    [HumanHand \"00 01 02 03 04 05 11 12 13\"]\n\
+                    [AIHand \"66 56 55 45 44 35 34 33 25\"]\n\
+                    [Boneyard \"46 14 15 16 22 23 24 26 36 06\"]\n\n\
+                    a:66 h:draw:46 h:46R a:45R";
+        let game = DgnGame::parse(text).unwrap();
+        assert_eq!(game.variant, Variant::Draw);
+        assert_eq!(game.hands, [hand("00 01 02 03 04 05 11 12 13"), hand("66 56 55 45 44 35 34 33 25")]);
+        let replay = game.validate().unwrap();
+        assert_eq!(replay.boneyard.count_ones(), 9);
+        assert_eq!((replay.state.left, replay.state.right), (6, 5));
+        assert_eq!(replay.state.to_move, HUMAN);
+
+        // Drawing out of order, or with a legal move, is rejected
+        let mut g = game.clone();
+        g.moves[1].action = Action::Draw { tile: t("14") };
+        assert!(g.validate().is_err());
+        let mut g = game;
+        g.moves.insert(2, DgnMove { who: HUMAN, action: Action::Draw { tile: t("14") } });
+        assert!(g.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let ok = "[DGN \"1\"]\n[Leader \"ai\"]\n[HumanHand \"01\"]\n[AIHand \"66\"]\n\na:66";
+        assert!(DgnGame::parse(ok).is_ok());
+        for (from, to) in [
+            ("[DGN \"1\"]", "[DGN \"2\"]"),
+            ("[Leader \"ai\"]", "[Leader \"bob\"]"),
+            ("[Leader \"ai\"]\n", ""),
+            ("a:66", "a:67"),
+            ("a:66", "x:66"),
+            ("[AIHand \"66\"]", "[AIHand \"66\"]\n[Rules \"ghost13=maybe\"]"),
+            ("[AIHand \"66\"]", "[AIHand \"66 66\"]"),
+        ] {
+            assert!(DgnGame::parse(&ok.replace(from, to)).is_err(), "{} -> {}", from, to);
+        }
+        // Rules and unknown tags survive a round trip
+        let text = ok.replace("[AIHand \"66\"]",
+                              "[AIHand \"66\"]\n[Rules \"ghost13=off,target=150\"]\n[Event \"club night\"]");
+        let g = DgnGame::parse(&text).unwrap();
+        assert!(!g.rules.ghost13);
+        assert_eq!(g.rules.target_score, 150);
+        assert_eq!(g.extra_tags, vec![("Event".to_string(), "club night".to_string())]);
+        assert_eq!(DgnGame::parse(&g.to_text()), Ok(g));
+    }
+
+    #[test]
+    fn test_history_json_round_trip() {
+        let game = random_game(9);
+        let out: serde_json::Value = serde_json::from_str(&crate::wasm_dgn_to_history(&game.to_text())).unwrap();
+        let history = &out["games"][0];
+        assert_eq!(history["result"], game.result.unwrap());
+        let back: serde_json::Value =
+            serde_json::from_str(&crate::wasm_dgn_from_history(&history.to_string())).unwrap();
+        assert_eq!(back["dgn"].as_str(), Some(game.to_text().as_str()));
+
+        // game.js entries: no leader field, `boardEnds` ignored
+        let json = history.to_string().replace(r#""leader":"ai","#, "")
+            .replace(r#""end":"left""#, r#""end":"left","boardEnds":{"left":1,"right":2}"#);
+        let back: serde_json::Value = serde_json::from_str(&crate::wasm_dgn_from_history(&json)).unwrap();
+        assert_eq!(back["dgn"].as_str(), Some(game.to_text().as_str()));
+
+        let bad = json.replacen(r#""player":"ai""#, r#""player":"human""#, 1);
+        let out: serde_json::Value = serde_json::from_str(&crate::wasm_dgn_from_history(&bad)).unwrap();
+        assert!(out["error"].is_string());
+    }
+}
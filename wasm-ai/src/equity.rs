@@ -0,0 +1,266 @@
+//! Match equity — probability of winning the race to `target_score`.
+//!
+//! The hand-result distribution is measured by greedy self-play on seeded
+//! deals under the active `RuleSet`; the table is then filled by DP over the
+//! points each side still needs, assuming either player is equally likely to
+//! take a hand. The search maps hand results through the table so that, near
+//! the target, "enough" points are worth as much as the maximum.
+
+use std::sync::{Arc, Mutex};
+
+use crate::deal::deal_from_rng;
+use crate::game::{HandState, AI};
+use crate::lookup::{NUM_TILES, TILE_PIPS};
+use crate::rules::RuleSet;
+use crate::zobrist::Xorshift32;
+
+/// Deals played to measure the hand-result distribution.
+const SELF_PLAY_DEALS: u32 = 10_000;
+const SELF_PLAY_SEED: u32 = 0x5EED_0E01;
+
+/// Equity is reported as `(2p − 1) × EQUITY_SCALE`, so 0 is an even match.
+/// ±1000 keeps every value inside the i16 TT slot.
+pub const EQUITY_SCALE: i32 = 1000;
+
+/// Largest hand result (either sign) the per-root table covers; anything
+/// beyond is clamped, which is harmless because it already exceeds any
+/// realistic points-needed.
+pub const EQ_RANGE: i32 = 400;
+pub const EQ_SLOTS: usize = (2 * EQ_RANGE + 1) as usize;
+
+/// Spread (points per tile left in both hands) assumed around a static
+/// evaluation when it is converted to equity.
+const SIGMA_PER_TILE: f64 = 1.5;
+
+/// Three-point Gauss–Hermite rule for a standard normal: (node, weight).
+const QUADRATURE: [(f64, f64); 3] = [
+    (-1.732_050_807_568_877, 1.0 / 6.0),
+    (0.0, 2.0 / 3.0),
+    (1.732_050_807_568_877, 1.0 / 6.0),
+];
+
+/// Which quantity the root search maximizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// Raw hand points (the classic engine).
+    Points,
+    /// Probability of winning the match from the given running score.
+    MatchEquity { ai_score: i32, human_score: i32 },
+}
+
+/// Win probabilities for every (AI needs, human needs) pair up to the target.
+pub struct MatchEquity {
+    pub rules: RuleSet,
+    /// (points, probability) of a single hand's winning margin; zero-point
+    /// hands are dropped since they leave the match state unchanged.
+    pub hand_points: Vec<(i32, f64)>,
+    target: i32,
+    table: Vec<f64>,
+}
+
+impl MatchEquity {
+    /// Measure the hand distribution by self-play under `rules`, then solve.
+    pub fn build(rules: &RuleSet) -> Self {
+        let dist = self_play_distribution(rules, SELF_PLAY_DEALS, SELF_PLAY_SEED);
+        Self::from_distribution(rules, &dist)
+    }
+
+    /// Solve the race for an explicit hand-result distribution.
+    pub fn from_distribution(rules: &RuleSet, hand_points: &[(i32, f64)]) -> Self {
+        let target = rules.target_score.max(1);
+        let side = (target + 1) as usize;
+        let mut me = MatchEquity {
+            rules: *rules,
+            hand_points: hand_points.to_vec(),
+            target,
+            table: vec![0.0; side * side],
+        };
+        for a in 1..=target {
+            for b in 1..=target {
+                let mut p = 0.0;
+                for &(pts, w) in hand_points {
+                    p += w * 0.5 * (me.win_probability(a - pts, b) + me.win_probability(a, b - pts));
+                }
+                me.table[a as usize * side + b as usize] = p;
+            }
+        }
+        me
+    }
+
+    /// Probability the AI wins the match when it needs `ai_need` more points
+    /// and the human needs `human_need`.
+    pub fn win_probability(&self, ai_need: i32, human_need: i32) -> f64 {
+        if ai_need <= 0 {
+            return 1.0;
+        }
+        if human_need <= 0 {
+            return 0.0;
+        }
+        let side = (self.target + 1) as usize;
+        let a = ai_need.min(self.target) as usize;
+        let b = human_need.min(self.target) as usize;
+        self.table[a * side + b]
+    }
+
+    /// AI win probability after a hand worth `points` (AI perspective) is
+    /// added to the running score.
+    pub fn after_hand(&self, ai_score: i32, human_score: i32, points: i32) -> f64 {
+        let ai_need = self.target - ai_score - points.max(0);
+        let human_need = self.target - human_score + points.min(0);
+        self.win_probability(ai_need, human_need)
+    }
+
+    /// Scaled equity of every hand result in `-EQ_RANGE..=EQ_RANGE` from the
+    /// given running score.
+    pub fn at(&self, ai_score: i32, human_score: i32) -> RootEquity {
+        let mut values = [0i16; EQ_SLOTS];
+        for (i, v) in values.iter_mut().enumerate() {
+            let p = self.after_hand(ai_score, human_score, i as i32 - EQ_RANGE);
+            *v = scale(p) as i16;
+        }
+        RootEquity { values }
+    }
+}
+
+/// Hand result → scaled equity, fixed for one root search.
+pub struct RootEquity {
+    values: [i16; EQ_SLOTS],
+}
+
+impl RootEquity {
+    pub const EMPTY: RootEquity = RootEquity { values: [0; EQ_SLOTS] };
+
+    /// Equity of a finished hand.
+    #[inline]
+    pub fn terminal(&self, points: i32) -> i32 {
+        self.values[(points.clamp(-EQ_RANGE, EQ_RANGE) + EQ_RANGE) as usize] as i32
+    }
+
+    /// Expected equity of a static evaluation, treating it as the mean of a
+    /// normal spread that narrows as tiles leave the hands.
+    #[inline]
+    pub fn estimate(&self, points: f64, tiles_left: i32) -> i32 {
+        let sigma = SIGMA_PER_TILE * tiles_left as f64;
+        let mut v = 0.0;
+        for &(z, w) in &QUADRATURE {
+            v += w * self.terminal((points + sigma * z).round() as i32) as f64;
+        }
+        v.round() as i32
+    }
+}
+
+/// Scaled equity → AI match-win probability.
+pub fn to_probability(equity: i32) -> f64 {
+    (equity as f64 / EQUITY_SCALE as f64 + 1.0) / 2.0
+}
+
+fn scale(p: f64) -> i32 {
+    ((2.0 * p - 1.0) * EQUITY_SCALE as f64).round() as i32
+}
+
+/// Process-wide table for `rules`, rebuilt only when the rules change.
+pub fn shared(rules: &RuleSet) -> Arc<MatchEquity> {
+    static CACHE: Mutex<Option<Arc<MatchEquity>>> = Mutex::new(None);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(ref me) = *cache {
+        if me.rules == *rules {
+            return Arc::clone(me);
+        }
+    }
+    let me = Arc::new(MatchEquity::build(rules));
+    *cache = Some(Arc::clone(&me));
+    me
+}
+
+/// Play `deals` seeded 14/14 deals with a heaviest-tile-first policy for both
+/// sides (leader alternating) and return the distribution of winning margins.
+pub fn self_play_distribution(rules: &RuleSet, deals: u32, seed: u32) -> Vec<(i32, f64)> {
+    let mut rng = Xorshift32::new(seed);
+    let mut counts: Vec<u32> = Vec::new();
+    let mut total = 0u32;
+    for d in 0..deals {
+        let hands = deal_from_rng(&mut rng); // [human, ai]
+        let pts = play_out_greedy(hands, (d & 1) as usize, rules).unsigned_abs() as usize;
+        if pts == 0 {
+            continue;
+        }
+        if counts.len() <= pts {
+            counts.resize(pts + 1, 0);
+        }
+        counts[pts] += 1;
+        total += 1;
+    }
+    counts.iter().enumerate()
+        .filter(|&(_, &c)| c > 0)
+        .map(|(p, &c)| (p as i32, c as f64 / total as f64))
+        .collect()
+}
+
+/// One greedy hand; `hands` is [human, ai], `leader` 0 = human, 1 = AI.
+/// Returns the hand result from the AI's perspective.
+fn play_out_greedy(hands: [i32; 2], leader: usize, rules: &RuleSet) -> i32 {
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let mut pos = HandState::new(hands[0], hands[1], leader as i8);
+    loop {
+        if let Some(result) = pos.result_for(AI, rules) {
+            return result;
+        }
+        let n = pos.legal_moves(&mut buf);
+        if n == 0 {
+            pos.pass();
+            continue;
+        }
+        let &(t, end) = buf[..n].iter()
+            .max_by_key(|&&(t, _)| TILE_PIPS[t as usize])
+            .unwrap();
+        pos.place(t, end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribution_normalized() {
+        let dist = self_play_distribution(&RuleSet::STANDARD, 500, 7);
+        let sum: f64 = dist.iter().map(|&(_, w)| w).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+        assert!(dist.iter().all(|&(p, _)| p > 0));
+    }
+
+    #[test]
+    fn test_equity_symmetry_and_monotonicity() {
+        let me = MatchEquity::build(&RuleSet::STANDARD);
+        assert!((me.win_probability(100, 100) - 0.5).abs() < 1e-9);
+        for a in [10, 40, 70] {
+            assert!((me.win_probability(a, 60) + me.win_probability(60, a) - 1.0).abs() < 1e-9);
+            // Needing fewer points is never worse
+            assert!(me.win_probability(a, 60) >= me.win_probability(a + 10, 60));
+        }
+        assert_eq!(me.win_probability(0, 5), 1.0);
+        assert_eq!(me.win_probability(5, 0), 0.0);
+    }
+
+    #[test]
+    fn test_enough_points_saturate() {
+        // At 95-80, any win of 5+ points ends the match: extra points add nothing.
+        let me = MatchEquity::build(&RuleSet::STANDARD);
+        let root = me.at(95, 80);
+        assert_eq!(root.terminal(5), EQUITY_SCALE);
+        assert_eq!(root.terminal(60), EQUITY_SCALE);
+        assert!(root.terminal(4) < EQUITY_SCALE);
+        assert!(root.terminal(-20) < root.terminal(0));
+        // Ahead near the target, a sure small edge beats a riskier equal mean
+        assert!(root.estimate(6.0, 0) > root.estimate(6.0, 10));
+    }
+
+    #[test]
+    fn test_shared_cache_tracks_rules() {
+        let a = shared(&RuleSet::STANDARD);
+        let b = shared(&RuleSet::STANDARD);
+        assert!(Arc::ptr_eq(&a, &b));
+        let short = RuleSet { target_score: 50, ..RuleSet::STANDARD };
+        assert_eq!(shared(&short).rules.target_score, 50);
+    }
+}
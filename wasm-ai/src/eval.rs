@@ -1,158 +1,433 @@
-/// Static evaluation function — 7-component heuristic with phase-dependent weights.
-/// Matches the JS `evaluateBB()` function in ai-worker.js.
+//! Static evaluation function — 7-component heuristic with phase-dependent weights.
+//! Matches the JS `evaluateBB()` function in ai-worker.js; `evaluate_explain`
+//! returns the same score split into its components.
 
-use crate::lookup::{
-    TILE_PIPS, TILE_00_BIT, ZERO_SUIT_NO_00, SUIT_MASK, DOUBLE_MASK, popcount,
-};
 use crate::movegen::count_moves_bb;
+use crate::rules::RuleSet;
+use crate::set::{bits, Bitboard, DominoSet};
+use std::ptr::addr_of;
 
-// Base evaluation weights (matching JS W_* constants)
-const W_PIP: f64 = 2.0;
-const W_MOBILITY: f64 = 4.0;
-const W_TILE: f64 = 5.0;
-const W_SUIT: f64 = 3.0;
-const W_LOCKIN: f64 = 8.0;
-const W_LOCKIN_BOTH: f64 = 15.0;
-const W_GHOST: f64 = 10.0;
-const W_DOUBLE: f64 = 1.5;
+/// Evaluation weights and phase/match multipliers. `DEFAULT` matches the JS
+/// W_* constants; tuned sets are loaded from a params file (`name = value`
+/// per line, `#` comments) via `EvalParams::parse` and `set_params`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvalParams {
+    pub w_pip: f64,
+    pub w_mobility: f64,
+    pub w_tile: f64,
+    pub w_suit: f64,
+    pub w_lockin: f64,
+    pub w_lockin_both: f64,
+    pub w_ghost: f64,
+    pub w_double: f64,
+    /// Phase multipliers: [pip, mobility, suit, double]
+    pub opening: [f64; 4],
+    pub midgame: [f64; 4],
+    pub endgame: [f64; 4],
+    /// Leading multipliers: [pip, suit]
+    pub leading: [f64; 2],
+    /// Trailing multipliers: [pip, suit, mobility]
+    pub trailing: [f64; 3],
+}
 
-/// Static evaluation of the current position.
-/// Returns a score from AI's perspective (positive = good for AI).
-///
-/// # Arguments
-/// * `ai_hand` — AI hand bitmask
-/// * `human_hand` — Human hand bitmask
-/// * `left` — Left board end (7 = empty)
-/// * `right` — Right board end (7 = empty)
-/// * `match_diff` — AI match score minus human match score
-#[inline]
-pub fn evaluate_bb(
-    ai_hand: i32,
-    human_hand: i32,
+/// Number of scalar parameters in `EvalParams`.
+pub const NUM_EVAL_PARAMS: usize = 25;
+
+impl EvalParams {
+    pub const DEFAULT: EvalParams = EvalParams {
+        w_pip: 2.0,
+        w_mobility: 4.0,
+        w_tile: 5.0,
+        w_suit: 3.0,
+        w_lockin: 8.0,
+        w_lockin_both: 15.0,
+        w_ghost: 10.0,
+        w_double: 1.5,
+        opening: [0.7, 1.5, 1.3, 1.3],
+        midgame: [1.0, 1.0, 1.0, 1.0],
+        endgame: [1.5, 0.6, 1.5, 1.0],
+        leading: [1.4, 0.6],
+        trailing: [0.7, 1.5, 1.3],
+    };
+
+    /// Every parameter with its params-file name, in file order.
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut f64); NUM_EVAL_PARAMS] {
+        let [o0, o1, o2, o3] = &mut self.opening;
+        let [m0, m1, m2, m3] = &mut self.midgame;
+        let [e0, e1, e2, e3] = &mut self.endgame;
+        let [l0, l1] = &mut self.leading;
+        let [t0, t1, t2] = &mut self.trailing;
+        [
+            ("w_pip", &mut self.w_pip),
+            ("w_mobility", &mut self.w_mobility),
+            ("w_tile", &mut self.w_tile),
+            ("w_suit", &mut self.w_suit),
+            ("w_lockin", &mut self.w_lockin),
+            ("w_lockin_both", &mut self.w_lockin_both),
+            ("w_ghost", &mut self.w_ghost),
+            ("w_double", &mut self.w_double),
+            ("opening_pip", o0), ("opening_mobility", o1), ("opening_suit", o2), ("opening_double", o3),
+            ("midgame_pip", m0), ("midgame_mobility", m1), ("midgame_suit", m2), ("midgame_double", m3),
+            ("endgame_pip", e0), ("endgame_mobility", e1), ("endgame_suit", e2), ("endgame_double", e3),
+            ("leading_pip", l0), ("leading_suit", l1),
+            ("trailing_pip", t0), ("trailing_suit", t1), ("trailing_mobility", t2),
+        ]
+    }
+
+    /// Parse a params file. Unlisted parameters keep their default.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut p = EvalParams::DEFAULT;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected name = value", n + 1))?;
+            let (name, value) = (name.trim(), value.trim());
+            let v: f64 = value.parse()
+                .map_err(|_| format!("line {}: bad number '{}'", n + 1, value))?;
+            let mut fields = p.fields_mut();
+            let slot = fields.iter_mut().find(|(k, _)| *k == name)
+                .ok_or_else(|| format!("line {}: unknown parameter '{}'", n + 1, name))?;
+            *slot.1 = v;
+        }
+        Ok(p)
+    }
+
+    /// Params-file text for these values.
+    pub fn to_text(&self) -> String {
+        let mut copy = *self;
+        let mut out = String::from("# dominos-ai evaluation parameters\n");
+        for (name, v) in copy.fields_mut() {
+            out.push_str(&format!("{} = {}\n", name, v));
+        }
+        out
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Parameters read by `evaluate_bb` (set between searches).
+static mut G_EVAL_PARAMS: EvalParams = EvalParams::DEFAULT;
+
+/// Install `params` for all later searches. Takes the engine lock, so it
+/// waits for a running search to finish. The TT records the parameters its
+/// values were scored under, so the next search after a change starts from
+/// an empty table.
+pub fn set_params(params: &EvalParams) {
+    let _guard = crate::search::lock_engine();
+    unsafe { set_params_locked(params) }
+}
+
+/// `set_params` for a caller already holding the engine lock.
+pub(crate) unsafe fn set_params_locked(params: &EvalParams) {
+    G_EVAL_PARAMS = *params;
+}
+
+/// The parameters currently in use.
+pub fn params() -> EvalParams {
+    unsafe { G_EVAL_PARAMS }
+}
+
+/// Game phase bucket used to scale the components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// 20+ tiles in hand: mobility & suit control matter
+    Opening,
+    Midgame,
+    /// Fewer than 8 tiles: pips & suit control matter
+    Endgame,
+}
+
+/// Match-score adjustment applied on top of the phase scaling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchAdjust {
+    None,
+    /// `match_diff >= target/2`: pips up, suit risk down
+    Leading,
+    /// `match_diff <= -target/2`: suit control & mobility up
+    Trailing,
+}
+
+/// One evaluation term: `value = raw × weight × scale`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EvalComponent {
+    /// Feature difference, AI-favourable positive (pips, moves, tiles, ...)
+    pub raw: f64,
+    /// `raw` times the base weight (suit lock-in bonuses are added here)
+    pub weighted: f64,
+    /// Phase × match multiplier
+    pub scale: f64,
+    /// Contribution to the total
+    pub value: f64,
+}
+
+/// `evaluate_bb` split into its terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvalBreakdown {
+    pub pips: EvalComponent,
+    pub mobility: EvalComponent,
+    pub tiles: EvalComponent,
+    pub suit: EvalComponent,
+    pub ghost: EvalComponent,
+    pub doubles: EvalComponent,
+    pub phase: Phase,
+    pub match_adjust: MatchAdjust,
+    /// Equal to `evaluate_bb` for the same arguments
+    pub total: f64,
+}
+
+impl EvalBreakdown {
+    /// Components with their display names, in evaluation order.
+    pub fn components(&self) -> [(&'static str, &EvalComponent); 6] {
+        [
+            ("pips", &self.pips),
+            ("mobility", &self.mobility),
+            ("tiles", &self.tiles),
+            ("suit", &self.suit),
+            ("ghost", &self.ghost),
+            ("doubles", &self.doubles),
+        ]
+    }
+}
+
+/// Raw terms shared by `evaluate_bb` and `evaluate_explain`.
+struct Terms {
+    pip_diff: i32,
+    mob_diff: i32,
+    tile_diff: i32,
+    suit_diff: i32,
+    suit_score: f64,
+    ghost_sign: i32,
+    double_diff: f64,
+    phase: Phase,
+    match_adjust: MatchAdjust,
+    phase_pip: f64,
+    phase_mob: f64,
+    phase_suit: f64,
+    phase_dbl: f64,
+}
+
+#[inline(always)]
+fn terms<S: DominoSet>(
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
     left: i8,
     right: i8,
     match_diff: i32,
-) -> f64 {
+    rules: &RuleSet,
+    p: &EvalParams,
+) -> Terms {
+    let tb = S::tables();
     let both_hands = ai_hand | human_hand;
 
     // 1. Pip advantage
-    let ai_pips = total_pips_eval(ai_hand, both_hands);
-    let human_pips = total_pips_eval(human_hand, both_hands);
-    let pip_score = (human_pips - ai_pips) as f64 * W_PIP;
+    let ai_pips = total_pips_eval::<S>(ai_hand, both_hands, rules.ghost13);
+    let human_pips = total_pips_eval::<S>(human_hand, both_hands, rules.ghost13);
 
     // 2. Mobility
-    let ai_mob = count_moves_bb(ai_hand, left, right);
-    let human_mob = count_moves_bb(human_hand, left, right);
-    let mob_score = (ai_mob - human_mob) as f64 * W_MOBILITY;
+    let ai_mob = count_moves_bb::<S>(ai_hand, left, right);
+    let human_mob = count_moves_bb::<S>(human_hand, left, right);
 
     // 3. Tile count
-    let ai_count = popcount(ai_hand);
-    let human_count = popcount(human_hand);
-    let tile_score = (human_count - ai_count) as f64 * W_TILE;
+    let ai_count = ai_hand.count();
+    let human_count = human_hand.count();
 
     // 4. Suit control + lock-in detection
+    let mut suit_diff = 0;
     let mut suit_score = 0.0;
-    if left != 7 {
+    if left != S::EMPTY_END {
         if left == right {
-            let ai_l = popcount(SUIT_MASK[left as usize] & ai_hand);
-            let h_l = popcount(SUIT_MASK[left as usize] & human_hand);
-            suit_score = (ai_l - h_l) as f64 * W_SUIT * 2.0;
+            let ai_l = (tb.suit_mask[left as usize] & ai_hand).count();
+            let h_l = (tb.suit_mask[left as usize] & human_hand).count();
+            suit_diff = (ai_l - h_l) * 2;
+            suit_score = (ai_l - h_l) as f64 * p.w_suit * 2.0;
             if h_l == 0 {
-                suit_score += W_LOCKIN * 2.0 + W_LOCKIN_BOTH;
+                suit_score += p.w_lockin * 2.0 + p.w_lockin_both;
             }
         } else {
-            let ai_l = popcount(SUIT_MASK[left as usize] & ai_hand);
-            let ai_r = popcount(SUIT_MASK[right as usize] & ai_hand);
-            let h_l = popcount(SUIT_MASK[left as usize] & human_hand);
-            let h_r = popcount(SUIT_MASK[right as usize] & human_hand);
-            suit_score = (ai_l + ai_r - h_l - h_r) as f64 * W_SUIT;
+            let ai_l = (tb.suit_mask[left as usize] & ai_hand).count();
+            let ai_r = (tb.suit_mask[right as usize] & ai_hand).count();
+            let h_l = (tb.suit_mask[left as usize] & human_hand).count();
+            let h_r = (tb.suit_mask[right as usize] & human_hand).count();
+            suit_diff = ai_l + ai_r - h_l - h_r;
+            suit_score = (ai_l + ai_r - h_l - h_r) as f64 * p.w_suit;
             if h_l == 0 {
-                suit_score += W_LOCKIN;
+                suit_score += p.w_lockin;
             }
             if h_r == 0 {
-                suit_score += W_LOCKIN;
+                suit_score += p.w_lockin;
             }
             if h_l == 0 && h_r == 0 {
-                suit_score += W_LOCKIN_BOTH;
+                suit_score += p.w_lockin_both;
             }
         }
     }
 
     // 5. Ghost 13 bonus
-    let mut ghost = 0.0;
-    if (both_hands & ZERO_SUIT_NO_00) == 0 {
-        if (human_hand & TILE_00_BIT) != 0 {
-            ghost = W_GHOST;
+    let mut ghost_sign = 0;
+    if rules.ghost13 && (both_hands & tb.zero_suit_no_00).is_zero() {
+        if !(human_hand & tb.tile_00_bit).is_zero() {
+            ghost_sign = 1;
         }
-        if (ai_hand & TILE_00_BIT) != 0 {
-            ghost -= W_GHOST;
+        if !(ai_hand & tb.tile_00_bit).is_zero() {
+            ghost_sign -= 1;
         }
     }
 
     // 6. Double penalty/bonus
-    let mut double_pen = 0.0;
-    let mut ai_doubles = ai_hand & DOUBLE_MASK;
-    while ai_doubles != 0 {
-        let bit = ai_doubles & ai_doubles.wrapping_neg();
-        let idx = bit.trailing_zeros() as usize;
-        double_pen -= (TILE_PIPS[idx] as f64 + 2.0) * W_DOUBLE;
-        ai_doubles ^= bit;
-    }
-    let mut human_doubles = human_hand & DOUBLE_MASK;
-    while human_doubles != 0 {
-        let bit = human_doubles & human_doubles.wrapping_neg();
-        let idx = bit.trailing_zeros() as usize;
-        double_pen += (TILE_PIPS[idx] as f64 + 2.0) * W_DOUBLE;
-        human_doubles ^= bit;
+    let mut double_diff = 0.0;
+    for idx in bits(ai_hand & tb.double_mask) {
+        double_diff -= tb.pips[idx] as f64 + 2.0;
+    }
+    for idx in bits(human_hand & tb.double_mask) {
+        double_diff += tb.pips[idx] as f64 + 2.0;
     }
 
     // 7. Phase-dependent weight scaling
-    let total_remaining = popcount(ai_hand) + popcount(human_hand);
-    let (mut phase_pip, mut phase_mob, mut phase_suit, phase_dbl) =
+    let total_remaining = ai_count + human_count;
+    let (phase, [mut phase_pip, mut phase_mob, mut phase_suit, phase_dbl]) =
         if total_remaining >= 20 {
-            (0.7, 1.5, 1.3, 1.3) // Opening: mobility & suit control matter
+            (Phase::Opening, p.opening)
         } else if total_remaining < 8 {
-            (1.5, 0.6, 1.5, 1.0) // Endgame: pips & suit control matter
+            (Phase::Endgame, p.endgame)
         } else {
-            (1.0, 1.0, 1.0, 1.0) // Midgame: balanced
+            (Phase::Midgame, p.midgame)
         };
 
-    // 8. Match-score aware adjustment
-    if match_diff >= 50 {
+    // 8. Match-score aware adjustment (±50 with the standard 100 target)
+    let swing = rules.target_score / 2;
+    let match_adjust = if match_diff >= swing {
         // Leading: play defensively — prioritize pips, reduce suit risk
-        phase_pip *= 1.4;
-        phase_suit *= 0.6;
-    } else if match_diff <= -50 {
+        phase_pip *= p.leading[0];
+        phase_suit *= p.leading[1];
+        MatchAdjust::Leading
+    } else if match_diff <= -swing {
         // Trailing: play aggressively — suit control & mobility
-        phase_pip *= 0.7;
-        phase_suit *= 1.5;
-        phase_mob *= 1.3;
+        phase_pip *= p.trailing[0];
+        phase_suit *= p.trailing[1];
+        phase_mob *= p.trailing[2];
+        MatchAdjust::Trailing
+    } else {
+        MatchAdjust::None
+    };
+
+    Terms {
+        pip_diff: human_pips - ai_pips,
+        mob_diff: ai_mob - human_mob,
+        tile_diff: human_count - ai_count,
+        suit_diff,
+        suit_score,
+        ghost_sign,
+        double_diff,
+        phase,
+        match_adjust,
+        phase_pip,
+        phase_mob,
+        phase_suit,
+        phase_dbl,
     }
+}
 
-    pip_score * phase_pip
-        + mob_score * phase_mob
-        + tile_score
-        + suit_score * phase_suit
-        + ghost
-        + double_pen * phase_dbl
+/// Static evaluation of the current position.
+/// Returns a score from AI's perspective (positive = good for AI).
+///
+/// # Arguments
+/// * `ai_hand` — AI hand bitmask
+/// * `human_hand` — Human hand bitmask
+/// * `left` — Left board end (`S::EMPTY_END` = empty)
+/// * `right` — Right board end (`S::EMPTY_END` = empty)
+/// * `match_diff` — AI match score minus human match score
+/// * `rules` — Ghost 13 switch and match target (leading/trailing at half of it)
+#[inline]
+pub fn evaluate_bb<S: DominoSet>(
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
+    left: i8,
+    right: i8,
+    match_diff: i32,
+    rules: &RuleSet,
+) -> f64 {
+    // SAFETY: only written by `set_params_locked`, under the engine lock.
+    let p = unsafe { &*addr_of!(G_EVAL_PARAMS) };
+    evaluate_with::<S>(p, ai_hand, human_hand, left, right, match_diff, rules)
+}
+
+/// `evaluate_bb` with explicit parameters (used by the tuner).
+#[inline(always)]
+pub fn evaluate_with<S: DominoSet>(
+    p: &EvalParams,
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
+    left: i8,
+    right: i8,
+    match_diff: i32,
+    rules: &RuleSet,
+) -> f64 {
+    let t = terms::<S>(ai_hand, human_hand, left, right, match_diff, rules, p);
+    t.pip_diff as f64 * p.w_pip * t.phase_pip
+        + t.mob_diff as f64 * p.w_mobility * t.phase_mob
+        + t.tile_diff as f64 * p.w_tile
+        + t.suit_score * t.phase_suit
+        + t.ghost_sign as f64 * p.w_ghost
+        + t.double_diff * p.w_double * t.phase_dbl
+}
+
+/// `evaluate_bb` with every component's raw and weighted contribution, the
+/// phase bucket and the match adjustment. Same arguments, same total.
+pub fn evaluate_explain<S: DominoSet>(
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
+    left: i8,
+    right: i8,
+    match_diff: i32,
+    rules: &RuleSet,
+) -> EvalBreakdown {
+    let p = params();
+    let t = terms::<S>(ai_hand, human_hand, left, right, match_diff, rules, &p);
+    let comp = |raw: f64, weighted: f64, scale: f64| EvalComponent {
+        raw,
+        weighted,
+        scale,
+        value: weighted * scale,
+    };
+    let pips = comp(t.pip_diff as f64, t.pip_diff as f64 * p.w_pip, t.phase_pip);
+    let mobility = comp(t.mob_diff as f64, t.mob_diff as f64 * p.w_mobility, t.phase_mob);
+    let tiles = comp(t.tile_diff as f64, t.tile_diff as f64 * p.w_tile, 1.0);
+    let suit = comp(t.suit_diff as f64, t.suit_score, t.phase_suit);
+    let ghost = comp(t.ghost_sign as f64, t.ghost_sign as f64 * p.w_ghost, 1.0);
+    let doubles = comp(t.double_diff, t.double_diff * p.w_double, t.phase_dbl);
+    EvalBreakdown {
+        total: pips.value + mobility.value + tiles.value + suit.value + ghost.value + doubles.value,
+        pips,
+        mobility,
+        tiles,
+        suit,
+        ghost,
+        doubles,
+        phase: t.phase,
+        match_adjust: t.match_adjust,
+    }
 }
 
 /// Quick pip counting for eval (same as scoring::total_pips_bb but inline here
 /// to avoid circular dependency and keep the hot path tight).
 #[inline(always)]
-fn total_pips_eval(hand: i32, both_hands: i32) -> i32 {
-    let ghost13 = (hand & TILE_00_BIT) != 0 && (both_hands & ZERO_SUIT_NO_00) == 0;
+fn total_pips_eval<S: DominoSet>(hand: S::Bits, both_hands: S::Bits, ghost13: bool) -> i32 {
+    let t = S::tables();
+    let ghost13 = ghost13 && !(hand & t.tile_00_bit).is_zero() && (both_hands & t.zero_suit_no_00).is_zero();
     let mut sum = 0i32;
-    let mut h = hand;
-    while h != 0 {
-        let bit = h & h.wrapping_neg();
-        let idx = bit.trailing_zeros() as usize;
+    for idx in bits(hand) {
         if idx == 0 && ghost13 {
             sum += 13;
         } else {
-            sum += TILE_PIPS[idx] as i32;
+            sum += t.pips[idx] as i32;
         }
-        h ^= bit;
     }
     sum
 }
@@ -160,6 +435,7 @@ fn total_pips_eval(hand: i32, both_hands: i32) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::set::DoubleSix;
 
     #[test]
     fn test_eval_symmetric_start() {
@@ -167,7 +443,7 @@ mod tests {
         let hand_a = 0b0000000_0000000_0000011_1111111; // first 9 tiles
         let hand_b = 0b1111111_1111111_1111100_0000000; // remaining 19 tiles
         // Not truly symmetric, but check it returns a finite value
-        let score = evaluate_bb(hand_a, hand_b, 7, 7, 0);
+        let score = evaluate_bb::<DoubleSix>(hand_a, hand_b, 7, 7, 0, &RuleSet::STANDARD);
         assert!(score.is_finite());
     }
 
@@ -176,7 +452,7 @@ mod tests {
         // AI has 1 tile, human has many — AI should be winning
         let ai = 1 << 0; // just (0,0)
         let human = (1 << 1) | (1 << 2) | (1 << 3) | (1 << 27); // 4 tiles
-        let score = evaluate_bb(ai, human, 0, 0, 0);
+        let score = evaluate_bb::<DoubleSix>(ai, human, 0, 0, 0, &RuleSet::STANDARD);
         assert!(score > 0.0, "AI with fewer tiles should have positive eval");
     }
 
@@ -184,9 +460,9 @@ mod tests {
     fn test_eval_match_diff_effect() {
         let ai = 0b111;
         let human = 0b111000;
-        let s_neutral = evaluate_bb(ai, human, 0, 1, 0);
-        let s_leading = evaluate_bb(ai, human, 0, 1, 100);
-        let s_trailing = evaluate_bb(ai, human, 0, 1, -100);
+        let s_neutral = evaluate_bb::<DoubleSix>(ai, human, 0, 1, 0, &RuleSet::STANDARD);
+        let s_leading = evaluate_bb::<DoubleSix>(ai, human, 0, 1, 100, &RuleSet::STANDARD);
+        let s_trailing = evaluate_bb::<DoubleSix>(ai, human, 0, 1, -100, &RuleSet::STANDARD);
         // All should be finite and different
         assert!(s_neutral.is_finite());
         assert!(s_leading.is_finite());
@@ -194,4 +470,68 @@ mod tests {
         // Leading and trailing should produce different evaluations
         assert_ne!(s_leading as i64, s_trailing as i64);
     }
+
+    #[test]
+    fn test_eval_target_scales_thresholds() {
+        let ai = 0b111;
+        let human = 0b111000;
+        let short = RuleSet { target_score: 60, ..RuleSet::STANDARD };
+        // +40 is "leading" only when the target is 60
+        let std_40 = evaluate_bb::<DoubleSix>(ai, human, 0, 1, 40, &RuleSet::STANDARD);
+        let std_0 = evaluate_bb::<DoubleSix>(ai, human, 0, 1, 0, &RuleSet::STANDARD);
+        let short_40 = evaluate_bb::<DoubleSix>(ai, human, 0, 1, 40, &short);
+        assert_eq!(std_40, std_0);
+        assert_ne!(short_40, std_40);
+    }
+
+    #[test]
+    fn test_params_text_round_trip() {
+        let mut p = EvalParams::DEFAULT;
+        p.w_pip = 2.75;
+        p.endgame[1] = 0.45;
+        p.trailing[2] = 1.125;
+        assert_eq!(EvalParams::parse(&p.to_text()).unwrap(), p);
+        assert_eq!(EvalParams::parse("").unwrap(), EvalParams::DEFAULT);
+        assert_eq!(EvalParams::parse("w_tile = 6 # comment\n").unwrap().w_tile, 6.0);
+        assert!(EvalParams::parse("w_nothing = 1").is_err());
+        assert!(EvalParams::parse("w_pip = two").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_with_params() {
+        let (ai, human) = (0b111, 0b111000);
+        let d = evaluate_with::<DoubleSix>(&EvalParams::DEFAULT, ai, human, 0, 1, 0, &RuleSet::STANDARD);
+        assert_eq!(d, evaluate_bb::<DoubleSix>(ai, human, 0, 1, 0, &RuleSet::STANDARD));
+        let zero = EvalParams { w_tile: 0.0, ..EvalParams::DEFAULT };
+        // Tile counts are equal here, so the tile weight cannot matter
+        assert_eq!(evaluate_with::<DoubleSix>(&zero, ai, human, 0, 1, 0, &RuleSet::STANDARD), d);
+        let heavy = EvalParams { w_pip: 4.0, ..EvalParams::DEFAULT };
+        assert_ne!(evaluate_with::<DoubleSix>(&heavy, ai, human, 0, 1, 0, &RuleSet::STANDARD), d);
+    }
+
+    #[test]
+    fn test_explain_matches_eval() {
+        let cases: [(i32, i32, i8, i8, i32); 5] = [
+            (0b111, 0b111000, 0, 1, 0),
+            (0b111, 0b111000, 0, 1, 100),
+            (0b111, 0b111000, 0, 1, -100),
+            (1 << 0, (1 << 7) | (1 << 27), 6, 6, 0), // double-ended, ghost 13 live
+            (0x0FFF_C000, 0x0000_3FFF, 7, 7, 0),     // opening, empty board
+        ];
+        for &(ai, human, l, r, diff) in &cases {
+            let ev = evaluate_bb::<DoubleSix>(ai, human, l, r, diff, &RuleSet::STANDARD);
+            let ex = evaluate_explain::<DoubleSix>(ai, human, l, r, diff, &RuleSet::STANDARD);
+            assert!((ev - ex.total).abs() < 1e-9, "{} vs {}", ev, ex.total);
+        }
+
+        let ex = evaluate_explain::<DoubleSix>(0b111, 0b111000, 0, 1, 100, &RuleSet::STANDARD);
+        assert_eq!(ex.phase, Phase::Endgame);
+        assert_eq!(ex.match_adjust, MatchAdjust::Leading);
+        assert_eq!(ex.tiles.raw, 0.0);
+        assert_eq!(ex.pips.scale, 1.5 * 1.4);
+
+        let ex = evaluate_explain::<DoubleSix>(0x0FFF_C000, 0x0000_3FFF, 7, 7, 0, &RuleSet::STANDARD);
+        assert_eq!(ex.phase, Phase::Opening);
+        assert_eq!(ex.suit.value, 0.0);
+    }
 }
@@ -0,0 +1,43 @@
+//! Positions shared by the unit tests.
+
+use crate::deal::deal;
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::{tile_id_to_index, NUM_TILES};
+use crate::notation::Position;
+
+/// Hand mask of the double-six tile [lo-hi].
+pub(crate) fn bit(lo: i8, hi: i8) -> i32 {
+    1 << tile_id_to_index(lo, hi)
+}
+
+/// AI: [6-6] [0-1], human: [5-6], AI to lead. Leading [6-6] lets the human
+/// go out for −1; leading [0-1] locks at once for −23.
+pub(crate) fn six_or_lock() -> HandState {
+    HandState::new(bit(5, 6), bit(6, 6) | bit(0, 1), AI)
+}
+
+/// `six_or_lock` in notation.
+pub(crate) const SIX_OR_LOCK: &str = "56/0166 - a 0 -";
+
+/// `six_or_lock`'s hands as `SearchInput` JSON fields.
+pub(crate) const SIX_OR_LOCK_TILES: &str =
+    r#""aiTiles":[{"low":6,"high":6},{"low":0,"high":1}],"humanTiles":[{"low":5,"high":6}]"#;
+
+/// Deal `seed` after `plies` first-listed moves (or passes), leader
+/// alternating with the seed as in `puzzle::generate`.
+pub(crate) fn dealt(seed: u32, plies: usize) -> Position {
+    let [human, ai] = deal(seed);
+    let leader = if seed & 1 == 0 { AI } else { HUMAN };
+    let mut pos = Position::from_state(HandState::new(human, ai, leader));
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    for _ in 0..plies {
+        if pos.state.hand_end().is_some() {
+            break;
+        }
+        match pos.state.legal_moves(&mut buf) {
+            0 => pos.pass(),
+            _ => pos.place(buf[0].0, buf[0].1),
+        }
+    }
+    pos
+}
@@ -0,0 +1,225 @@
+//! Two-player hand state outside the search: move application, puppeteer
+//! history, hand-end detection and perspective-swapped searches.
+//! Used by the native tools (analysis, arena) that replay or play whole hands;
+//! the search itself keeps its own `static mut` copy of this state.
+
+use crate::equity::Objective;
+use crate::lookup::NUM_TILES;
+use crate::movegen::{collect_moves, count_moves_bb};
+use crate::rules::RuleSet;
+use crate::scoring::{detect_aggressor_bb, score_block_bb, score_domino_bb};
+use crate::search::{choose_move, compute_new_ends, SearchLimits, SearchResult};
+use crate::set::DoubleSix;
+
+pub const HUMAN: i8 = 0;
+pub const AI: i8 = 1;
+
+/// How a finished hand ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandEnd {
+    /// A side played its last tile
+    Domino,
+    /// Right after a placement, neither side can play
+    Lock,
+    /// Both sides passed in a row
+    PassBlock,
+}
+
+/// Hand state in absolute terms: `hands[HUMAN]`, `hands[AI]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandState {
+    pub hands: [i32; 2],
+    pub left: i8,
+    pub right: i8,
+    pub to_move: i8,
+    pub cons_pass: i32,
+    /// Last placer: (who, left, right, tile)
+    pub p1: (i8, i8, i8, i8),
+    /// Second-to-last placer: (who, left, right)
+    pub p2: (i8, i8, i8),
+}
+
+impl HandState {
+    pub fn new(human_hand: i32, ai_hand: i32, leader: i8) -> Self {
+        HandState {
+            hands: [human_hand, ai_hand],
+            left: 7,
+            right: 7,
+            to_move: leader,
+            cons_pass: 0,
+            p1: (-1, 0, 0, -1),
+            p2: (-1, 0, 0),
+        }
+    }
+
+    /// Legal moves of the side to move, as (tile index, end).
+    pub fn legal_moves(&self, out: &mut [(i8, i8); NUM_TILES]) -> usize {
+        collect_moves::<DoubleSix>(self.hands[self.to_move as usize], self.left, self.right, out)
+    }
+
+    pub fn can_move(&self, who: i8) -> bool {
+        count_moves_bb::<DoubleSix>(self.hands[who as usize], self.left, self.right) > 0
+    }
+
+    /// The legal move matching `tile`/`end`. Playing a tile on the other end
+    /// gives the same position when both ends match (or the board is empty),
+    /// so those fall back to a tile-only match.
+    pub fn find_legal(&self, tile: i8, end: i8) -> Option<(i8, i8)> {
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        let n = self.legal_moves(&mut buf);
+        buf[..n].iter()
+            .find(|&&(t, e)| t == tile && e == end)
+            .or_else(|| if self.left == 7 || self.left == self.right {
+                buf[..n].iter().find(|&&(t, _)| t == tile)
+            } else {
+                None
+            })
+            .copied()
+    }
+
+    /// Place `tile` for the side to move and hand the turn over.
+    pub fn place(&mut self, tile: i8, end: i8) {
+        let who = self.to_move;
+        let (nl, nr) = compute_new_ends::<DoubleSix>(tile as usize, end, self.left, self.right);
+        self.hands[who as usize] ^= 1 << tile;
+        self.left = nl;
+        self.right = nr;
+        self.cons_pass = 0;
+        self.p2 = (self.p1.0, self.p1.1, self.p1.2);
+        self.p1 = (who, nl, nr, tile);
+        self.to_move = 1 - who;
+    }
+
+    pub fn pass(&mut self) {
+        self.cons_pass += 1;
+        self.to_move = 1 - self.to_move;
+    }
+
+    /// Puppeteer `who` as seen by a search where `mover` is the AI side.
+    fn rel(who: i8, mover: i8) -> i8 {
+        if who < 0 { -1 } else if who == mover { 1 } else { 0 }
+    }
+
+    /// Hand result from `mover`'s perspective if the hand is over (domino,
+    /// or neither side can play).
+    pub fn result_for(&self, mover: i8, rules: &RuleSet) -> Option<i32> {
+        let me = self.hands[mover as usize];
+        let opp = self.hands[1 - mover as usize];
+        if me == 0 {
+            return Some(score_domino_bb::<DoubleSix>(true, opp, rules));
+        }
+        if opp == 0 {
+            return Some(score_domino_bb::<DoubleSix>(false, me, rules));
+        }
+        if self.can_move(HUMAN) || self.can_move(AI) {
+            return None;
+        }
+        let (w1, l1, r1, t1) = self.p1;
+        let (w2, l2, r2) = self.p2;
+        Some(score_block_bb::<DoubleSix>(me, opp,
+                                         Self::rel(w1, mover), l1, r1, t1,
+                                         Self::rel(w2, mover), l2, r2, rules))
+    }
+
+    /// How the hand ended, or `None` while it is still going.
+    pub fn hand_end(&self) -> Option<HandEnd> {
+        if self.hands[HUMAN as usize] == 0 || self.hands[AI as usize] == 0 {
+            Some(HandEnd::Domino)
+        } else if self.can_move(HUMAN) || self.can_move(AI) {
+            None
+        } else if self.cons_pass >= 2 {
+            Some(HandEnd::PassBlock)
+        } else {
+            Some(HandEnd::Lock)
+        }
+    }
+
+    /// The side blamed for a blocked board: the last placer, or with the
+    /// puppeteer rule the side that forced them into it.
+    pub fn aggressor(&self, rules: &RuleSet) -> i8 {
+        let (w1, l1, r1, t1) = self.p1;
+        let (w2, l2, r2) = self.p2;
+        if !rules.puppeteer || w1 < 0 {
+            return w1;
+        }
+        detect_aggressor_bb::<DoubleSix>(w1, l1, r1, t1, w2, l2, r2, self.hands[w1 as usize], self.hands[1 - w1 as usize])
+    }
+
+    /// Search with `mover` as the maximizing side. `match_diff` is from the
+    /// mover's perspective.
+    pub fn search(
+        &self, mover: i8, time_budget: f64, rules: &RuleSet,
+        objective: Objective, match_diff: i32,
+    ) -> SearchResult {
+        self.search_limited(mover, time_budget, rules, objective, match_diff, &SearchLimits::NONE)
+    }
+
+    /// `search` under depth/node caps.
+    pub fn search_limited(
+        &self, mover: i8, time_budget: f64, rules: &RuleSet,
+        objective: Objective, match_diff: i32, limits: &SearchLimits,
+    ) -> SearchResult {
+        let (w1, l1, r1, t1) = self.p1;
+        let (w2, l2, r2) = self.p2;
+        choose_move(
+            self.hands[mover as usize], self.hands[1 - mover as usize],
+            self.left, self.right, self.cons_pass, match_diff,
+            Self::rel(w1, mover), l1, r1, t1,
+            Self::rel(w2, mover), l2, r2,
+            time_budget,
+            rules,
+            objective,
+            limits,
+        )
+    }
+
+    /// Points value for `mover` of this position, reached by `mover`'s own
+    /// move (so it is the opponent's turn unless they must pass). Returns
+    /// (score, solved).
+    pub fn value_after_move(
+        &self, mover: i8, time_budget: f64, rules: &RuleSet, match_diff: i32, limits: &SearchLimits,
+    ) -> (i32, bool) {
+        if let Some(sc) = self.result_for(mover, rules) {
+            return (sc, true);
+        }
+        let opp = 1 - mover;
+        let tiles = self.tiles_left();
+        if self.can_move(opp) {
+            let r = self.search_limited(opp, time_budget, rules, Objective::Points, -match_diff, limits);
+            (-r.best_score, r.depth >= tiles)
+        } else {
+            // Opponent passes; not terminal, so the mover can play again
+            let mut after = *self;
+            after.pass();
+            let r = after.search_limited(mover, time_budget, rules, Objective::Points, match_diff, limits);
+            (r.best_score, r.depth >= tiles)
+        }
+    }
+
+    pub fn tiles_left(&self) -> i32 {
+        self.hands[0].count_ones() as i32 + self.hands[1].count_ones() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::six_or_lock;
+    use crate::lookup::tile_id_to_index;
+
+    #[test]
+    fn test_place_pass_and_block() {
+        // AI leads [0-1] → immediate lock
+        let mut s = six_or_lock();
+        assert_eq!(s.result_for(AI, &RuleSet::STANDARD), None);
+        assert_eq!(s.hand_end(), None);
+        s.place(tile_id_to_index(0, 1) as i8, 0);
+        assert_eq!(s.to_move, HUMAN);
+        assert_eq!(s.p1, (AI, 0, 1, tile_id_to_index(0, 1) as i8));
+        // Block, AI heavier (12 vs 11): human scores all 23
+        assert_eq!(s.result_for(AI, &RuleSet::STANDARD), Some(-23));
+        assert_eq!(s.result_for(HUMAN, &RuleSet::STANDARD), Some(23));
+        assert_eq!(s.hand_end(), Some(HandEnd::Lock));
+        assert_eq!(s.aggressor(&RuleSet::STANDARD), AI);
+    }
+}
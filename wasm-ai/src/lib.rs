@@ -1,14 +1,40 @@
-/// WASM entry point — single exported function that accepts JSON, runs search, returns JSON.
-/// Called from ai-worker.js via wasm_bindgen.
-
-mod lookup;
-mod zobrist;
-mod tt;
-mod movegen;
-mod scoring;
-mod eval;
-mod ordering;
-mod search;
+//! WASM entry points: `wasm_*` functions that take JSON (or position
+//! notation), run the engine and return JSON, with bad input reported in an
+//! `error` field.
+//! Called from ai-worker.js via wasm_bindgen; `server` and `capi` expose the
+//! same calls over HTTP and C.
+
+pub mod lookup;
+pub mod zobrist;
+pub mod tt;
+pub mod movegen;
+pub mod scoring;
+pub mod eval;
+pub mod ordering;
+pub mod search;
+pub mod multi;
+pub mod team;
+pub mod set;
+pub mod rules;
+pub mod equity;
+pub mod game;
+pub mod notation;
+pub mod dgn;
+pub mod deal;
+pub mod skill;
+pub mod book;
+pub mod analysis;
+pub mod arena;
+pub mod tune;
+pub mod perft;
+pub mod puzzle;
+pub mod training;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(test)]
+mod fixtures;
 
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -17,7 +43,7 @@ use serde::{Deserialize, Serialize};
 // Serde types matching the JS worker message format
 // =====================================================================
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct TileDesc {
     low: i8,
@@ -40,17 +66,76 @@ struct MoveHistoryEntry {
     board_right: i8,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct MatchScore {
     ai: i32,
     human: i32,
 }
 
-#[derive(Deserialize)]
+/// House-rule switches; every field falls back to the standard rules.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RulesDesc {
+    #[serde(default)]
+    ghost13: Option<bool>,
+    #[serde(default)]
+    puppeteer: Option<bool>,
+    /// "double" (default) or "allPips"
+    #[serde(default)]
+    block_scoring: Option<String>,
+    /// "loserPips" (default) or "allOpponentsRounded"
+    #[serde(default)]
+    domino_scoring: Option<String>,
+    #[serde(default)]
+    target_score: Option<i32>,
+}
+
+impl RulesDesc {
+    fn to_rule_set(&self) -> rules::RuleSet {
+        let std = rules::RuleSet::STANDARD;
+        rules::RuleSet {
+            ghost13: self.ghost13.unwrap_or(std.ghost13),
+            puppeteer: self.puppeteer.unwrap_or(std.puppeteer),
+            block_scoring: match self.block_scoring.as_deref() {
+                Some("allPips") => rules::BlockScoring::AllPips,
+                _ => std.block_scoring,
+            },
+            domino_scoring: match self.domino_scoring.as_deref() {
+                Some("allOpponentsRounded") => rules::DominoScoring::AllOpponentsRounded,
+                _ => std.domino_scoring,
+            },
+            target_score: self.target_score.unwrap_or(std.target_score),
+        }
+    }
+
+    fn from_rule_set(r: &rules::RuleSet) -> Self {
+        RulesDesc {
+            ghost13: Some(r.ghost13),
+            puppeteer: Some(r.puppeteer),
+            block_scoring: Some(match r.block_scoring {
+                rules::BlockScoring::DoubleOpponent => "double",
+                rules::BlockScoring::AllPips => "allPips",
+            }.to_string()),
+            domino_scoring: Some(match r.domino_scoring {
+                rules::DominoScoring::LoserPips => "loserPips",
+                rules::DominoScoring::AllOpponentsRounded => "allOpponentsRounded",
+            }.to_string()),
+            target_score: Some(r.target_score),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 struct SearchInput {
+    /// The whole position in `notation` form; replaces the tile, board,
+    /// history and match score fields and may put either side to move
+    #[serde(default)]
+    position: Option<String>,
+    #[serde(default)]
     ai_tiles: Vec<TileDesc>,
+    #[serde(default)]
     human_tiles: Vec<TileDesc>,
     #[serde(default)]
     board_empty: bool,
@@ -66,9 +151,73 @@ struct SearchInput {
     match_score: Option<MatchScore>,
     #[serde(default)]
     time_budget: Option<f64>,
+    #[serde(default)]
+    rules: Option<RulesDesc>,
+    /// "points" (default) or "matchEquity" (maximize match-win probability
+    /// from `matchScore`)
+    #[serde(default)]
+    objective: Option<String>,
+    /// Attach an evaluation breakdown to every analysis entry
+    #[serde(default)]
+    explain: bool,
+    /// Strength 1–10 (default 10 = full engine); see `skill`
+    #[serde(default)]
+    skill_level: Option<u8>,
+    /// Seed for the weaker levels' move pick (default: clock)
+    #[serde(default)]
+    skill_seed: Option<u32>,
+    /// Only consider these moves at the root (full strength only); moves
+    /// that are not legal here are dropped with a warning
+    #[serde(default)]
+    search_moves: Vec<LegalMoveDesc>,
+    /// Highest pip of the set: 6 (default), 9 or 12. The larger sets read
+    /// the tile, board, history, match score, rules and objective fields
+    /// only (no `position`, skill levels, book or `explain`)
+    #[serde(default)]
+    max_pip: i8,
+    /// Skip the opening book at the root (server `/solve`)
+    #[serde(skip)]
+    solve: bool,
+}
+
+impl SearchInput {
+    /// The position to search: `position` if given, else the JSON fields
+    /// with the AI to move.
+    fn to_position(&self) -> Result<notation::Position, String> {
+        if let Some(text) = &self.position {
+            return notation::Position::parse(text);
+        }
+        type D6 = set::DoubleSix;
+        let mut state = game::HandState::new(
+            set_hand::<D6>(&self.human_tiles)?, set_hand::<D6>(&self.ai_tiles)?, game::AI,
+        );
+        if !self.board_empty {
+            state.left = set_end::<D6>(self.left)?;
+            state.right = set_end::<D6>(self.right)?;
+        }
+
+        // Seed puppeteer history from move_history
+        let who = |player: &str| if player == "ai" { game::AI } else { game::HUMAN };
+        let mut placements = self.move_history.iter().rev().filter(|e| !e.pass);
+        if let Some(e) = placements.next() {
+            let tile = set_tile::<D6>(e.tile_low, e.tile_high)? as i8;
+            let (l, r) = history_ends::<D6>(e)?;
+            state.p1 = (who(&e.player), l, r, tile);
+        }
+        if let Some(e) = placements.next() {
+            let (l, r) = history_ends::<D6>(e)?;
+            state.p2 = (who(&e.player), l, r);
+        }
+
+        let mut pos = notation::Position::from_state(state);
+        if let Some(ms) = &self.match_score {
+            pos.score = [ms.human, ms.ai];
+        }
+        Ok(pos)
+    }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct LegalMoveDesc {
     tile_low: i8,
@@ -82,6 +231,56 @@ struct AnalysisEntry {
     tile_id: String,
     end: String,
     score: i32,
+    /// Static evaluation of the position after this move, by component
+    /// (only with `explain: true`, and not for moves that end the hand)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    explain: Option<EvalExplainDesc>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EvalComponentDesc {
+    name: String,
+    raw: f64,
+    weighted: f64,
+    scale: f64,
+    value: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EvalExplainDesc {
+    components: Vec<EvalComponentDesc>,
+    /// "opening" | "midgame" | "endgame"
+    phase: String,
+    /// "none" | "leading" | "trailing"
+    match_adjust: String,
+    total: f64,
+}
+
+impl EvalExplainDesc {
+    fn from_breakdown(b: &eval::EvalBreakdown) -> Self {
+        EvalExplainDesc {
+            components: b.components().iter().map(|&(name, c)| EvalComponentDesc {
+                name: name.to_string(),
+                raw: c.raw,
+                weighted: c.weighted,
+                scale: c.scale,
+                value: c.value,
+            }).collect(),
+            phase: match b.phase {
+                eval::Phase::Opening => "opening",
+                eval::Phase::Midgame => "midgame",
+                eval::Phase::Endgame => "endgame",
+            }.to_string(),
+            match_adjust: match b.match_adjust {
+                eval::MatchAdjust::None => "none",
+                eval::MatchAdjust::Leading => "leading",
+                eval::MatchAdjust::Trailing => "trailing",
+            }.to_string(),
+            total: b.total,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -92,6 +291,11 @@ struct SearchOutput {
     best_score: i32,
     depth: i32,
     nodes: u32,
+    /// Tile placements still to come before the hand ends under best play
+    /// (passes do not count), when the search solved the hand; `bestScore`
+    /// is then exact
+    #[serde(skip_serializing_if = "Option::is_none")]
+    placements_left: Option<i32>,
     analysis: Vec<AnalysisEntry>,
     // TT diagnostics (included in JSON for debugging; ignored by UI)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -102,95 +306,497 @@ struct SearchOutput {
     tt_cutoffs: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tt_hints: Option<u32>,
+    /// Mover's match-win probability of the best line ("matchEquity"
+    /// objective only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    win_probability: Option<f64>,
+    /// Disagreements the engine papered over; empty when all is well
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<WarningDesc>,
+}
+
+/// A self-check failure in `wasm_choose_move`: an input or engine bug.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WarningDesc {
+    /// "badInput" | "noEngineMove" | "engineMoveIllegal" | "endChanged" |
+    /// "notInLegalMoves" | "legalMovesMismatch"
+    code: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    engine_move: Option<LegalMoveDesc>,
+    /// What was returned instead of `engineMove`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    returned_move: Option<LegalMoveDesc>,
+    /// Legal by the engine's rules but not in `legalMoves`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing: Vec<LegalMoveDesc>,
+    /// In `legalMoves` but not legal by the engine's rules
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unexpected: Vec<LegalMoveDesc>,
+}
+
+impl WarningDesc {
+    fn new(code: &str, message: &str) -> Self {
+        WarningDesc {
+            code: code.to_string(),
+            message: message.to_string(),
+            engine_move: None,
+            returned_move: None,
+            missing: vec![],
+            unexpected: vec![],
+        }
+    }
+}
+
+impl SearchOutput {
+    /// No move: the reply to input that could not be parsed.
+    fn empty() -> Self {
+        SearchOutput {
+            tile_id: String::new(),
+            end: String::new(),
+            best_score: 0,
+            depth: 0,
+            nodes: 0,
+            placements_left: None,
+            analysis: vec![],
+            tt_probes: None,
+            tt_hits: None,
+            tt_cutoffs: None,
+            tt_hints: None,
+            win_probability: None,
+            warnings: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MultiSearchInput {
+    /// One tile list per seat, in turn order
+    hands: Vec<Vec<TileDesc>>,
+    to_move: usize,
+    #[serde(default)]
+    board_empty: bool,
+    #[serde(default)]
+    left: Option<i8>,
+    #[serde(default)]
+    right: Option<i8>,
+    #[serde(default)]
+    cons_pass: i32,
+    /// "maxn" (default) or "paranoid"
+    #[serde(default)]
+    mode: Option<String>,
+    /// 4 seats only: "2v2" or "1v3" switches to team search
+    #[serde(default)]
+    teams: Option<String>,
+    /// Team block scoring: "lowest" (default) or "aggressor"
+    #[serde(default)]
+    block_rule: Option<String>,
+    /// Highest pip of the set: 6 (default), 9 or 12
+    #[serde(default)]
+    max_pip: i8,
+    #[serde(default)]
+    time_budget: Option<f64>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct MultiSearchOutput {
+    tile_id: String,
+    end: String,
+    /// Utility of the principal line for each seat; `paranoid` mode gives
+    /// only the mover's unless the move ends the hand
+    scores: Vec<i32>,
+    depth: i32,
+    nodes: u32,
+    exact: bool,
+    analysis: Vec<AnalysisEntry>,
+    /// Why there is no move: the input does not describe a position
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// One move of a saved game, in the analyze-game.js format (game.js
+/// `moveHistory` entries also carry `draw` in the boneyard variant).
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameMoveDesc {
+    player: String,
+    #[serde(default)]
+    pass: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    draw: bool,
+    #[serde(default)]
+    tile: Option<TileDesc>,
+    #[serde(default)]
+    end: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalyzeGameInput {
+    leader: String,
+    human_tiles: Vec<TileDesc>,
+    ai_tiles: Vec<TileDesc>,
+    moves: Vec<GameMoveDesc>,
+    #[serde(default)]
+    time_budget: Option<f64>,
+    #[serde(default)]
+    rules: Option<RulesDesc>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveReviewDesc {
+    move_num: usize,
+    player: String,
+    pass: bool,
+    tile_id: String,
+    end: String,
+    best_tile_id: String,
+    best_end: String,
+    best_score: i32,
+    played_score: i32,
+    loss: i32,
+    grade: String,
+    forced: bool,
+    depth: i32,
+    solved: bool,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PlayerSummaryDesc {
+    decisions: u32,
+    perfect: u32,
+    points_lost: i32,
+    accuracy: f64,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AnalyzeGameOutput {
+    moves: Vec<MoveReviewDesc>,
+    human: PlayerSummaryDesc,
+    ai: PlayerSummaryDesc,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdviseLeadInput {
+    ai_tiles: Vec<TileDesc>,
+    /// Omitted: the complement of `aiTiles` (no boneyard)
+    #[serde(default)]
+    human_tiles: Option<Vec<TileDesc>>,
+    /// Per leader, in ms
+    #[serde(default)]
+    time_budget: Option<f64>,
+    #[serde(default)]
+    rules: Option<RulesDesc>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LeaderOutcomeDesc {
+    /// Hand result from the AI's perspective
+    score: i32,
+    best_tile_id: String,
+    solved: bool,
+}
+
+impl LeaderOutcomeDesc {
+    fn from_outcome(o: &deal::LeaderOutcome) -> Self {
+        LeaderOutcomeDesc { score: o.score, best_tile_id: tile_name(o.best_tile), solved: o.solved }
+    }
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AdviseLeadOutput {
+    /// Points the leader gains by leading instead of deferring (either side)
+    lead_value: i32,
+    should_lead: bool,
+    human_leads: LeaderOutcomeDesc,
+    ai_leads: LeaderOutcomeDesc,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DealOutput {
+    seed: u32,
+    human_tiles: Vec<TileDesc>,
+    ai_tiles: Vec<TileDesc>,
+}
+
+/// A hand as game.js keeps it: the deal plus `hand.moveHistory` (`moves`
+/// is accepted too, as in analyze-game.js). The DGN import/export format.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct GameHistoryDesc {
+    /// Defaults to the player of the first move
+    #[serde(default)]
+    leader: Option<String>,
+    human_tiles: Vec<TileDesc>,
+    ai_tiles: Vec<TileDesc>,
+    /// Boneyard variant only, in draw order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    boneyard: Vec<TileDesc>,
+    #[serde(alias = "moves")]
+    move_history: Vec<GameMoveDesc>,
+    /// Before the hand
+    #[serde(default)]
+    match_score: Option<MatchScore>,
+    #[serde(default)]
+    rules: Option<RulesDesc>,
+    #[serde(default)]
+    seed: Option<u32>,
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    human_name: Option<String>,
+    #[serde(default)]
+    ai_name: Option<String>,
+    /// Hand result from the AI's side (output only; unfinished hands omit it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<i32>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DgnExportOutput {
+    dgn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DgnImportOutput {
+    games: Vec<GameHistoryDesc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A move for `wasm_apply_move`: an entry of `wasm_legal_moves`, or
+/// `{"pass": true}`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApplyMoveDesc {
+    #[serde(default)]
+    pass: bool,
+    #[serde(default)]
+    tile_low: i8,
+    #[serde(default)]
+    tile_high: i8,
+    /// "left" | "right"; either on the empty board
+    #[serde(default)]
+    end: String,
+}
+
+/// How a hand ended, in game.js terms.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HandEndDesc {
+    /// "domino" | "lock" (neither side can play right after a placement)
+    /// | "passBlock" (both sides passed)
+    kind: String,
+    /// "ai" | "human"
+    winner: String,
+    /// Points the winner scores
+    points: i32,
+    /// Side blamed for a block, after the puppeteer rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aggressor: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LegalMovesOutput {
+    /// "ai" | "human"
+    to_move: String,
+    /// Ready to pass back as `legalMoves`; empty when the side must pass
+    /// or the hand is over
+    moves: Vec<LegalMoveDesc>,
+    hand_end: Option<HandEndDesc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct EvaluateMoveOutput {
+    tile_id: String,
+    end: String,
+    /// Hand points (or equity) for the side to move after playing the move,
+    /// as `bestScore` would be if it were the best move
+    score: i32,
+    depth: i32,
+    nodes: u32,
+    /// As in `SearchOutput`, counting the move itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    placements_left: Option<i32>,
+    /// Expected play after the move, starting with the opponent's reply;
+    /// may stop before the hand ends
+    reply: Vec<GameMoveDesc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A generated puzzle (`puzzle::Puzzle::to_json`); also a `SearchInput`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleDesc {
+    /// `<deal seed>-<ply>`
+    id: String,
+    position: String,
+    rules: RulesDesc,
+    /// The solver: "ai" | "human"
+    to_move: String,
+    /// "win" | "ghost13" | "puppeteer"
+    theme: String,
+    /// The solution move, then best play to the end of the hand
+    solution: Vec<GameMoveDesc>,
+    /// Hand points for the solver after the solution
+    score: i32,
+    /// Points the best alternative loses against the solution
+    margin: i32,
+    /// Moves in `solution`
+    depth: usize,
+    /// 1 (easy) – 5 (hard)
+    difficulty: u8,
+}
+
+/// A labelled training position (`training::TrainingSample::to_json`).
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrainingSampleDesc {
+    deal: u32,
+    ply: u8,
+    /// Side to move as the AI, in `notation` form
+    position: String,
+    /// Solved hand points for the side to move
+    value: i32,
+    /// `evaluate_bb` for the side to move
+    static_eval: f32,
+    best_move: LegalMoveDesc,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ApplyMoveOutput {
+    /// The position after the move, in `notation` form
+    position: String,
+    to_move: String,
+    hand_end: Option<HandEndDesc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 // =====================================================================
-// WASM exported function
+// WASM exported functions
 // =====================================================================
 
+/// Choose a move for the side to move. Input: `SearchInput` JSON, or a bare
+/// position in `notation` form (searched with the defaults). Output:
+/// `SearchOutput` JSON.
 #[wasm_bindgen]
 pub fn wasm_choose_move(input_json: &str) -> String {
-    let input: SearchInput = match serde_json::from_str(input_json) {
-        Ok(v) => v,
-        Err(_e) => {
-            return serde_json::to_string(&SearchOutput {
-                tile_id: String::new(),
-                end: String::new(),
-                best_score: 0,
-                depth: 0,
-                nodes: 0,
-                analysis: vec![],
-                tt_probes: None,
-                tt_hits: None,
-                tt_cutoffs: None,
-                tt_hints: None,
-            }).unwrap_or_else(|_| "{}".to_string());
-        }
+    let output = match read_search_input(input_json) {
+        Ok(input) => match input.max_pip {
+            0 | 6 => with_position(input).map(|(input, pos)| run_choose_move(&input, &pos)),
+            9 => run_choose_move_set::<set::DoubleNine>(&input),
+            12 => run_choose_move_set::<set::DoubleTwelve>(&input),
+            n => Err(format!("maxPip {} is not 6, 9 or 12", n)),
+        },
+        Err(e) => Err(e),
     };
+    let output = output.unwrap_or_else(|e| {
+        SearchOutput { warnings: vec![WarningDesc::new("badInput", &e)], ..SearchOutput::empty() }
+    });
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
 
-    // Convert tile descriptors to bitmasks
-    let mut ai_hand: i32 = 0;
-    for t in &input.ai_tiles {
-        let lo = t.low.min(t.high);
-        let hi = t.low.max(t.high);
-        let idx = lookup::tile_id_to_index(lo, hi);
-        ai_hand |= 1 << idx;
-    }
-    let mut human_hand: i32 = 0;
-    for t in &input.human_tiles {
-        let lo = t.low.min(t.high);
-        let hi = t.low.max(t.high);
-        let idx = lookup::tile_id_to_index(lo, hi);
-        human_hand |= 1 << idx;
-    }
-
-    let left: i8 = if input.board_empty { 7 } else { input.left.unwrap_or(7) };
-    let right: i8 = if input.board_empty { 7 } else { input.right.unwrap_or(7) };
-
-    let match_diff = input.match_score.as_ref()
-        .map(|ms| ms.ai - ms.human)
-        .unwrap_or(0);
-
-    // Seed puppeteer history from move_history
-    let mut p1_who: i8 = -1;
-    let mut p1_l: i8 = 0;
-    let mut p1_r: i8 = 0;
-    let mut p1_tile: i8 = -1;
-    let mut p2_who: i8 = -1;
-    let mut p2_l: i8 = 0;
-    let mut p2_r: i8 = 0;
-
-    let mut placement_count = 0;
-    for entry in input.move_history.iter().rev() {
-        if placement_count >= 2 { break; }
-        if !entry.pass {
-            if placement_count == 0 {
-                p1_who = if entry.player == "ai" { 1 } else { 0 };
-                p1_l = entry.board_left;
-                p1_r = entry.board_right;
-                let t_lo = entry.tile_low.min(entry.tile_high);
-                let t_hi = entry.tile_low.max(entry.tile_high);
-                p1_tile = lookup::tile_id_to_index(t_lo, t_hi) as i8;
-            } else {
-                p2_who = if entry.player == "ai" { 1 } else { 0 };
-                p2_l = entry.board_left;
-                p2_r = entry.board_right;
-            }
-            placement_count += 1;
-        }
+/// `SearchInput` JSON, or a bare `notation` position.
+fn read_search_input(input_json: &str) -> Result<SearchInput, String> {
+    if input_json.trim_start().starts_with('{') {
+        serde_json::from_str::<SearchInput>(input_json).map_err(|e| e.to_string())
+    } else {
+        Ok(SearchInput { position: Some(input_json.to_string()), ..Default::default() })
+    }
+}
+
+/// `SearchInput` JSON, or a bare `notation` position, and the position it
+/// describes.
+fn parse_search_input(input_json: &str) -> Result<(SearchInput, notation::Position), String> {
+    read_search_input(input_json).and_then(with_position)
+}
+
+/// The double-six position `input` describes, filling in its legal moves.
+fn with_position(mut input: SearchInput) -> Result<(SearchInput, notation::Position), String> {
+    let pos = input.to_position()?;
+    if input.legal_moves.is_empty() {
+        // Notation input, or a caller that left them out
+        let mut buf = [(0i8, 0i8); lookup::NUM_TILES];
+        let n = pos.state.legal_moves(&mut buf);
+        input.legal_moves = buf[..n].iter().map(|&(t, e)| move_desc(t, e)).collect();
     }
+    Ok((input, pos))
+}
+
+fn run_choose_move(input: &SearchInput, pos: &notation::Position) -> SearchOutput {
+    // Everything below is from the mover's side (the AI for JSON input)
+    let mover = pos.state.to_move;
+    let my_hand = pos.state.hands[mover as usize];
+    let opp_hand = pos.state.hands[1 - mover as usize];
+    let (left, right) = (pos.state.left, pos.state.right);
+    let (my_score, opp_score) = (pos.score[mover as usize], pos.score[1 - mover as usize]);
+    let match_diff = my_score - opp_score;
 
     let time_budget = input.time_budget.unwrap_or(5000.0);
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let objective = match input.objective.as_deref() {
+        Some("matchEquity") => equity::Objective::MatchEquity { ai_score: my_score, human_score: opp_score },
+        _ => equity::Objective::Points,
+    };
 
     // Run the search
-    let result = search::choose_move(
-        ai_hand, human_hand, left, right,
-        0, // cons_pass always 0 at root (AI is about to move)
-        match_diff,
-        p1_who, p1_l, p1_r, p1_tile,
-        p2_who, p2_l, p2_r,
-        time_budget,
-    );
+    let mut warnings = Vec::new();
+    let skill = skill::SkillLevel::new(input.skill_level.unwrap_or(skill::MAX_LEVEL));
+    let result = if skill.is_full_strength() {
+        let (root_moves, dropped) = root_move_mask(&pos.state, &input.search_moves);
+        if !dropped.is_empty() {
+            warnings.push(WarningDesc {
+                unexpected: dropped,
+                ..WarningDesc::new("searchMovesIllegal", "searchMoves has moves that are not legal; ignored")
+            });
+        }
+        let base = if input.solve { search::SearchLimits::SOLVE } else { search::SearchLimits::NONE };
+        let limits = search::SearchLimits { root_moves, ..base };
+        pos.state.search_limited(mover, time_budget, &rules, objective, match_diff, &limits)
+    } else {
+        let seed = input.skill_seed.unwrap_or_else(|| search::now_ms() as u64 as u32);
+        skill::choose_move_at_level(&pos.state, &skill, time_budget, &rules, objective, match_diff, seed)
+    };
+
+    // Self-check: the engine's move against its own rules model, and the
+    // caller's legal list against the engine's
+    let mut buf = [(0i8, 0i8); lookup::NUM_TILES];
+    let n = pos.state.legal_moves(&mut buf);
+    let engine_move = (result.best_tile_idx >= 0).then(|| move_desc(result.best_tile_idx, result.best_end));
+    if result.best_tile_idx >= 0 && pos.state.find_legal(result.best_tile_idx, result.best_end).is_none() {
+        warnings.push(WarningDesc {
+            engine_move: engine_move.clone(),
+            ..WarningDesc::new("engineMoveIllegal", "the engine's move is not legal in this position")
+        });
+    } else if result.best_tile_idx < 0 && n > 0 {
+        warnings.push(WarningDesc::new("noEngineMove", &format!("the search returned no move with {} legal", n)));
+    }
+    warnings.extend(legal_moves_mismatch(&pos.state, &buf[..n], &input.legal_moves));
 
     // Map result back to tile ID format
     let best_tile_id = if result.best_tile_idx >= 0 {
@@ -223,10 +829,25 @@ pub fn wasm_choose_move(input_json: &str) -> String {
         final_tile_id = best_tile_id;
         final_end = best_end;
     } else if let Some(lm) = find_legal_move_by_tile(&input.legal_moves, &best_tile_id) {
+        // Either end gives the same board on an empty board or equal ends
+        if engine_move.is_some() && left != 7 && left != right {
+            warnings.push(WarningDesc {
+                engine_move: engine_move.clone(),
+                returned_move: Some(lm.clone()),
+                ..WarningDesc::new("endChanged", "the engine's end is not in legalMoves; playing the tile's listed end")
+            });
+        }
         final_end = lm.end.clone();
         final_tile_id = best_tile_id;
     } else if !input.legal_moves.is_empty() {
         let lm = &input.legal_moves[0];
+        if engine_move.is_some() {
+            warnings.push(WarningDesc {
+                engine_move: engine_move.clone(),
+                returned_move: Some(lm.clone()),
+                ..WarningDesc::new("notInLegalMoves", "the engine's tile is not in legalMoves; playing the first listed move")
+            });
+        }
         let lo = lm.tile_low.min(lm.tile_high);
         let hi = lm.tile_low.max(lm.tile_high);
         final_tile_id = format!("{}-{}", lo, hi);
@@ -236,31 +857,160 @@ pub fn wasm_choose_move(input_json: &str) -> String {
         final_end = "left".to_string();
     }
 
-    // Build analysis array
+    // Build analysis array (the equity objective evaluates with match_diff 0)
+    let eval_diff = match objective {
+        equity::Objective::MatchEquity { .. } if skill.is_full_strength() => 0,
+        _ => match_diff,
+    };
     let mut analysis: Vec<AnalysisEntry> = result.analysis.iter().map(|&(ti, ei, sc)| {
         let idx = ti as usize;
         AnalysisEntry {
             tile_id: format!("{}-{}", lookup::TILE_LOW[idx], lookup::TILE_HIGH[idx]),
             end: if ei == 0 { "left".to_string() } else { "right".to_string() },
             score: sc,
+            explain: if input.explain {
+                explain_root_move(my_hand, opp_hand, left, right, idx, ei, eval_diff, &rules)
+            } else {
+                None
+            },
         }
     }).collect();
-    analysis.sort_by(|a, b| b.score.cmp(&a.score));
+    analysis.sort_by_key(|a| std::cmp::Reverse(a.score));
 
-    let output = SearchOutput {
+    SearchOutput {
         tile_id: final_tile_id,
         end: final_end,
         best_score: result.best_score,
         depth: result.depth,
         nodes: result.nodes,
+        placements_left: result.placements_left,
         analysis,
         tt_probes: Some(result.tt_probes),
         tt_hits: Some(result.tt_hits),
         tt_cutoffs: Some(result.tt_cutoffs),
         tt_hints: Some(result.tt_hints),
+        // Weakened levels play for points, so there is no equity to report
+        win_probability: match objective {
+            equity::Objective::MatchEquity { .. } if skill.is_full_strength() => {
+                Some(equity::to_probability(result.best_score))
+            }
+            _ => None,
+        },
+        warnings,
+    }
+}
+
+/// Tile index of `low`-`high` in set `S`, or an error naming the tile.
+fn set_tile<S: set::DominoSet>(low: i8, high: i8) -> Result<usize, String> {
+    lookup::tile_index::<S>(low, high)
+        .ok_or_else(|| format!("tile {}-{} is not in the double-{} set", low, high, S::MAX_PIP))
+}
+
+/// Bitmask of `tiles` in set `S`.
+fn set_hand<S: set::DominoSet>(tiles: &[TileDesc]) -> Result<S::Bits, String> {
+    use set::Bitboard;
+    tiles.iter().try_fold(S::Bits::ZERO, |h, t| Ok(h | S::Bits::bit(set_tile::<S>(t.low, t.high)?)))
+}
+
+/// A board end in set `S` (`None`: empty board).
+fn set_end<S: set::DominoSet>(end: Option<i8>) -> Result<i8, String> {
+    match end {
+        Some(v) if !(0..=S::MAX_PIP).contains(&v) => Err(format!("board end {} is out of range", v)),
+        Some(v) => Ok(v),
+        None => Ok(S::EMPTY_END),
+    }
+}
+
+/// Board ends recorded with a `moveHistory` placement in set `S`
+/// (`S::EMPTY_END`: the board was empty).
+fn history_ends<S: set::DominoSet>(e: &MoveHistoryEntry) -> Result<(i8, i8), String> {
+    let end = |v: i8| if v == S::EMPTY_END { Ok(v) } else { set_end::<S>(Some(v)) };
+    Ok((end(e.board_left)?, end(e.board_right)?))
+}
+
+/// `wasm_choose_move` for a double-nine or double-twelve hand: the AI to
+/// move, full strength, on the generic engine.
+fn run_choose_move_set<S: set::DominoSet>(input: &SearchInput) -> Result<SearchOutput, String> {
+    let ai_hand = set_hand::<S>(&input.ai_tiles)?;
+    let human_hand = set_hand::<S>(&input.human_tiles)?;
+    let (left, right) = if input.board_empty {
+        (S::EMPTY_END, S::EMPTY_END)
+    } else {
+        (set_end::<S>(input.left)?, set_end::<S>(input.right)?)
     };
 
-    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+    // Puppeteer history, as in `SearchInput::to_position`
+    let who = |player: &str| if player == "ai" { 1 } else { 0 };
+    let mut placements = input.move_history.iter().rev().filter(|e| !e.pass);
+    let (mut p1, mut p2) = ((-1, 0, 0, -1), (-1, 0, 0));
+    if let Some(e) = placements.next() {
+        let (l, r) = history_ends::<S>(e)?;
+        p1 = (who(&e.player), l, r, set_tile::<S>(e.tile_low, e.tile_high)? as i8);
+    }
+    if let Some(e) = placements.next() {
+        let (l, r) = history_ends::<S>(e)?;
+        p2 = (who(&e.player), l, r);
+    }
+
+    let (ai_score, human_score) = input.match_score.as_ref().map_or((0, 0), |ms| (ms.ai, ms.human));
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let objective = match input.objective.as_deref() {
+        Some("matchEquity") => equity::Objective::MatchEquity { ai_score, human_score },
+        _ => equity::Objective::Points,
+    };
+    let result = search::choose_move_set::<S>(
+        ai_hand, human_hand, left, right, 0, ai_score - human_score,
+        p1.0, p1.1, p1.2, p1.3, p2.0, p2.1, p2.2,
+        input.time_budget.unwrap_or(5000.0), &rules, objective, &search::SearchLimits::NONE,
+    );
+
+    let tables = S::tables();
+    let tile_id = |idx: i8| format!("{}-{}", tables.low[idx as usize], tables.high[idx as usize]);
+    let mut analysis: Vec<AnalysisEntry> = result.analysis.iter().map(|&(ti, ei, sc)| AnalysisEntry {
+        tile_id: tile_id(ti),
+        end: end_name(ei),
+        score: sc,
+        explain: None,
+    }).collect();
+    analysis.sort_by_key(|a| std::cmp::Reverse(a.score));
+
+    Ok(SearchOutput {
+        tile_id: if result.best_tile_idx >= 0 { tile_id(result.best_tile_idx) } else { String::new() },
+        end: end_name(result.best_end),
+        best_score: result.best_score,
+        depth: result.depth,
+        nodes: result.nodes,
+        placements_left: result.placements_left,
+        analysis,
+        tt_probes: Some(result.tt_probes),
+        tt_hits: Some(result.tt_hits),
+        tt_cutoffs: Some(result.tt_cutoffs),
+        tt_hints: Some(result.tt_hints),
+        win_probability: match objective {
+            equity::Objective::MatchEquity { .. } => Some(equity::to_probability(result.best_score)),
+            equity::Objective::Points => None,
+        },
+        warnings: Vec::new(),
+    })
+}
+
+/// Evaluation breakdown of the position after the AI plays `idx` on `end`,
+/// or `None` when that move ends the hand.
+#[allow(clippy::too_many_arguments)]
+fn explain_root_move(
+    ai_hand: i32, human_hand: i32, left: i8, right: i8,
+    idx: usize, end: i8, match_diff: i32, rules: &rules::RuleSet,
+) -> Option<EvalExplainDesc> {
+    let ai_after = ai_hand & !(1 << idx);
+    let (l, r) = search::compute_new_ends::<set::DoubleSix>(idx, end, left, right);
+    if ai_after == 0
+        || (movegen::count_moves_bb::<set::DoubleSix>(ai_after, l, r) == 0
+            && movegen::count_moves_bb::<set::DoubleSix>(human_hand, l, r) == 0)
+    {
+        return None;
+    }
+    let b = eval::evaluate_explain::<set::DoubleSix>(ai_after, human_hand, l, r, match_diff, rules);
+    Some(EvalExplainDesc::from_breakdown(&b))
 }
 
 fn find_legal_move<'a>(moves: &'a [LegalMoveDesc], tile_id: &str, end: &str) -> Option<&'a LegalMoveDesc> {
@@ -280,3 +1030,890 @@ fn find_legal_move_by_tile<'a>(moves: &'a [LegalMoveDesc], tile_id: &str) -> Opt
         lm_id == tile_id
     })
 }
+
+/// `SearchLimits::root_moves` for `moves`, and the ones that are not legal.
+fn root_move_mask(state: &game::HandState, moves: &[LegalMoveDesc]) -> (u64, Vec<LegalMoveDesc>) {
+    let mut mask = 0;
+    let mut dropped = Vec::new();
+    for lm in moves {
+        let pip = 0..=6;
+        let legal = (pip.contains(&lm.tile_low) && pip.contains(&lm.tile_high))
+            .then(|| lookup::tile_id_to_index(lm.tile_low, lm.tile_high) as i8)
+            .and_then(|tile| state.find_legal(tile, (lm.end == "right") as i8));
+        match legal {
+            Some((t, e)) => mask |= search::SearchLimits::root_move_bit(t, e),
+            None => dropped.push(lm.clone()),
+        }
+    }
+    (mask, dropped)
+}
+
+fn move_desc(tile: i8, end: i8) -> LegalMoveDesc {
+    LegalMoveDesc {
+        tile_low: lookup::TILE_LOW[tile as usize],
+        tile_high: lookup::TILE_HIGH[tile as usize],
+        end: end_name(end),
+    }
+}
+
+/// Compare the caller's `legalMoves` with the engine's own list; ends are
+/// interchangeable on an empty board or equal ends.
+fn legal_moves_mismatch(
+    state: &game::HandState, engine_moves: &[(i8, i8)], caller: &[LegalMoveDesc],
+) -> Option<WarningDesc> {
+    let same_ends = state.left == 7 || state.left == state.right;
+    let key = |tile: i8, end: i8| (tile, if same_ends { 0 } else { end });
+    let caller_key = |lm: &LegalMoveDesc| {
+        let pip = 0..=6;
+        (pip.contains(&lm.tile_low) && pip.contains(&lm.tile_high)).then(|| {
+            key(lookup::tile_id_to_index(lm.tile_low, lm.tile_high) as i8, (lm.end == "right") as i8)
+        })
+    };
+    let caller_keys: Vec<_> = caller.iter().filter_map(caller_key).collect();
+    let engine_keys: Vec<_> = engine_moves.iter().map(|&(t, e)| key(t, e)).collect();
+
+    let missing: Vec<LegalMoveDesc> = engine_moves.iter()
+        .filter(|&&(t, e)| !caller_keys.contains(&key(t, e)))
+        .map(|&(t, e)| move_desc(t, e))
+        .collect();
+    let unexpected: Vec<LegalMoveDesc> = caller.iter()
+        .filter(|lm| caller_key(lm).is_none_or(|k| !engine_keys.contains(&k)))
+        .cloned()
+        .collect();
+    if missing.is_empty() && unexpected.is_empty() {
+        return None;
+    }
+    Some(WarningDesc {
+        missing,
+        unexpected,
+        ..WarningDesc::new("legalMovesMismatch", "legalMoves disagrees with the engine's rules")
+    })
+}
+
+/// N-side search for the 3- and 4-player variants. Input: `MultiSearchInput`
+/// JSON, output: `MultiSearchOutput` JSON (empty `tileId` when the side must pass).
+/// With `teams` set on a 4-seat position, partners share one utility;
+/// `maxPip` 9 or 12 selects the double-nine / double-twelve set.
+#[wasm_bindgen]
+pub fn wasm_choose_move_multi(input_json: &str) -> String {
+    let output = match serde_json::from_str::<MultiSearchInput>(input_json) {
+        Err(e) => Err(e.to_string()),
+        Ok(input) if input.hands.len() < 2 || input.hands.len() > multi::MAX_SIDES
+            || input.to_move >= input.hands.len() =>
+        {
+            Err(format!("expected 2 to {} hands with toMove among them", multi::MAX_SIDES))
+        }
+        Ok(input) => match input.max_pip {
+            0 | 6 => run_multi::<set::DoubleSix>(&input),
+            9 => run_multi::<set::DoubleNine>(&input),
+            12 => run_multi::<set::DoubleTwelve>(&input),
+            n => Err(format!("maxPip {} is not 6, 9 or 12", n)),
+        },
+    };
+    let output = output.unwrap_or_else(|e| MultiSearchOutput { error: Some(e), ..Default::default() });
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn run_multi<S: set::DominoSet>(input: &MultiSearchInput) -> Result<MultiSearchOutput, String> {
+    let hands = input.hands.iter().map(|tiles| set_hand::<S>(tiles)).collect::<Result<Vec<_>, _>>()?;
+    let (left, right) = if input.board_empty {
+        (S::EMPTY_END, S::EMPTY_END)
+    } else {
+        (set_end::<S>(input.left)?, set_end::<S>(input.right)?)
+    };
+
+    let mut pos = multi::MultiPosition::<S>::new(&hands, left, right, input.to_move);
+    pos.cons_pass = input.cons_pass;
+
+    let time_budget = input.time_budget.unwrap_or(2000.0);
+    let team_config = match input.teams.as_deref() {
+        Some("2v2") => Some(team::TeamConfig::TwoVsTwo),
+        Some("1v3") => Some(team::TeamConfig::OneVsThree),
+        _ => None,
+    };
+    let result = match team_config {
+        Some(config) if hands.len() == 4 => {
+            let rules = team::TeamRules {
+                config,
+                block_rule: match input.block_rule.as_deref() {
+                    Some("aggressor") => team::TeamBlockRule::Aggressor,
+                    _ => team::TeamBlockRule::LowestTeam,
+                },
+            };
+            team::choose_move_team(&pos, &rules, time_budget)
+        }
+        _ => {
+            let mode = match input.mode.as_deref() {
+                Some("paranoid") => multi::MultiMode::Paranoid,
+                _ => multi::MultiMode::MaxN,
+            };
+            multi::choose_move_multi(&pos, mode, time_budget)
+        }
+    };
+
+    let tables = S::tables();
+    let mut analysis: Vec<AnalysisEntry> = result.analysis.iter().map(|&(ti, ei, sc)| {
+        let idx = ti as usize;
+        AnalysisEntry {
+            tile_id: format!("{}-{}", tables.low[idx], tables.high[idx]),
+            end: if ei == 0 { "left".to_string() } else { "right".to_string() },
+            score: sc,
+            explain: None,
+        }
+    }).collect();
+    analysis.sort_by_key(|a| std::cmp::Reverse(a.score));
+
+    Ok(MultiSearchOutput {
+        tile_id: if result.best_tile_idx >= 0 {
+            let idx = result.best_tile_idx as usize;
+            format!("{}-{}", tables.low[idx], tables.high[idx])
+        } else {
+            String::new()
+        },
+        end: if result.best_end == 1 { "right".to_string() } else { "left".to_string() },
+        scores: result.values[..hands.len()].to_vec(),
+        depth: result.depth,
+        nodes: result.nodes,
+        exact: result.exact,
+        analysis,
+        error: None,
+    })
+}
+
+/// Post-game review: replay a saved hand (analyze-game.js JSON: `leader`,
+/// `humanTiles`, `aiTiles`, `moves`) and solve every decision point for both
+/// players. Output: `AnalyzeGameOutput` JSON; `error` is set when the record
+/// names an unknown tile or does not replay.
+#[wasm_bindgen]
+pub fn wasm_analyze_game(input_json: &str) -> String {
+    let output = match serde_json::from_str::<AnalyzeGameInput>(input_json) {
+        Ok(input) => run_analyze_game(&input),
+        Err(e) => AnalyzeGameOutput { error: Some(e.to_string()), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// One-line `notation` form of a `SearchInput` JSON position, for bug
+/// reports; empty when the input does not parse. The layout chain is not
+/// part of `SearchInput`, so the board prints as its two ends.
+#[wasm_bindgen]
+pub fn wasm_position_notation(input_json: &str) -> String {
+    serde_json::from_str::<SearchInput>(input_json).map_err(|e| e.to_string())
+        .and_then(|input| input.to_position())
+        .map(|pos| pos.to_text())
+        .unwrap_or_default()
+}
+
+/// Legal moves of the side to move and whether the hand is over, so the UI
+/// need not carry its own rules. Input: a position as for
+/// `wasm_choose_move`; a `legalMoves` list in it is ignored. Output:
+/// `LegalMovesOutput` JSON; `error` is set for an invalid position (unknown
+/// pips included).
+#[wasm_bindgen]
+pub fn wasm_legal_moves(position: &str) -> String {
+    let output = match parse_search_input(position) {
+        Ok((input, pos)) => {
+            let hand_end = hand_end_desc(&pos.state, &input);
+            let mut buf = [(0i8, 0i8); lookup::NUM_TILES];
+            let n = if hand_end.is_some() { 0 } else { pos.state.legal_moves(&mut buf) };
+            LegalMovesOutput {
+                to_move: analysis::side_name(pos.state.to_move).to_string(),
+                moves: buf[..n].iter().map(|&(t, e)| move_desc(t, e)).collect(),
+                hand_end,
+                error: None,
+            }
+        }
+        Err(e) => LegalMovesOutput { error: Some(e), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Play `move_json` (`ApplyMoveDesc`) for the side to move. Input: a
+/// position as for `wasm_choose_move`. Output: `ApplyMoveOutput` JSON with
+/// the new position in `notation` form, which every export accepts, or
+/// `error` for an invalid position or move.
+#[wasm_bindgen]
+pub fn wasm_apply_move(position: &str, move_json: &str) -> String {
+    let output = match run_apply_move(position, move_json) {
+        Ok(v) => v,
+        Err(e) => ApplyMoveOutput { error: Some(e), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn run_apply_move(position: &str, move_json: &str) -> Result<ApplyMoveOutput, String> {
+    let (input, mut pos) = parse_search_input(position)?;
+    let mv: ApplyMoveDesc = serde_json::from_str(move_json).map_err(|e| e.to_string())?;
+    if pos.state.hand_end().is_some() {
+        return Err("the hand is over".to_string());
+    }
+    if mv.pass {
+        if pos.state.can_move(pos.state.to_move) {
+            return Err("cannot pass with a legal move".to_string());
+        }
+        pos.pass();
+    } else {
+        let (t, e) = legal_placement(&pos.state, &mv)?;
+        pos.place(t, e);
+    }
+    Ok(ApplyMoveOutput {
+        position: pos.to_text(),
+        to_move: analysis::side_name(pos.state.to_move).to_string(),
+        hand_end: hand_end_desc(&pos.state, &input),
+        error: None,
+    })
+}
+
+/// The (tile, end) a placement `ApplyMoveDesc` stands for, if it is legal.
+fn legal_placement(state: &game::HandState, mv: &ApplyMoveDesc) -> Result<(i8, i8), String> {
+    if !(0..=6).contains(&mv.tile_low) || !(0..=6).contains(&mv.tile_high) {
+        return Err(format!("no tile [{}-{}]", mv.tile_low, mv.tile_high));
+    }
+    let tile = lookup::tile_id_to_index(mv.tile_low, mv.tile_high) as i8;
+    let end = match mv.end.as_str() {
+        "right" => 1,
+        "left" | "" => 0,
+        e => return Err(format!("unknown end '{}'", e)),
+    };
+    state.find_legal(tile, end)
+        .ok_or_else(|| format!("[{}-{}] on the {} is not legal", mv.tile_low, mv.tile_high, end_name(end)))
+}
+
+/// Grade one move of the side to move before it is played: its value under
+/// best play and the opponent's expected reply line, searching only that
+/// move for `budget` ms (0 = the input's `timeBudget`, default 5000).
+/// Input: a position as for `wasm_choose_move` and a placement as for
+/// `wasm_apply_move`. Output: `EvaluateMoveOutput` JSON.
+#[wasm_bindgen]
+pub fn wasm_evaluate_move(position: &str, move_json: &str, budget: f64) -> String {
+    let output = match run_evaluate_move(position, move_json, budget) {
+        Ok(v) => v,
+        Err(e) => EvaluateMoveOutput { error: Some(e), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn run_evaluate_move(position: &str, move_json: &str, budget: f64) -> Result<EvaluateMoveOutput, String> {
+    let (input, pos) = parse_search_input(position)?;
+    let mv: ApplyMoveDesc = serde_json::from_str(move_json).map_err(|e| e.to_string())?;
+    if pos.state.hand_end().is_some() {
+        return Err("the hand is over".to_string());
+    }
+    if mv.pass {
+        return Err("only placements can be evaluated".to_string());
+    }
+    let (tile, end) = legal_placement(&pos.state, &mv)?;
+
+    let mover = pos.state.to_move;
+    let (my_score, opp_score) = (pos.score[mover as usize], pos.score[1 - mover as usize]);
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let objective = match input.objective.as_deref() {
+        Some("matchEquity") => equity::Objective::MatchEquity { ai_score: my_score, human_score: opp_score },
+        _ => equity::Objective::Points,
+    };
+    let budget = if budget > 0.0 { budget } else { input.time_budget.unwrap_or(5000.0) };
+    let limits = search::SearchLimits {
+        root_moves: search::SearchLimits::root_move_bit(tile, end),
+        ..search::SearchLimits::NONE
+    };
+    let r = pos.state.search_limited(mover, budget, &rules, objective, my_score - opp_score, &limits);
+    if r.best_tile_idx < 0 {
+        return Err("the search returned no result".to_string());
+    }
+
+    Ok(EvaluateMoveOutput {
+        tile_id: tile_name(tile),
+        end: end_name(end),
+        score: r.best_score,
+        depth: r.depth,
+        nodes: r.nodes,
+        placements_left: r.placements_left,
+        reply: line_descs(1 - mover, r.pv.get(1..).unwrap_or(&[])),
+        error: None,
+    })
+}
+
+/// A line of (tile, end) moves, tile -1 for a pass, with `first` moving first.
+fn line_descs(first: i8, line: &[(i8, i8)]) -> Vec<GameMoveDesc> {
+    line.iter().enumerate().map(|(i, &(t, e))| GameMoveDesc {
+        player: analysis::side_name(if i & 1 == 0 { first } else { 1 - first }).to_string(),
+        pass: t < 0,
+        draw: false,
+        tile: (t >= 0).then(|| TileDesc { low: lookup::TILE_LOW[t as usize], high: lookup::TILE_HIGH[t as usize] }),
+        end: (t >= 0).then(|| end_name(e)),
+    }).collect()
+}
+
+fn hand_end_desc(state: &game::HandState, input: &SearchInput) -> Option<HandEndDesc> {
+    let end = state.hand_end()?;
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let ai_points = state.result_for(game::AI, &rules)?;
+    Some(HandEndDesc {
+        kind: match end {
+            game::HandEnd::Domino => "domino",
+            game::HandEnd::Lock => "lock",
+            game::HandEnd::PassBlock => "passBlock",
+        }.to_string(),
+        winner: analysis::side_name(match end {
+            game::HandEnd::Domino if state.hands[game::AI as usize] == 0 => game::AI,
+            game::HandEnd::Domino => game::HUMAN,
+            _ if ai_points > 0 => game::AI,
+            _ => game::HUMAN,
+        }).to_string(),
+        points: ai_points.abs(),
+        aggressor: match end {
+            game::HandEnd::Domino => None,
+            _ => Some(analysis::side_name(state.aggressor(&rules)).to_string()),
+        },
+    })
+}
+
+/// Export a game.js hand (`GameHistoryDesc` JSON) as a DGN record, after
+/// replaying it through the rules. Output: `DgnExportOutput` JSON.
+#[wasm_bindgen]
+pub fn wasm_dgn_from_history(input_json: &str) -> String {
+    let output = match serde_json::from_str::<GameHistoryDesc>(input_json).map_err(|e| e.to_string())
+        .and_then(|h| history_to_dgn(&h))
+    {
+        Ok(game) => DgnExportOutput { dgn: game.to_text(), error: None },
+        Err(e) => DgnExportOutput { error: Some(e), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Import DGN text (one or more games), validating each by replay.
+/// Output: `DgnImportOutput` JSON, one `GameHistoryDesc` per game.
+#[wasm_bindgen]
+pub fn wasm_dgn_to_history(text: &str) -> String {
+    let games = dgn::parse_all(text).and_then(|games| {
+        games.iter().enumerate().map(|(i, g)| {
+            let replay = g.validate().map_err(|e| format!("game {}: {}", i + 1, e))?;
+            Ok(GameHistoryDesc { result: replay.result, ..dgn_to_history(g) })
+        }).collect::<Result<Vec<_>, String>>()
+    });
+    let output = match games {
+        Ok(games) => DgnImportOutput { games, error: None },
+        Err(e) => DgnImportOutput { error: Some(e), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Seeded 14/14 deal (see `deal` for the algorithm), so browser games can be
+/// replayed natively. Output: `DealOutput` JSON, tiles in index order.
+#[wasm_bindgen]
+pub fn wasm_deal(seed: u32) -> String {
+    let [human, ai] = deal::deal(seed);
+    let output = DealOutput { seed, human_tiles: hand_tiles(human), ai_tiles: hand_tiles(ai) };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// First-hand lead choice: search the deal with each side leading.
+/// Input: `AdviseLeadInput` JSON, output: `AdviseLeadOutput` JSON.
+#[wasm_bindgen]
+pub fn wasm_advise_lead(input_json: &str) -> String {
+    let output = match serde_json::from_str::<AdviseLeadInput>(input_json) {
+        Ok(input) => run_advise_lead(&input),
+        Err(e) => AdviseLeadOutput { error: Some(e.to_string()), ..Default::default() },
+    };
+    serde_json::to_string(&output).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn run_advise_lead(input: &AdviseLeadInput) -> AdviseLeadOutput {
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let advice = hand_mask(&input.ai_tiles).and_then(|ai| {
+        let human = input.human_tiles.as_deref().map(hand_mask).transpose()?;
+        deal::advise_lead(ai, human, input.time_budget.unwrap_or(5000.0), &rules)
+    });
+    match advice {
+        Ok(a) => AdviseLeadOutput {
+            lead_value: a.lead_value,
+            should_lead: a.should_lead(),
+            human_leads: LeaderOutcomeDesc::from_outcome(&a.evaluation.human_leads),
+            ai_leads: LeaderOutcomeDesc::from_outcome(&a.evaluation.ai_leads),
+            error: None,
+        },
+        Err(e) => AdviseLeadOutput { error: Some(e), ..Default::default() },
+    }
+}
+
+/// Load evaluation parameters (the params file written by the `tune` tool)
+/// for all later searches. Returns an empty string on success, otherwise the
+/// parse error; the previous parameters stay in place on error. New
+/// parameters invalidate the transposition table, so no search reuses
+/// values scored under the old ones.
+#[wasm_bindgen]
+pub fn wasm_set_eval_params(text: &str) -> String {
+    match eval::EvalParams::parse(text) {
+        Ok(p) => {
+            eval::set_params(&p);
+            String::new()
+        }
+        Err(e) => e,
+    }
+}
+
+/// Turn the embedded opening book on (the default) or off. With the book
+/// off the first lead of a deal is always searched.
+#[wasm_bindgen]
+pub fn wasm_set_book_enabled(enabled: bool) {
+    book::set_enabled(enabled);
+}
+
+/// The `analysis` record of a saved hand; an error names the first unknown
+/// tile.
+fn game_record(input: &AnalyzeGameInput) -> Result<analysis::GameRecord, String> {
+    let who = |player: &str| if player == "ai" { analysis::AI } else { analysis::HUMAN };
+    let moves = input.moves.iter().enumerate().map(|(i, m)| Ok(match (&m.tile, m.pass) {
+        (Some(t), false) => analysis::GameMove {
+            who: who(&m.player),
+            tile: set_tile::<set::DoubleSix>(t.low, t.high).map_err(|e| format!("move {}: {}", i + 1, e))? as i8,
+            end: if m.end.as_deref() == Some("right") { 1 } else { 0 },
+        },
+        _ => analysis::GameMove { who: who(&m.player), tile: -1, end: 0 },
+    })).collect::<Result<_, String>>()?;
+    Ok(analysis::GameRecord {
+        leader: who(&input.leader),
+        human_hand: hand_mask(&input.human_tiles)?,
+        ai_hand: hand_mask(&input.ai_tiles)?,
+        moves,
+    })
+}
+
+fn run_analyze_game(input: &AnalyzeGameInput) -> AnalyzeGameOutput {
+    let game = match game_record(input) {
+        Ok(g) => g,
+        Err(e) => return AnalyzeGameOutput { error: Some(e), ..Default::default() },
+    };
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let time_budget = input.time_budget.unwrap_or(30000.0);
+
+    let result = match analysis::analyze_game(&game, time_budget, &rules) {
+        Ok(r) => r,
+        Err(e) => return AnalyzeGameOutput { error: Some(e), ..Default::default() },
+    };
+    let summary = |s: &analysis::PlayerSummary| PlayerSummaryDesc {
+        decisions: s.decisions,
+        perfect: s.perfect,
+        points_lost: s.points_lost,
+        accuracy: s.accuracy(),
+    };
+    AnalyzeGameOutput {
+        moves: result.moves.iter().map(|r| MoveReviewDesc {
+            move_num: r.index + 1,
+            player: analysis::side_name(r.played.who).to_string(),
+            pass: r.played.is_pass(),
+            tile_id: tile_name(r.played.tile),
+            end: end_name(r.played.end),
+            best_tile_id: tile_name(r.best_tile),
+            best_end: end_name(r.best_end),
+            best_score: r.best_score,
+            played_score: r.played_score,
+            loss: r.loss,
+            grade: analysis::grade(r.loss).to_string(),
+            forced: !r.is_decision(),
+            depth: r.depth,
+            solved: r.solved,
+        }).collect(),
+        human: summary(&result.human),
+        ai: summary(&result.ai),
+        error: None,
+    }
+}
+
+fn history_to_dgn(h: &GameHistoryDesc) -> Result<dgn::DgnGame, String> {
+    let side = |player: &str| match player {
+        "ai" => Ok(game::AI),
+        "human" => Ok(game::HUMAN),
+        _ => Err(format!("unknown player '{}'", player)),
+    };
+    let tile = |t: &Option<TileDesc>, i: usize| match t {
+        Some(t) => set_tile::<set::DoubleSix>(t.low, t.high).map(|t| t as i8).map_err(|e| format!("move {}: {}", i + 1, e)),
+        None => Err(format!("move {}: missing tile", i + 1)),
+    };
+    let leader = match (&h.leader, h.move_history.first()) {
+        (Some(l), _) => side(l)?,
+        (None, Some(m)) => side(&m.player)?,
+        (None, None) => return Err("no leader and no moves".to_string()),
+    };
+
+    let mut game = dgn::DgnGame::new([hand_mask(&h.human_tiles)?, hand_mask(&h.ai_tiles)?], leader);
+    let draws = h.move_history.iter().any(|m| m.draw);
+    if h.human_tiles.len() != 14 || !h.boneyard.is_empty() || draws {
+        game.variant = dgn::Variant::Draw;
+    }
+    game.boneyard = h.boneyard.iter()
+        .map(|t| set_tile::<set::DoubleSix>(t.low, t.high).map(|t| t as i8))
+        .collect::<Result<_, _>>()?;
+    game.players = [h.human_name.clone().unwrap_or_default(), h.ai_name.clone().unwrap_or_default()];
+    game.rules = h.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    game.seed = h.seed;
+    game.match_score = h.match_score.as_ref().map_or([0, 0], |ms| [ms.human, ms.ai]);
+    game.date = h.date.clone();
+    for (i, m) in h.move_history.iter().enumerate() {
+        let action = if m.pass {
+            dgn::Action::Pass
+        } else if m.draw {
+            dgn::Action::Draw { tile: tile(&m.tile, i)? }
+        } else {
+            let end = match m.end.as_deref() {
+                Some("left") => 0,
+                Some("right") => 1,
+                _ => -1,
+            };
+            dgn::Action::Place { tile: tile(&m.tile, i)?, end }
+        };
+        game.moves.push(dgn::DgnMove { who: side(&m.player)?, action });
+    }
+    game.result = game.validate()?.result;
+    Ok(game)
+}
+
+fn dgn_to_history(g: &dgn::DgnGame) -> GameHistoryDesc {
+    let player = |who: i8| analysis::side_name(who).to_string();
+    let tile = |t: i8| Some(TileDesc { low: lookup::TILE_LOW[t as usize], high: lookup::TILE_HIGH[t as usize] });
+    let name = |s: &String| if s.is_empty() { None } else { Some(s.clone()) };
+    GameHistoryDesc {
+        leader: Some(player(g.leader)),
+        human_tiles: hand_tiles(g.hands[0]),
+        ai_tiles: hand_tiles(g.hands[1]),
+        boneyard: g.boneyard.iter().map(|&t| tile(t).unwrap()).collect(),
+        move_history: g.moves.iter().map(|m| {
+            let mut d = GameMoveDesc { player: player(m.who), pass: false, draw: false, tile: None, end: None };
+            match m.action {
+                dgn::Action::Pass => d.pass = true,
+                dgn::Action::Draw { tile: t } => {
+                    d.draw = true;
+                    d.tile = tile(t);
+                }
+                dgn::Action::Place { tile: t, end } => {
+                    d.tile = tile(t);
+                    d.end = match end {
+                        0 => Some("left".to_string()),
+                        1 => Some("right".to_string()),
+                        _ => None,
+                    };
+                }
+            }
+            d
+        }).collect(),
+        match_score: Some(MatchScore { human: g.match_score[0], ai: g.match_score[1] }),
+        rules: Some(RulesDesc::from_rule_set(&g.rules)),
+        seed: g.seed,
+        date: g.date.clone(),
+        human_name: name(&g.players[0]),
+        ai_name: name(&g.players[1]),
+        result: g.result,
+    }
+}
+
+fn hand_tiles(hand: i32) -> Vec<TileDesc> {
+    (0..lookup::NUM_TILES)
+        .filter(|&t| hand & (1 << t) != 0)
+        .map(|t| TileDesc { low: lookup::TILE_LOW[t], high: lookup::TILE_HIGH[t] })
+        .collect()
+}
+
+/// Double-six bitmask of `tiles`; an error names the first unknown tile.
+fn hand_mask(tiles: &[TileDesc]) -> Result<i32, String> {
+    set_hand::<set::DoubleSix>(tiles)
+}
+
+/// "lo-hi" for a tile index, empty for none (pass).
+fn tile_name(idx: i8) -> String {
+    if idx < 0 {
+        String::new()
+    } else {
+        format!("{}-{}", lookup::TILE_LOW[idx as usize], lookup::TILE_HIGH[idx as usize])
+    }
+}
+
+fn end_name(end: i8) -> String {
+    if end == 1 { "right".to_string() } else { "left".to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::fixtures::{dealt, SIX_OR_LOCK, SIX_OR_LOCK_TILES};
+
+    fn json(s: String) -> Value {
+        serde_json::from_str(&s).unwrap()
+    }
+
+    #[test]
+    fn test_choose_move_larger_sets() {
+        // Top double lets the human out for -1; [0-1] locks for -(18 + 17)
+        let out = json(crate::wasm_choose_move(r#"{"maxPip":9,"boardEmpty":true,"timeBudget":200,
+            "aiTiles":[{"low":9,"high":9},{"low":0,"high":1}],"humanTiles":[{"low":8,"high":9}]}"#));
+        assert_eq!((&out["tileId"], &out["bestScore"], &out["placementsLeft"]), (&Value::from("9-9"), &Value::from(-1), &Value::from(2)));
+        assert_eq!(out["analysis"][1], json(r#"{"tileId":"0-1","end":"left","score":-35}"#.to_string()));
+
+        let out = json(crate::wasm_choose_move(r#"{"maxPip":12,"left":12,"right":3,"timeBudget":200,
+            "aiTiles":[{"low":3,"high":11},{"low":0,"high":12}],"humanTiles":[{"low":5,"high":5}]}"#));
+        // Human's [5-5] never fits: the AI goes out either way for 10
+        assert_eq!((&out["bestScore"], &out["placementsLeft"]), (&Value::from(10), &Value::from(2)));
+
+        for bad in [
+            r#"{"maxPip":9,"aiTiles":[{"low":10,"high":1}],"boardEmpty":true}"#,
+            r#"{"maxPip":12,"aiTiles":[{"low":1,"high":1}],"left":13,"right":1}"#,
+        ] {
+            assert_eq!(json(crate::wasm_choose_move(bad))["warnings"][0]["code"], "badInput", "{}", bad);
+        }
+        // Other sets used to fall through to the double-six engine
+        for n in [7, 10] {
+            let out = json(crate::wasm_choose_move(&format!(r#"{{"maxPip":{},"boardEmpty":true,{}}}"#, n, SIX_OR_LOCK_TILES)));
+            assert_eq!(out["tileId"], "");
+            assert_eq!(out["warnings"][0]["code"], "badInput");
+            assert_eq!(out["warnings"][0]["message"], format!("maxPip {} is not 6, 9 or 12", n));
+        }
+        let out = json(crate::wasm_choose_move(&format!(r#"{{"maxPip":6,"boardEmpty":true,"timeBudget":100,{}}}"#, SIX_OR_LOCK_TILES)));
+        assert_eq!(out["tileId"], "6-6");
+    }
+
+    #[test]
+    fn test_choose_move_multi_rejects_bad_pips() {
+        // [9-1] used to map to some other tile and play "2-2"
+        let hands = |t: &str| format!(r#"{{"hands":[[{}],[{{"low":2,"high":2}}],[{{"low":3,"high":4}}]],"toMove":0,
+            "boardEmpty":true,"timeBudget":100}}"#, t);
+        let out = json(crate::wasm_choose_move_multi(&hands(r#"{"low":9,"high":1}"#)));
+        assert_eq!(out["tileId"], "");
+        assert!(out["error"].as_str().unwrap().contains("9-1"));
+        let out = json(crate::wasm_choose_move_multi(&hands(r#"{"low":6,"high":1}"#)));
+        assert_eq!(out["tileId"], "1-6");
+        assert!(out.get("error").is_none());
+
+        let nine = hands(r#"{"low":9,"high":1}"#).replacen('{', r#"{"maxPip":9,"#, 1);
+        assert_eq!(json(crate::wasm_choose_move_multi(&nine))["tileId"], "1-9");
+        let out = json(crate::wasm_choose_move_multi(&nine.replace("maxPip\":9", "maxPip\":7")));
+        assert!(out["error"].as_str().unwrap().contains("maxPip"));
+        let out = json(crate::wasm_choose_move_multi(&nine.replace("\"boardEmpty\":true", "\"left\":10,\"right\":1")));
+        assert!(out["error"].is_string());
+        assert!(json(crate::wasm_choose_move_multi(&nine.replace("\"toMove\":0", "\"toMove\":3")))["error"].is_string());
+    }
+
+    #[test]
+    fn test_choose_move_rejects_bad_pips() {
+        let hands = r#""aiTiles":[{"low":6,"high":6},{"low":0,"high":1}],"humanTiles":[{"low":5,"high":6}]"#;
+        for bad in [
+            r#""aiTiles":[{"low":7,"high":1}],"humanTiles":[{"low":5,"high":6}],"boardEmpty":true"#.to_string(),
+            format!(r#"{},"left":9,"right":1"#, hands),
+            format!(r#"{},"left":6,"right":1,"moveHistory":[{{"player":"human","tileLow":1,"tileHigh":8,
+                "boardLeft":6,"boardRight":1}}]"#, hands),
+            format!(r#"{},"left":6,"right":1,"moveHistory":[{{"player":"human","tileLow":1,"tileHigh":6,
+                "boardLeft":6,"boardRight":-2}}]"#, hands),
+        ] {
+            let out = json(crate::wasm_choose_move(&format!("{{{},\"timeBudget\":100}}", bad)));
+            assert_eq!((&out["tileId"], &out["warnings"][0]["code"]), (&Value::from(""), &Value::from("badInput")), "{}", bad);
+        }
+        // 7 is the empty board in a history entry
+        let out = json(crate::wasm_choose_move(&format!(r#"{{{},"left":1,"right":1,"timeBudget":100,
+            "moveHistory":[{{"player":"human","tileLow":1,"tileHigh":1,"boardLeft":7,"boardRight":7}}]}}"#, hands)));
+        assert_eq!(out["tileId"], "0-1");
+    }
+
+    #[test]
+    fn test_legal_and_apply_moves_reject_bad_pips() {
+        let pos = r#"{"aiTiles":[{"low":6,"high":6},{"low":0,"high":1}],"humanTiles":[{"low":5,"high":6}],"boardEmpty":true}"#;
+        for bad in [
+            pos.replace(r#""low":5"#, r#""low":7"#),
+            pos.replace(r#""boardEmpty":true"#, r#""left":6,"right":-1"#),
+            "56/0189 - a 0 -".to_string(),
+        ] {
+            assert!(json(crate::wasm_legal_moves(&bad))["error"].is_string(), "{}", bad);
+            let out = json(crate::wasm_apply_move(&bad, r#"{"tileLow":6,"tileHigh":6}"#));
+            assert!(out["error"].is_string(), "{}", bad);
+        }
+        for mv in [r#"{"tileLow":6,"tileHigh":7}"#, r#"{"tileLow":-1,"tileHigh":0}"#] {
+            assert!(json(crate::wasm_apply_move(pos, mv))["error"].is_string(), "{}", mv);
+        }
+        assert_eq!(json(crate::wasm_apply_move(pos, r#"{"tileLow":6,"tileHigh":6}"#))["toMove"], "human");
+    }
+
+    #[test]
+    fn test_advise_lead_rejects_bad_pips() {
+        let ai: Vec<String> = crate::hand_tiles(crate::deal::deal(3)[1]).iter()
+            .map(|t| serde_json::to_string(t).unwrap()).collect();
+        let input = |ai: &[String], human: &str| format!(r#"{{"aiTiles":[{}],{}"timeBudget":50}}"#, ai.join(","), human);
+        let mut bad = ai.clone();
+        bad[13] = r#"{"low":7,"high":7}"#.to_string();
+        for input in [input(&bad, ""), input(&ai, r#""humanTiles":[{"low":3,"high":-1}],"#)] {
+            let out = json(crate::wasm_advise_lead(&input));
+            assert!(out["error"].as_str().unwrap().contains("not in the double-6 set"), "{}", input);
+        }
+        assert!(json(crate::wasm_advise_lead(&input(&ai, "")))["error"].is_null());
+    }
+
+    #[test]
+    fn test_game_records_reject_bad_pips() {
+        // A dealt hand, AI to lead, after its first legal move
+        let [human, ai] = crate::deal::deal(5);
+        let mut buf = [(0i8, 0i8); crate::lookup::NUM_TILES];
+        crate::game::HandState::new(human, ai, crate::game::AI).legal_moves(&mut buf);
+        let lead = serde_json::json!({
+            "player": "ai", "end": "left",
+            "tile": { "low": crate::lookup::TILE_LOW[buf[0].0 as usize], "high": crate::lookup::TILE_HIGH[buf[0].0 as usize] },
+        });
+        let game = serde_json::json!({
+            "leader": "ai", "timeBudget": 100,
+            "humanTiles": crate::hand_tiles(human), "aiTiles": crate::hand_tiles(ai),
+            "moves": [lead],
+        });
+        let review = |g: &Value| json(crate::wasm_analyze_game(&g.to_string()));
+        let export = |g: &Value| json(crate::wasm_dgn_from_history(&g.to_string()));
+        assert!(review(&game)["error"].is_null());
+        assert!(export(&game)["error"].is_null());
+
+        for path in ["/humanTiles/0/low", "/aiTiles/3/high", "/moves/0/tile/high"] {
+            let mut bad = game.clone();
+            *bad.pointer_mut(path).unwrap() = Value::from(7);
+            for out in [review(&bad), export(&bad)] {
+                assert!(out["error"].as_str().unwrap().contains("not in the double-6 set"), "{} {}", path, out);
+            }
+        }
+        let mut bad = game.clone();
+        bad["boneyard"] = serde_json::json!([{ "low": 2, "high": 9 }]);
+        assert!(export(&bad)["error"].as_str().unwrap().contains("2-9"));
+    }
+
+    #[test]
+    fn test_wasm_legal_and_apply_moves() {
+        let out = json(crate::wasm_legal_moves(SIX_OR_LOCK));
+        assert_eq!(out["toMove"], "ai");
+        assert_eq!(out["moves"].as_array().unwrap().len(), 2);
+        assert_eq!(out["moves"][0], json(r#"{"tileLow":0,"tileHigh":1,"end":"left"}"#.to_string()));
+        assert!(out["handEnd"].is_null());
+
+        // A caller's list is no answer: the engine's own comes back
+        let real = out["moves"].clone();
+        let out = json(crate::wasm_legal_moves(&format!(r#"{{{},"boardEmpty":true,
+            "legalMoves":[{{"tileLow":5,"tileHigh":6,"end":"right"}}]}}"#, SIX_OR_LOCK_TILES)));
+        assert_eq!(out["moves"], real);
+        let out = json(crate::wasm_legal_moves(r#"{"position":"56/66 01 h 0 a01@01",
+            "legalMoves":[{"tileLow":5,"tileHigh":6,"end":"left"}]}"#));
+        assert_eq!(out["moves"], json("[]".to_string()));
+
+        // Feeding a listed move back in: [0-1] locks the board at once
+        let out = json(crate::wasm_apply_move(SIX_OR_LOCK, &real[0].to_string()));
+        assert_eq!(out["position"], "56/66 01 h 0 a01@01 0-0");
+        assert_eq!(out["handEnd"], json(r#"{"kind":"lock","winner":"human","points":23,"aggressor":"ai"}"#.to_string()));
+        assert!(json(crate::wasm_legal_moves(out["position"].as_str().unwrap()))["moves"]
+            .as_array().unwrap().is_empty());
+
+        // [6-6] leaves the human to play [5-6] and go out
+        let out = json(crate::wasm_apply_move(SIX_OR_LOCK, r#"{"tileLow":6,"tileHigh":6}"#));
+        assert!(out["handEnd"].is_null());
+        let out = json(crate::wasm_apply_move(out["position"].as_str().unwrap(),
+                                              r#"{"tileLow":5,"tileHigh":6,"end":"right"}"#));
+        assert_eq!(out["handEnd"]["kind"], "domino");
+        assert_eq!(out["handEnd"]["winner"], "human");
+
+        for (pos, mv) in [
+            (SIX_OR_LOCK, r#"{"pass":true}"#),                             // has a move
+            ("56/0166 66 h 0 a66@66", r#"{"tileLow":0,"tileHigh":1}"#),   // not human's tile
+            ("56/66 01 h 0 a01@01", r#"{"pass":true}"#),                   // hand over
+        ] {
+            assert!(json(crate::wasm_apply_move(pos, mv))["error"].is_string(), "{} {}", pos, mv);
+        }
+
+        // Mid-deal: the listed moves are the engine's, and each one applies
+        let pos = dealt(21, 5);
+        let text = pos.to_text();
+        let out = json(crate::wasm_legal_moves(&text));
+        let moves = out["moves"].as_array().unwrap();
+        let mut buf = [(0i8, 0i8); crate::lookup::NUM_TILES];
+        assert_eq!(moves.len(), pos.state.legal_moves(&mut buf));
+        assert!(moves.len() > 1);
+        for mv in moves {
+            let out = json(crate::wasm_apply_move(&text, &mv.to_string()));
+            let after = crate::notation::Position::parse(out["position"].as_str().unwrap()).unwrap();
+            assert_eq!(after.state.tiles_left(), pos.state.tiles_left() - 1, "{}", mv);
+            assert_eq!(after.state.to_move, 1 - pos.state.to_move);
+        }
+    }
+
+    #[test]
+    fn test_choose_move_reports_fallbacks() {
+        let choose = |input: &str| json(crate::wasm_choose_move(input));
+
+        let out = choose(&format!(r#"{{{},"boardEmpty":true,"timeBudget":200,"legalMoves":[
+            {{"tileLow":0,"tileHigh":1,"end":"left"}},{{"tileLow":6,"tileHigh":6,"end":"left"}}]}}"#, SIX_OR_LOCK_TILES));
+        assert_eq!(out["tileId"], "6-6");
+        assert!(out.get("warnings").is_none());
+
+        // The caller forgot [6-6] and lists the human's [5-6]
+        let out = choose(&format!(r#"{{{},"boardEmpty":true,"timeBudget":200,"legalMoves":[
+            {{"tileLow":1,"tileHigh":0,"end":"left"}},{{"tileLow":5,"tileHigh":6,"end":"left"}}]}}"#, SIX_OR_LOCK_TILES));
+        assert_eq!(out["tileId"], "0-1");
+        let codes: Vec<&str> = out["warnings"].as_array().unwrap().iter().map(|w| w["code"].as_str().unwrap()).collect();
+        assert_eq!(codes, ["legalMovesMismatch", "notInLegalMoves"]);
+        assert_eq!(out["warnings"][0]["missing"][0]["tileLow"], 6);
+        assert_eq!(out["warnings"][0]["unexpected"][0]["tileLow"], 5);
+        assert_eq!(out["warnings"][1]["engineMove"]["tileHigh"], 6);
+
+        // [6-6] only fits the left 6; the caller says right
+        let out = choose(r#"{"aiTiles":[{"low":6,"high":6}],"humanTiles":[{"low":2,"high":3}],"left":6,"right":1,
+            "timeBudget":200,"legalMoves":[{"tileLow":6,"tileHigh":6,"end":"right"}]}"#);
+        assert_eq!(out["end"], "right");
+        assert_eq!(out["warnings"][1]["code"], "endChanged");
+        assert_eq!(out["warnings"][1]["engineMove"]["end"], "left");
+
+        assert_eq!(choose("{not json")["warnings"][0]["code"], "badInput");
+
+        // Mid-deal: a complete list raises nothing; dropping the engine's
+        // pick from it makes the engine play a listed move and say so
+        let text = dealt(21, 5).to_text();
+        let moves = json(crate::wasm_legal_moves(&text))["moves"].clone();
+        let with = |moves: &Value| format!(r#"{{"position":"{}","timeBudget":200,"legalMoves":{}}}"#, text, moves);
+        let out = choose(&with(&moves));
+        assert!(out.get("warnings").is_none());
+        let pick = (out["tileId"].clone(), out["end"].clone());
+        let mut rest = moves.as_array().unwrap().clone();
+        rest.retain(|m| (Value::from(format!("{}-{}", m["tileLow"], m["tileHigh"])), m["end"].clone()) != pick);
+        assert_eq!(rest.len() + 1, moves.as_array().unwrap().len());
+        let out = choose(&with(&Value::from(rest)));
+        assert_ne!((out["tileId"].clone(), out["end"].clone()), pick);
+        assert_eq!(out["warnings"][0]["code"], "legalMovesMismatch");
+        assert_eq!(out["warnings"][0]["missing"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_moves_and_evaluate_move() {
+        // Restricted to the worse [0-1], which locks at once for -23
+        let out = json(crate::wasm_choose_move(&format!(r#"{{"position":"{}","timeBudget":200,
+            "searchMoves":[{{"tileLow":1,"tileHigh":0,"end":"left"}},{{"tileLow":5,"tileHigh":6,"end":"left"}}]}}"#, SIX_OR_LOCK)));
+        assert_eq!((&out["tileId"], &out["bestScore"]), (&Value::from("0-1"), &Value::from(-23)));
+        assert_eq!(out["warnings"][0]["code"], "searchMovesIllegal");
+        assert_eq!(out["warnings"][0]["unexpected"][0]["tileLow"], 5);
+        // ...and the restricted root did not stick in the TT
+        assert_eq!(json(crate::wasm_choose_move(SIX_OR_LOCK))["tileId"], "6-6");
+
+        let out = json(crate::wasm_evaluate_move(SIX_OR_LOCK, r#"{"tileLow":0,"tileHigh":1}"#, 200.0));
+        assert_eq!((&out["score"], &out["reply"]), (&Value::from(-23), &json("[]".to_string())));
+
+        // [6-6]: the human answers [5-6] and goes out
+        let out = json(crate::wasm_evaluate_move(SIX_OR_LOCK, r#"{"tileLow":6,"tileHigh":6,"end":"right"}"#, 200.0));
+        assert_eq!((&out["tileId"], &out["score"], &out["placementsLeft"]), (&Value::from("6-6"), &Value::from(-1), &Value::from(2)));
+        assert_eq!(out["reply"], json(r#"[{"player":"human","pass":false,"tile":{"low":5,"high":6},"end":"left"}]"#.to_string()));
+
+        for mv in [r#"{"pass":true}"#, r#"{"tileLow":5,"tileHigh":6}"#, r#"{"tileLow":9,"tileHigh":6}"#] {
+            assert!(json(crate::wasm_evaluate_move(SIX_OR_LOCK, mv, 100.0))["error"].is_string(), "{}", mv);
+        }
+
+        // Mid-deal, small enough to solve: each move searched alone and
+        // evaluated alone gets one value, and the best of them is the
+        // unrestricted search's
+        let text = dealt(22, 17).to_text();
+        let moves = json(crate::wasm_legal_moves(&text))["moves"].clone();
+        assert!(moves.as_array().unwrap().len() > 1);
+        let best = json(crate::wasm_choose_move(&format!(r#"{{"position":"{}","timeBudget":2000}}"#, text)))["bestScore"].clone();
+        let mut scores = Vec::new();
+        for mv in moves.as_array().unwrap() {
+            let only = json(crate::wasm_choose_move(&format!(r#"{{"position":"{}","timeBudget":2000,"searchMoves":[{}]}}"#, text, mv)));
+            let eval = json(crate::wasm_evaluate_move(&text, &mv.to_string(), 2000.0));
+            assert_eq!(only["bestScore"], eval["score"], "{}", mv);
+            scores.push(eval["score"].as_i64().unwrap());
+        }
+        assert_eq!(Value::from(*scores.iter().max().unwrap()), best);
+    }
+}
@@ -1,198 +1,164 @@
-/// Tile indexing and lookup tables for the double-six domino set (28 tiles).
-/// Tile ordering: (0,0),(0,1),(0,2),...,(0,6),(1,1),(1,2),...,(6,6)
+//! Tile indexing and lookup tables, one set of tables per domino set (`set.rs`).
+//! Tile ordering for highest pip N: (0,0),(0,1),...,(0,N),(1,1),(1,2),...,(N,N).
+//! An end value of N + 1 means the board is empty, and the `new_end_*`
+//! tables are indexed `t * (N + 2) + v`. The double-six tables keep their
+//! flat names (`TILE_LOW`, `SUIT_MASK`, `NEW_END_LEFT` at `t * 8 + v`, ...)
+//! for the code that only plays double-six.
+
+use crate::set::{DominoSet, MAX_SET_TILES};
+
+/// Suits in the largest set (0-12).
+pub const MAX_SUITS: usize = 13;
+
+/// Largest `new_end_*` stride (double-twelve: 12 + 2).
+pub const MAX_END_STRIDE: usize = 14;
+
+/// Lookup tables for one set. Arrays are sized for the largest set; only
+/// the first `NUM_TILES` tiles (and `MAX_PIP + 1` suits) are used.
+pub struct SetTables<B> {
+    /// Low pip value for each tile index
+    pub low: [i8; MAX_SET_TILES],
+    /// High pip value for each tile index
+    pub high: [i8; MAX_SET_TILES],
+    /// Total pips for each tile
+    pub pips: [i8; MAX_SET_TILES],
+    pub is_double: [bool; MAX_SET_TILES],
+    /// Tiles containing each suit value
+    pub suit_mask: [B; MAX_SUITS],
+    pub double_mask: B,
+    pub tile_00_bit: B,
+    /// Zero-suit tiles except [0-0] (Ghost 13 trigger)
+    pub zero_suit_no_00: B,
+    /// New left end after placing tile t on left end v, at `t * (N + 2) + v`;
+    /// -1 if illegal
+    pub new_end_left: [i8; MAX_SET_TILES * MAX_END_STRIDE],
+    /// New right end after placing tile t on right end v
+    pub new_end_right: [i8; MAX_SET_TILES * MAX_END_STRIDE],
+    /// Tile index of (low, high), low <= high
+    pub tile_id_map: [[i8; MAX_SUITS]; MAX_SUITS],
+}
+
+/// Tables for the set with highest pip `$max` and bitboard type `$bits`,
+/// built at compile time.
+macro_rules! set_tables {
+    ($bits:ty, $max:expr) => {{
+        const N: usize = $max;
+        const STRIDE: usize = N + 2;
+        let mut t = SetTables::<$bits> {
+            low: [0; MAX_SET_TILES],
+            high: [0; MAX_SET_TILES],
+            pips: [0; MAX_SET_TILES],
+            is_double: [false; MAX_SET_TILES],
+            suit_mask: [0; MAX_SUITS],
+            double_mask: 0,
+            tile_00_bit: 1,
+            zero_suit_no_00: 0,
+            new_end_left: [-1; MAX_SET_TILES * MAX_END_STRIDE],
+            new_end_right: [-1; MAX_SET_TILES * MAX_END_STRIDE],
+            tile_id_map: [[-1; MAX_SUITS]; MAX_SUITS],
+        };
+        let mut idx = 0;
+        let mut i = 0;
+        while i <= N {
+            let mut j = i;
+            while j <= N {
+                let bit: $bits = 1 << idx;
+                t.low[idx] = i as i8;
+                t.high[idx] = j as i8;
+                t.pips[idx] = (i + j) as i8;
+                t.is_double[idx] = i == j;
+                t.suit_mask[i] |= bit;
+                t.suit_mask[j] |= bit;
+                if i == j {
+                    t.double_mask |= bit;
+                }
+                t.tile_id_map[i][j] = idx as i8;
+                let mut v = 0;
+                while v <= N {
+                    let off = idx * STRIDE + v;
+                    if j == v {
+                        t.new_end_left[off] = i as i8;
+                    } else if i == v {
+                        t.new_end_left[off] = j as i8;
+                    }
+                    if i == v {
+                        t.new_end_right[off] = j as i8;
+                    } else if j == v {
+                        t.new_end_right[off] = i as i8;
+                    }
+                    v += 1;
+                }
+                // v = N + 1: empty board
+                t.new_end_left[idx * STRIDE + N + 1] = i as i8;
+                t.new_end_right[idx * STRIDE + N + 1] = j as i8;
+                idx += 1;
+                j += 1;
+            }
+            i += 1;
+        }
+        t.zero_suit_no_00 = t.suit_mask[0] & !t.tile_00_bit;
+        t
+    }};
+}
+
+const D6: SetTables<i32> = set_tables!(i32, 6);
+
+pub static DOUBLE_SIX: SetTables<i32> = D6;
+pub static DOUBLE_NINE: SetTables<u64> = set_tables!(u64, 9);
+pub static DOUBLE_TWELVE: SetTables<u128> = set_tables!(u128, 12);
+
+/// Map (low, high) pip values to a tile index of set `S` (order of the two
+/// is free); `None` if either pip is outside 0..=`S::MAX_PIP`.
+#[inline]
+pub fn tile_index<S: DominoSet>(a: i8, b: i8) -> Option<usize> {
+    let pips = 0..=S::MAX_PIP;
+    (pips.contains(&a) && pips.contains(&b)).then(|| {
+        S::tables().tile_id_map[a.min(b) as usize][a.max(b) as usize] as usize
+    })
+}
+
+// =====================================================================
+// Double-six
+// =====================================================================
 
 pub const NUM_TILES: usize = 28;
 
 /// Low pip value for each tile index
-pub static TILE_LOW: [i8; NUM_TILES] = {
-    let mut t = [0i8; NUM_TILES];
-    let mut idx = 0;
-    let mut i = 0i8;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            t[idx] = i;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    t
-};
+pub static TILE_LOW: &[i8] = &DOUBLE_SIX.low;
 
 /// High pip value for each tile index
-pub static TILE_HIGH: [i8; NUM_TILES] = {
-    let mut t = [0i8; NUM_TILES];
-    let mut idx = 0;
-    let mut i = 0i8;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            t[idx] = j;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    t
-};
+pub static TILE_HIGH: &[i8] = &DOUBLE_SIX.high;
 
 /// Total pips for each tile
-pub static TILE_PIPS: [i8; NUM_TILES] = {
-    let mut t = [0i8; NUM_TILES];
-    let mut idx = 0;
-    let mut i = 0i8;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            t[idx] = i + j;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    t
-};
+pub static TILE_PIPS: &[i8] = &DOUBLE_SIX.pips;
 
 /// Whether tile is a double
-pub static TILE_IS_DOUBLE: [bool; NUM_TILES] = {
-    let mut t = [false; NUM_TILES];
-    let mut idx = 0;
-    let mut i = 0i8;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            t[idx] = i == j;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    t
-};
+pub static TILE_IS_DOUBLE: &[bool] = &DOUBLE_SIX.is_double;
 
 /// Bitmask of tiles containing each suit value (0-6)
-pub static SUIT_MASK: [i32; 7] = {
-    let mut m = [0i32; 7];
-    let mut idx = 0u32;
-    let mut i = 0;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            m[i as usize] |= 1 << idx;
-            if i != j {
-                m[j as usize] |= 1 << idx;
-            }
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    m
-};
+pub static SUIT_MASK: &[i32] = &DOUBLE_SIX.suit_mask;
 
 /// Bitmask of all double tiles
-pub static DOUBLE_MASK: i32 = {
-    let mut mask = 0i32;
-    let mut idx = 0u32;
-    let mut i = 0;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            if i == j {
-                mask |= 1 << idx;
-            }
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    mask
-};
+pub static DOUBLE_MASK: i32 = D6.double_mask;
 
 /// Bit for [0-0] tile
 pub const TILE_00_BIT: i32 = 1; // index 0 = (0,0), bit = 1<<0
 
 /// Bitmask of [0-1] through [0-6] (zero suit excluding [0-0])
-pub static ZERO_SUIT_NO_00: i32 = {
-    SUIT_MASK[0] & !TILE_00_BIT
-};
+pub static ZERO_SUIT_NO_00: i32 = D6.zero_suit_no_00;
 
 /// New left end after placing tile t on board with left end v.
 /// Index: t * 8 + v. v=7 means empty board. Returns -1 if illegal.
-pub static NEW_END_LEFT: [i8; NUM_TILES * 8] = {
-    let mut table = [-1i8; NUM_TILES * 8];
-    let mut idx = 0;
-    let mut i = 0i8;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            // t = idx, lo = i, hi = j
-            let mut v = 0;
-            while v <= 6 {
-                let off = idx * 8 + v;
-                if j == v as i8 {
-                    table[off] = i;
-                } else if i == v as i8 {
-                    table[off] = j;
-                }
-                // else stays -1
-                v += 1;
-            }
-            // v=7: empty board
-            table[idx * 8 + 7] = i;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    table
-};
+pub static NEW_END_LEFT: &[i8] = &DOUBLE_SIX.new_end_left;
 
 /// New right end after placing tile t on board with right end v.
-pub static NEW_END_RIGHT: [i8; NUM_TILES * 8] = {
-    let mut table = [-1i8; NUM_TILES * 8];
-    let mut idx = 0;
-    let mut i = 0i8;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            let mut v = 0;
-            while v <= 6 {
-                let off = idx * 8 + v;
-                if i == v as i8 {
-                    table[off] = j;
-                } else if j == v as i8 {
-                    table[off] = i;
-                }
-                v += 1;
-            }
-            table[idx * 8 + 7] = j;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    table
-};
+pub static NEW_END_RIGHT: &[i8] = &DOUBLE_SIX.new_end_right;
 
 /// Map (low, high) pip values to tile index. low <= high required.
-pub static TILE_ID_MAP: [[i8; 7]; 7] = {
-    let mut m = [[-1i8; 7]; 7];
-    let mut idx = 0i8;
-    let mut i = 0;
-    while i <= 6 {
-        let mut j = i;
-        while j <= 6 {
-            m[i][j] = idx;
-            idx += 1;
-            j += 1;
-        }
-        i += 1;
-    }
-    m
-};
+pub static TILE_ID_MAP: &[[i8; MAX_SUITS]] = &DOUBLE_SIX.tile_id_map;
 
+/// Double-six tile index of (low, high); both pips must be 0-6
+/// (`tile_index` checks them).
 #[inline(always)]
 pub fn tile_id_to_index(low: i8, high: i8) -> usize {
     let lo = low.min(high) as usize;
@@ -210,13 +176,14 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn test_tile_count() {
         // Should have exactly 28 tiles
         let mut count = 0;
         for i in 0..=6 {
             for j in i..=6 {
                 let idx = TILE_ID_MAP[i][j];
-                assert!(idx >= 0 && idx < 28, "Invalid index for ({},{}): {}", i, j, idx);
+                assert!((0..28).contains(&idx), "Invalid index for ({},{}): {}", i, j, idx);
                 count += 1;
             }
         }
@@ -256,6 +223,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_new_end_left() {
         // Tile [0-6] (idx 6) placed on left end 6 → new left = 0
         let idx = tile_id_to_index(0, 6);
@@ -0,0 +1,551 @@
+//! N-side engine for the 3-player variant (27 tiles, no [0-0], 9 each).
+//! The 4-seat team modes reuse the position and build on it in `team.rs`.
+//! Generic over the domino set (`set.rs`), so double-nine and double-twelve
+//! games run through the same search.
+//! Position state lives on the stack — no globals, no shared TT — so the search
+//! can run on a perfect-information deal or be called repeatedly from a
+//! determinization layer that samples the hidden hands.
+//!
+//! Rules (3players/game.js):
+//! - Domino: winner scores the sum of all opponents' pips.
+//! - Block (immediate lock, or every side passes in turn): lowest pip count
+//!   wins, ties go to the lowest seat; winner scores opponents' pips minus own.
+
+use crate::movegen::{collect_moves, count_moves_bb};
+use crate::scoring::total_pips_bb;
+use crate::search::{compute_new_ends, now_ms};
+use crate::set::{Bitboard, DominoSet, MAX_SET_TILES};
+use std::marker::PhantomData;
+
+/// Maximum number of sides supported by the N-side position.
+pub const MAX_SIDES: usize = 4;
+
+const NODE_LIMIT: u32 = 5_000_000;
+
+// Evaluation weights (per side, before conversion to relative utilities)
+const W_PIP: f64 = 1.0;
+const W_TILE: f64 = 4.0;
+const W_MOBILITY: f64 = 2.0;
+
+/// Search algorithm for more than two sides.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MultiMode {
+    /// Every side maximizes its own utility (no pruning).
+    MaxN,
+    /// The root side assumes all others play against it (alpha-beta).
+    Paranoid,
+}
+
+/// N-side position over set `S`. Seats are numbered in turn order.
+#[derive(Clone, Copy, Debug)]
+pub struct MultiPosition<S: DominoSet> {
+    pub hands: [S::Bits; MAX_SIDES],
+    pub num_sides: usize,
+    pub left: i8,
+    pub right: i8,
+    pub to_move: usize,
+    pub cons_pass: i32,
+    /// Seat that made the most recent placement (-1 = none yet)
+    pub last_placer: i8,
+    _set: PhantomData<S>,
+}
+
+/// Points credited to each seat when the hand ends.
+pub type Outcome = [i32; MAX_SIDES];
+
+/// How a hand ended. Scoring is left to the caller's ruleset.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HandEnd {
+    /// The given seat played its last tile.
+    Domino(usize),
+    /// Nobody can move (immediate lock or every side passed in turn).
+    Block,
+}
+
+impl<S: DominoSet> MultiPosition<S> {
+    /// `left == right == S::EMPTY_END` for an empty board.
+    pub fn new(hands: &[S::Bits], left: i8, right: i8, to_move: usize) -> Self {
+        let mut h = [S::Bits::ZERO; MAX_SIDES];
+        h[..hands.len()].copy_from_slice(hands);
+        Self {
+            hands: h,
+            num_sides: hands.len(),
+            left,
+            right,
+            to_move,
+            cons_pass: 0,
+            last_placer: -1,
+            _set: PhantomData,
+        }
+    }
+
+    /// Union of every side's hand.
+    #[inline]
+    pub fn all_hands(&self) -> S::Bits {
+        let mut all = S::Bits::ZERO;
+        for s in 0..self.num_sides {
+            all |= self.hands[s];
+        }
+        all
+    }
+
+    #[inline]
+    fn any_can_move(&self) -> bool {
+        (0..self.num_sides).any(|s| count_moves_bb::<S>(self.hands[s], self.left, self.right) > 0)
+    }
+
+    /// Place `t_idx` on `end` for the side to move. Returns how the hand
+    /// ended if the placement ends it (domino or immediate lock).
+    pub fn play(&mut self, t_idx: usize, end: i8) -> Option<HandEnd> {
+        let me = self.to_move;
+        self.hands[me] ^= S::Bits::bit(t_idx);
+        let (new_l, new_r) = compute_new_ends::<S>(t_idx, end, self.left, self.right);
+        self.left = new_l;
+        self.right = new_r;
+        self.cons_pass = 0;
+        self.last_placer = me as i8;
+
+        if self.hands[me].is_zero() {
+            return Some(HandEnd::Domino(me));
+        }
+        if !self.any_can_move() {
+            return Some(HandEnd::Block);
+        }
+        self.to_move = (me + 1) % self.num_sides;
+        None
+    }
+
+    /// Pass for the side to move. Returns `Block` if every side has now
+    /// passed in turn (confirmed lock).
+    pub fn pass(&mut self) -> Option<HandEnd> {
+        self.cons_pass += 1;
+        if self.cons_pass >= self.num_sides as i32 {
+            return Some(HandEnd::Block);
+        }
+        self.to_move = (self.to_move + 1) % self.num_sides;
+        None
+    }
+
+    /// Score a finished hand under the individual (3-player) rules.
+    pub fn score(&self, end: HandEnd) -> Outcome {
+        match end {
+            HandEnd::Domino(w) => score_domino_multi::<S>(&self.hands, self.num_sides, w),
+            HandEnd::Block => score_block_multi::<S>(&self.hands, self.num_sides),
+        }
+    }
+}
+
+/// Domino: the winner scores every opponent's remaining pips.
+pub fn score_domino_multi<S: DominoSet>(
+    hands: &[S::Bits; MAX_SIDES], num_sides: usize, winner: usize,
+) -> Outcome {
+    let all = hands[..num_sides].iter().fold(S::Bits::ZERO, |a, &h| a | h);
+    let mut out = [0i32; MAX_SIDES];
+    for (s, &h) in hands[..num_sides].iter().enumerate() {
+        if s != winner {
+            out[winner] += total_pips_bb::<S>(h, all, true);
+        }
+    }
+    out
+}
+
+/// Block: lowest pip count wins (ties to the lowest seat) and scores the
+/// opponents' pips minus its own.
+pub fn score_block_multi<S: DominoSet>(hands: &[S::Bits; MAX_SIDES], num_sides: usize) -> Outcome {
+    let all = hands[..num_sides].iter().fold(S::Bits::ZERO, |a, &h| a | h);
+    let mut pips = [0i32; MAX_SIDES];
+    let mut winner = 0;
+    for (s, &h) in hands[..num_sides].iter().enumerate() {
+        pips[s] = total_pips_bb::<S>(h, all, true);
+        if pips[s] < pips[winner] {
+            winner = s;
+        }
+    }
+    let mut out = [0i32; MAX_SIDES];
+    for (s, &p) in pips[..num_sides].iter().enumerate() {
+        if s != winner {
+            out[winner] += p;
+        }
+    }
+    out[winner] -= pips[winner];
+    out
+}
+
+/// Convert per-seat points into relative utilities: each seat's points
+/// against the average of its opponents, scaled by (n - 1) to stay integral.
+/// The result is zero-sum across seats.
+#[inline]
+pub fn utilities(points: &Outcome, num_sides: usize) -> Outcome {
+    let total: i32 = points[..num_sides].iter().sum();
+    let mut u = [0i32; MAX_SIDES];
+    for s in 0..num_sides {
+        u[s] = points[s] * num_sides as i32 - total;
+    }
+    u
+}
+
+/// Static evaluation: per-seat utilities from pips, tile count and mobility.
+pub fn evaluate_multi<S: DominoSet>(pos: &MultiPosition<S>) -> Outcome {
+    let all = pos.all_hands();
+    let mut v = [0i32; MAX_SIDES];
+    for (s, &h) in pos.hands[..pos.num_sides].iter().enumerate() {
+        let pips = total_pips_bb::<S>(h, all, true) as f64;
+        let tiles = h.count() as f64;
+        let mob = count_moves_bb::<S>(h, pos.left, pos.right) as f64;
+        v[s] = (-pips * W_PIP - tiles * W_TILE + mob * W_MOBILITY) as i32;
+    }
+    utilities(&v, pos.num_sides)
+}
+
+// =====================================================================
+// Search
+// =====================================================================
+
+/// Result of an N-side root search.
+pub struct MultiSearchResult {
+    pub best_tile_idx: i8,
+    pub best_end: i8,
+    /// Utilities of the principal line, per seat. A `Paranoid` search only
+    /// fills the mover's seat unless the best move ends the hand.
+    pub values: Outcome,
+    pub depth: i32,
+    pub nodes: u32,
+    /// True if the last completed iteration reached every terminal.
+    pub exact: bool,
+    /// Per-move root-side utilities: (tile_idx, end, score)
+    pub analysis: Vec<(i8, i8, i32)>,
+}
+
+pub(crate) struct MultiCtx {
+    pub root_seat: usize,
+    pub nodes: u32,
+    pub deadline: f64,
+    pub aborted: bool,
+    pub horizon: bool,
+}
+
+impl MultiCtx {
+    pub(crate) fn new(root_seat: usize, time_budget: f64) -> Self {
+        Self {
+            root_seat,
+            nodes: 0,
+            deadline: now_ms() + time_budget,
+            aborted: false,
+            horizon: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes >= NODE_LIMIT || (self.nodes & 1023 == 0 && now_ms() > self.deadline) {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+}
+
+/// Order moves by pips shed (doubles first among equals).
+#[inline]
+pub(crate) fn order_moves_multi<S: DominoSet>(moves: &mut [(i8, i8)]) {
+    let tables = S::tables();
+    moves.sort_by_key(|&(t, _)| {
+        let t = t as usize;
+        -(tables.pips[t] as i32 * 2 + tables.is_double[t] as i32)
+    });
+}
+
+fn maxn<S: DominoSet>(pos: &MultiPosition<S>, depth: i32, ctx: &mut MultiCtx) -> Outcome {
+    if ctx.tick() {
+        return evaluate_multi(pos);
+    }
+    let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+    let n = collect_moves::<S>(pos.hands[pos.to_move], pos.left, pos.right, &mut buf);
+    if n == 0 {
+        let mut next = *pos;
+        return match next.pass() {
+            Some(end) => utilities(&next.score(end), pos.num_sides),
+            None => maxn(&next, depth, ctx),
+        };
+    }
+    if depth <= 0 {
+        ctx.horizon = true;
+        return evaluate_multi(pos);
+    }
+    order_moves_multi::<S>(&mut buf[..n]);
+
+    let me = pos.to_move;
+    let mut best = [i32::MIN; MAX_SIDES];
+    for &(t, e) in &buf[..n] {
+        let mut next = *pos;
+        let u = match next.play(t as usize, e) {
+            Some(end) => utilities(&next.score(end), pos.num_sides),
+            None => maxn(&next, depth - 1, ctx),
+        };
+        if u[me] > best[me] {
+            best = u;
+        }
+    }
+    best
+}
+
+fn paranoid<S: DominoSet>(pos: &MultiPosition<S>, depth: i32, mut alpha: i32, mut beta: i32, ctx: &mut MultiCtx) -> i32 {
+    let root = ctx.root_seat;
+    if ctx.tick() {
+        return evaluate_multi(pos)[root];
+    }
+    let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+    let n = collect_moves::<S>(pos.hands[pos.to_move], pos.left, pos.right, &mut buf);
+    if n == 0 {
+        let mut next = *pos;
+        return match next.pass() {
+            Some(end) => utilities(&next.score(end), pos.num_sides)[root],
+            None => paranoid(&next, depth, alpha, beta, ctx),
+        };
+    }
+    if depth <= 0 {
+        ctx.horizon = true;
+        return evaluate_multi(pos)[root];
+    }
+    order_moves_multi::<S>(&mut buf[..n]);
+
+    let maximizing = pos.to_move == root;
+    let mut best = if maximizing { i32::MIN } else { i32::MAX };
+    for &(t, e) in &buf[..n] {
+        let mut next = *pos;
+        let sc = match next.play(t as usize, e) {
+            Some(end) => utilities(&next.score(end), pos.num_sides)[root],
+            None => paranoid(&next, depth - 1, alpha, beta, ctx),
+        };
+        if maximizing {
+            if sc > best { best = sc; }
+            if best > alpha { alpha = best; }
+        } else {
+            if sc < best { best = sc; }
+            if best < beta { beta = best; }
+        }
+        if beta <= alpha {
+            break;
+        }
+    }
+    best
+}
+
+/// Iterative-deepening root search for the side to move in `pos`.
+/// `time_budget` is in ms (0 = 2000 ms default).
+pub fn choose_move_multi<S: DominoSet>(pos: &MultiPosition<S>, mode: MultiMode, time_budget: f64) -> MultiSearchResult {
+    let budget = if time_budget > 0.0 { time_budget } else { 2000.0 };
+    let mut ctx = MultiCtx::new(pos.to_move, budget);
+
+    let mut result = MultiSearchResult {
+        best_tile_idx: -1,
+        best_end: -1,
+        values: [0; MAX_SIDES],
+        depth: 0,
+        nodes: 0,
+        exact: false,
+        analysis: Vec::new(),
+    };
+
+    let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+    let n = collect_moves::<S>(pos.hands[pos.to_move], pos.left, pos.right, &mut buf);
+    if n == 0 {
+        return result;
+    }
+    order_moves_multi::<S>(&mut buf[..n]);
+
+    let max_depth = pos.all_hands().count();
+    for iter_depth in 1..=max_depth {
+        ctx.horizon = false;
+        let me = pos.to_move;
+        let mut best_vals = [i32::MIN; MAX_SIDES];
+        let mut best_move = (-1i8, -1i8);
+        let mut scores = Vec::with_capacity(n);
+
+        for &(t, e) in &buf[..n] {
+            let mut next = *pos;
+            let vals = match next.play(t as usize, e) {
+                Some(end) => utilities(&next.score(end), pos.num_sides),
+                None => match mode {
+                    MultiMode::MaxN => maxn(&next, iter_depth - 1, &mut ctx),
+                    MultiMode::Paranoid => {
+                        // Full window: the analysis reports every move's value
+                        let mut v = [0i32; MAX_SIDES];
+                        v[me] = paranoid(&next, iter_depth - 1, i32::MIN, i32::MAX, &mut ctx);
+                        v
+                    }
+                },
+            };
+            scores.push((t, e, vals[me]));
+            if vals[me] > best_vals[me] {
+                best_vals = vals;
+                best_move = (t, e);
+            }
+            if ctx.aborted {
+                break;
+            }
+        }
+
+        // Keep the first iteration even if aborted, so there is always a move
+        if ctx.aborted && result.best_tile_idx >= 0 {
+            break;
+        }
+        result.best_tile_idx = best_move.0;
+        result.best_end = best_move.1;
+        result.values = best_vals;
+        result.depth = iter_depth;
+        result.analysis = scores;
+        result.exact = !ctx.horizon && !ctx.aborted;
+
+        if ctx.aborted || result.exact {
+            break;
+        }
+
+        // Search the previous best move first next iteration
+        if let Some(pos_best) = buf[..n].iter().position(|&m| m == best_move) {
+            buf[..=pos_best].rotate_right(1);
+        }
+    }
+
+    result.nodes = ctx.nodes;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::{tile_id_to_index, tile_index, NUM_TILES};
+    use crate::set::{DoubleSix, DoubleNine, full_set};
+
+    type Pos = MultiPosition<DoubleSix>;
+
+    fn hand(tiles: &[(i8, i8)]) -> i32 {
+        tiles.iter().fold(0, |h, &(a, b)| h | (1 << tile_id_to_index(a, b)))
+    }
+
+    #[test]
+    fn test_domino_scores_all_opponents() {
+        let hands = [0, hand(&[(6, 6)]), hand(&[(2, 3), (1, 1)]), 0];
+        let out = score_domino_multi::<DoubleSix>(&hands, 3, 0);
+        assert_eq!(out, [12 + 5 + 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_block_lowest_wins_ties_to_lowest_seat() {
+        let hands = [hand(&[(4, 5)]), hand(&[(3, 6)]), hand(&[(0, 1)]), 0];
+        let out = score_block_multi::<DoubleSix>(&hands, 3);
+        assert_eq!(out, [0, 0, 9 + 9 - 1, 0]);
+
+        let tied = [hand(&[(6, 6)]), hand(&[(2, 3)]), hand(&[(1, 4)]), 0];
+        let out = score_block_multi::<DoubleSix>(&tied, 3);
+        assert_eq!(out, [0, 12 + 5 - 5, 0, 0]);
+    }
+
+    #[test]
+    fn test_utilities_zero_sum() {
+        let u = utilities(&[17, 0, 0, 0], 3);
+        assert_eq!(u[..3].iter().sum::<i32>(), 0);
+        assert!(u[0] > 0 && u[1] < 0 && u[1] == u[2]);
+    }
+
+    #[test]
+    fn test_three_way_pass_blocks() {
+        // Ends 6/6: nobody holds a six.
+        let mut pos = Pos::new(&[hand(&[(1, 2)]), hand(&[(0, 3)]), hand(&[(4, 5)])], 6, 6, 0);
+        assert!(pos.pass().is_none());
+        assert!(pos.pass().is_none());
+        let end = pos.pass().expect("third pass must end the hand");
+        assert_eq!(end, HandEnd::Block);
+        assert_eq!(pos.score(end), [3 + 9 - 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_immediate_lock_after_placement() {
+        // Seat 0 plays [5-6] on 5 → ends 6/6, nobody else can follow.
+        let mut pos = Pos::new(
+            &[hand(&[(5, 6), (1, 1)]), hand(&[(0, 1)]), hand(&[(2, 3)])],
+            5, 6, 0,
+        );
+        let t = tile_id_to_index(5, 6);
+        let end = pos.play(t, 0).expect("lock expected");
+        assert_eq!(end, HandEnd::Block);
+        assert_eq!(pos.last_placer, 0);
+        // Pips: seat0 = 2, seat1 = 1, seat2 = 5 → seat1 wins 2 + 5 - 1
+        assert_eq!(pos.score(end), [0, 6, 0, 0]);
+    }
+
+    #[test]
+    fn test_choose_move_multi_finds_domino() {
+        let pos = Pos::new(
+            &[hand(&[(0, 1)]), hand(&[(6, 6), (5, 5)]), hand(&[(4, 4)])],
+            1, 3, 0,
+        );
+        for mode in [MultiMode::MaxN, MultiMode::Paranoid] {
+            let r = choose_move_multi(&pos, mode, 500.0);
+            assert_eq!(r.best_tile_idx as usize, tile_id_to_index(0, 1));
+            assert_eq!(r.best_end, 0);
+            assert!(r.exact);
+        }
+    }
+
+    #[test]
+    fn test_paranoid_analysis_is_exact() {
+        // 15 tiles over three seats, every line searched to the end
+        let mut tiles: Vec<usize> = (0..NUM_TILES).collect();
+        tiles.rotate_left(11);
+        let mut hands = [0i32; 3];
+        for (i, &t) in tiles.iter().take(15).enumerate() {
+            hands[i % 3] |= 1 << t;
+        }
+        let pos = Pos::new(&hands, 7, 7, 0);
+        let r = choose_move_multi(&pos, MultiMode::Paranoid, 20_000.0);
+        assert!(r.exact);
+        let mut distinct = r.analysis.iter().map(|a| a.2).collect::<Vec<_>>();
+        distinct.dedup();
+        assert!(distinct.len() > 1);
+        for &(t, e, sc) in &r.analysis {
+            let mut next = pos;
+            let mut ctx = MultiCtx::new(0, 20_000.0);
+            let value = match next.play(t as usize, e) {
+                Some(end) => utilities(&next.score(end), 3)[0],
+                None => paranoid(&next, 15, i32::MIN, i32::MAX, &mut ctx),
+            };
+            assert_eq!(sc, value, "{}", t);
+        }
+    }
+
+    #[test]
+    fn test_choose_move_multi_full_deal() {
+        // 27-tile deal, [0-0] removed, 9 tiles each
+        let mut tiles: Vec<usize> = (1..NUM_TILES).collect();
+        tiles.rotate_left(5);
+        let mut hands = [0i32; 3];
+        for (i, &t) in tiles.iter().enumerate() {
+            hands[i / 9] |= 1 << t;
+        }
+        let pos = Pos::new(&hands, 7, 7, 1);
+        let r = choose_move_multi(&pos, MultiMode::Paranoid, 300.0);
+        assert!(r.best_tile_idx >= 0);
+        assert!(hands[1] & (1 << r.best_tile_idx) != 0);
+        assert_eq!(r.analysis.len(), 9);
+    }
+
+    #[test]
+    fn test_double_nine_four_seats() {
+        // Double-nine, 4 seats × 10 tiles (15 undealt), individual scoring
+        let all = full_set::<DoubleNine>();
+        let mut hands = [0u64; 4];
+        for (i, t) in crate::set::bits(all).enumerate().take(40) {
+            hands[i % 4] |= 1 << t;
+        }
+        let pos = MultiPosition::<DoubleNine>::new(&hands, DoubleNine::EMPTY_END, DoubleNine::EMPTY_END, 0);
+        let r = choose_move_multi(&pos, MultiMode::Paranoid, 200.0);
+        assert!(r.best_tile_idx >= 0);
+        assert!(hands[0] & (1 << r.best_tile_idx) != 0);
+
+        // [9-9] on a 9 end finishes seat 0's hand
+        let nine = 1u64 << tile_index::<DoubleNine>(9, 9).unwrap();
+        let mut pos = MultiPosition::<DoubleNine>::new(&[nine, 1u64 << 3, 1u64 << 4], 9, 2, 0);
+        assert_eq!(pos.play(tile_index::<DoubleNine>(9, 9).unwrap(), 0), Some(HandEnd::Domino(0)));
+    }
+}
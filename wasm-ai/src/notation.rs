@@ -0,0 +1,332 @@
+//! One-line position notation, for bug reports and test fixtures.
+//!
+//! Six whitespace-separated fields, the last optional:
+//!
+//! ```text
+//! <human>/<ai> <board> <to move> <passes> <history> [<score>]
+//! 0122/0566 633335 a 0 h35@65,a@63 40-25
+//! ```
+//!
+//! - hands: tiles as two pips (`05` = [0-5]), concatenated; `-` is an empty
+//!   hand. Printed in tile-index order, either pip order is accepted.
+//! - board: `-` when empty, the layout chain left to right with each tile
+//!   oriented (`633335` = [6|3][3|3][3|5], ends 6 and 5), or just the ends
+//!   (`6:5`) when the layout is unknown.
+//! - to move: `h` or `a`.
+//! - passes: consecutive passes so far.
+//! - history: the last two placements for the Puppeteer rule, most recent
+//!   first, each `<who>[<tile>]@<left><right>` with the ends after the
+//!   placement; the most recent one names its tile. `-` when none.
+//! - score: match score `<human>-<ai>`, 0-0 when omitted.
+
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::{tile_id_to_index, NUM_TILES, TILE_HIGH, TILE_LOW};
+use crate::search::compute_new_ends;
+use crate::set::DoubleSix;
+
+/// Oriented (left pip, right pip) tiles, left to right.
+pub type Chain = Vec<(i8, i8)>;
+
+/// A hand state plus the layout and match score around it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub state: HandState,
+    /// Board layout; empty when the board is empty or only its ends are
+    /// known
+    pub chain: Chain,
+    /// Match score, `[human, ai]`
+    pub score: [i32; 2],
+}
+
+impl Position {
+    /// Position with an unknown layout and no match score.
+    pub fn from_state(state: HandState) -> Self {
+        Position { state, chain: Vec::new(), score: [0, 0] }
+    }
+
+    /// Place `tile` on `end` for the side to move, extending the chain when
+    /// it is known.
+    pub fn place(&mut self, tile: i8, end: i8) {
+        let (left, right) = (self.state.left, self.state.right);
+        let (nl, nr) = compute_new_ends::<DoubleSix>(tile as usize, end, left, right);
+        if left == 7 {
+            self.chain = vec![(nl, nr)];
+        } else if !self.chain.is_empty() {
+            if end == 0 {
+                self.chain.insert(0, (nl, left));
+            } else {
+                self.chain.push((right, nr));
+            }
+        }
+        self.state.place(tile, end);
+    }
+
+    pub fn pass(&mut self) {
+        self.state.pass();
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() != 5 && fields.len() != 6 {
+            return Err(format!("expected 5 or 6 fields, got {}", fields.len()));
+        }
+        let (human, ai) = fields[0].split_once('/')
+            .ok_or_else(|| format!("hands: expected <human>/<ai>, got '{}'", fields[0]))?;
+        let hands = [parse_hand(human)?, parse_hand(ai)?];
+        if hands[0] & hands[1] != 0 {
+            return Err("a tile is in both hands".to_string());
+        }
+
+        let (left, right, chain) = parse_board(fields[1])?;
+        for &(a, b) in &chain {
+            if (hands[0] | hands[1]) & 1 << tile_id_to_index(a, b) != 0 {
+                return Err(format!("[{}-{}] is both on the board and in a hand", a.min(b), a.max(b)));
+            }
+        }
+
+        let to_move = match fields[2] {
+            "h" => HUMAN,
+            "a" => AI,
+            s => return Err(format!("to move: expected h or a, got '{}'", s)),
+        };
+        let cons_pass = match fields[3].parse::<i32>() {
+            Ok(n) if (0..=2).contains(&n) => n,
+            _ => return Err(format!("passes: expected 0-2, got '{}'", fields[3])),
+        };
+
+        let mut state = HandState { hands, left, right, to_move, cons_pass, ..HandState::new(0, 0, to_move) };
+        if fields[4] != "-" {
+            let entries: Vec<&str> = fields[4].split(',').collect();
+            if entries.len() > 2 {
+                return Err("history: at most two placements".to_string());
+            }
+            let (who, tile, l, r) = parse_placement(entries[0])?;
+            if tile < 0 {
+                return Err("history: the last placement needs its tile".to_string());
+            }
+            if (l, r) != (left, right) {
+                return Err("history: the last placement must leave the board ends".to_string());
+            }
+            if (hands[0] | hands[1]) & 1 << tile != 0 {
+                return Err("history: the last placed tile is still in a hand".to_string());
+            }
+            state.p1 = (who, l, r, tile);
+            if let Some(e) = entries.get(1) {
+                let (who, _, l, r) = parse_placement(e)?;
+                state.p2 = (who, l, r);
+            }
+        }
+
+        let score = match fields.get(5) {
+            None => [0, 0],
+            Some(s) => {
+                let (h, a) = s.split_once('-')
+                    .ok_or_else(|| format!("score: expected <human>-<ai>, got '{}'", s))?;
+                let num = |v: &str| v.parse::<i32>().map_err(|_| format!("score: bad number '{}'", v));
+                [num(h)?, num(a)?]
+            }
+        };
+        Ok(Position { state, chain, score })
+    }
+
+    pub fn to_text(&self) -> String {
+        let s = &self.state;
+        let board = if s.left == 7 {
+            "-".to_string()
+        } else if self.chain.is_empty() {
+            format!("{}:{}", s.left, s.right)
+        } else {
+            self.chain.iter().map(|&(a, b)| format!("{}{}", a, b)).collect()
+        };
+        let who = |w: i8| if w == AI { 'a' } else { 'h' };
+        let history = match (s.p1, s.p2) {
+            ((-1, ..), _) => "-".to_string(),
+            ((w1, l1, r1, t1), (w2, l2, r2)) => {
+                let t = t1 as usize;
+                let mut h = format!("{}{}{}@{}{}", who(w1), TILE_LOW[t], TILE_HIGH[t], l1, r1);
+                if w2 >= 0 {
+                    h += &format!(",{}@{}{}", who(w2), l2, r2);
+                }
+                h
+            }
+        };
+        format!("{}/{} {} {} {} {} {}-{}",
+                hand_text(s.hands[0]), hand_text(s.hands[1]), board, who(s.to_move),
+                s.cons_pass, history, self.score[0], self.score[1])
+    }
+}
+
+fn parse_pip(c: u8) -> Result<i8, String> {
+    match c {
+        b'0'..=b'6' => Ok((c - b'0') as i8),
+        _ => Err(format!("bad pip '{}'", c as char)),
+    }
+}
+
+/// Pip pairs of a concatenated tile list.
+fn parse_pairs(s: &str) -> Result<Vec<(i8, i8)>, String> {
+    if s.len() & 1 != 0 {
+        return Err(format!("'{}': tiles take two pips each", s));
+    }
+    s.as_bytes().chunks(2).map(|p| Ok((parse_pip(p[0])?, parse_pip(p[1])?))).collect()
+}
+
+fn parse_hand(s: &str) -> Result<i32, String> {
+    if s == "-" {
+        return Ok(0);
+    }
+    let mut hand = 0;
+    for (a, b) in parse_pairs(s)? {
+        let bit = 1 << tile_id_to_index(a, b);
+        if hand & bit != 0 {
+            return Err(format!("[{}-{}] appears twice", a.min(b), a.max(b)));
+        }
+        hand |= bit;
+    }
+    Ok(hand)
+}
+
+/// (left, right, chain)
+fn parse_board(s: &str) -> Result<(i8, i8, Chain), String> {
+    if s == "-" {
+        return Ok((7, 7, Vec::new()));
+    }
+    if let [l, b':', r] = s.as_bytes() {
+        return Ok((parse_pip(*l)?, parse_pip(*r)?, Vec::new()));
+    }
+    let chain = parse_pairs(s)?;
+    let mut seen = 0;
+    for (i, &(a, b)) in chain.iter().enumerate() {
+        if i > 0 && chain[i - 1].1 != a {
+            return Err(format!("board: [{}|{}] does not join [{}|{}]", a, b, chain[i - 1].0, chain[i - 1].1));
+        }
+        let bit = 1 << tile_id_to_index(a, b);
+        if seen & bit != 0 {
+            return Err(format!("board: [{}-{}] appears twice", a.min(b), a.max(b)));
+        }
+        seen |= bit;
+    }
+    Ok((chain[0].0, chain[chain.len() - 1].1, chain))
+}
+
+/// `<who>[<tile>]@<left><right>` → (who, tile or -1, left, right)
+fn parse_placement(s: &str) -> Result<(i8, i8, i8, i8), String> {
+    let bad = || format!("history: bad placement '{}'", s);
+    let (head, ends) = s.split_once('@').ok_or_else(bad)?;
+    let who = match head.as_bytes().first() {
+        Some(b'h') => HUMAN,
+        Some(b'a') => AI,
+        _ => return Err(bad()),
+    };
+    let tile = match &head.as_bytes()[1..] {
+        [] => -1,
+        [a, b] => tile_id_to_index(parse_pip(*a)?, parse_pip(*b)?) as i8,
+        _ => return Err(bad()),
+    };
+    match ends.as_bytes() {
+        [l, r] => Ok((who, tile, parse_pip(*l)?, parse_pip(*r)?)),
+        _ => Err(bad()),
+    }
+}
+
+fn hand_text(hand: i32) -> String {
+    if hand == 0 {
+        return "-".to_string();
+    }
+    (0..NUM_TILES).filter(|&t| hand & 1 << t != 0)
+        .map(|t| format!("{}{}", TILE_LOW[t], TILE_HIGH[t]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::deal;
+    use crate::fixtures::{SIX_OR_LOCK, SIX_OR_LOCK_TILES};
+
+    #[test]
+    fn test_round_trip_through_a_hand() {
+        let [human, ai] = deal(3);
+        let mut pos = Position { score: [35, 60], ..Position::from_state(HandState::new(human, ai, AI)) };
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        for ply in 0..40 {
+            let text = pos.to_text();
+            assert_eq!(Position::parse(&text), Ok(pos.clone()), "{}", text);
+            if pos.state.result_for(AI, &crate::rules::RuleSet::STANDARD).is_some() {
+                return;
+            }
+            let n = pos.state.legal_moves(&mut buf);
+            if n == 0 {
+                pos.pass();
+            } else {
+                let (t, e) = buf[ply % n];
+                pos.place(t, e);
+            }
+        }
+        panic!("hand did not end");
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let p = Position::parse("0122/0566 633335 a 0 h35@65,a@63 40-25").unwrap();
+        let t = |a, b| tile_id_to_index(a, b) as i8;
+        assert_eq!(p.state.hands, [1 << t(0, 1) | 1 << t(2, 2), 1 << t(0, 5) | 1 << t(6, 6)]);
+        assert_eq!((p.state.left, p.state.right, p.state.to_move), (6, 5, AI));
+        assert_eq!(p.chain, vec![(6, 3), (3, 3), (3, 5)]);
+        assert_eq!(p.state.p1, (HUMAN, 6, 5, t(3, 5)));
+        assert_eq!(p.state.p2, (AI, 6, 3));
+        assert_eq!(p.score, [40, 25]);
+
+        // Ends-only board, default score
+        let p = Position::parse("10/66 6:5 h 1 -").unwrap();
+        assert_eq!((p.state.left, p.state.right, p.state.cons_pass), (6, 5, 1));
+        assert!(p.chain.is_empty());
+        assert_eq!(p.to_text(), "01/66 6:5 h 1 - 0-0");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "01/01 - a 0 -",            // tile in both hands
+            "01/66 6353 a 0 -",         // chain does not join
+            "01/66 - x 0 -",
+            "01/66 - a 3 -",
+            "01/66 36 a 0 a36@35",      // ends disagree with the board
+            "01/66 36 a 0 a@36",        // last placement without tile
+            "01/66 36 a 0 a36@36 4-x",
+            "01/66 - a 0",
+        ] {
+            assert!(Position::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_choose_move_accepts_notation() {
+        let json = format!(r#"{{{},"boardEmpty":true}}"#, SIX_OR_LOCK_TILES);
+        let text = crate::wasm_position_notation(&json);
+        assert_eq!(text, format!("{} 0-0", SIX_OR_LOCK));
+        assert!(crate::wasm_choose_move(&text).starts_with(r#"{"tileId":"6-6""#));
+        // Human to move plays the same hand from the other side
+        assert!(crate::wasm_choose_move("0166/56 - h 0 -").starts_with(r#"{"tileId":"6-6""#));
+    }
+
+    #[test]
+    fn test_single_placement_history_and_long_scores() {
+        // One placement so far: no second-to-last placer
+        let text = "55/0166 36 a 0 h36@36 120-95";
+        let p = Position::parse(text).unwrap();
+        assert_eq!(p.state.p1, (HUMAN, 3, 6, tile_id_to_index(3, 6) as i8));
+        assert_eq!(p.state.p2, (-1, 0, 0));
+        assert_eq!(p.score, [120, 95]);
+        assert_eq!(p.to_text(), text);
+
+        // The same position from JSON, where only the ends are known
+        let json = r#"{"aiTiles":[{"low":6,"high":6},{"low":0,"high":1}],"humanTiles":[{"low":5,"high":5}],
+            "left":3,"right":6,"matchScore":{"human":120,"ai":95},
+            "moveHistory":[{"player":"human","tileLow":3,"tileHigh":6,"boardLeft":3,"boardRight":6}]}"#;
+        let from_json = crate::wasm_position_notation(json);
+        assert_eq!(from_json, "55/0166 3:6 a 0 h36@36 120-95");
+        let q = Position::parse(&from_json).unwrap();
+        assert_eq!((q.state, q.score), (p.state, p.score));
+    }
+}
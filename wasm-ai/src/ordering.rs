@@ -1,11 +1,9 @@
-/// Move ordering: killer heuristic (2 slots per depth) + history heuristic.
-/// Insertion sort by score — small move lists (max ~14 moves) make this optimal.
+//! Move ordering: killer heuristic (2 slots per depth) + history heuristic.
+//! Insertion sort by score — small move lists (max ~14 moves) make this optimal.
 
-use crate::lookup::{
-    TILE_PIPS, TILE_IS_DOUBLE, TILE_LOW, TILE_HIGH, TILE_00_BIT, ZERO_SUIT_NO_00,
-    NEW_END_LEFT, NEW_END_RIGHT, popcount,
-};
 use crate::movegen::{count_moves_bb, MOVE_TILE_BUF, MOVE_END_BUF, MOVE_SCORE_BUF};
+use crate::search::compute_new_ends;
+use crate::set::{Bitboard, DominoSet, MAX_SET_TILES};
 
 // Move ordering bonuses (matching JS MO_* constants)
 const MO_DOMINO: f64 = 1000.0;
@@ -22,16 +20,17 @@ pub static mut KILLER_TILE_ID: [i8; MAX_DEPTH_SLOTS * 2] = [-1; MAX_DEPTH_SLOTS
 pub static mut KILLER_END: [i8; MAX_DEPTH_SLOTS * 2] = [-2; MAX_DEPTH_SLOTS * 2];
 
 /// History heuristic: [tile_idx][end+1] (end: -1=pass(unused), 0=left, 1=right).
-pub static mut HISTORY_SCORE: [[i32; 3]; 28] = [[0; 3]; 28];
+pub static mut HISTORY_SCORE: [[i32; 3]; MAX_SET_TILES] = [[0; 3]; MAX_SET_TILES];
 
 /// Clear killer and history tables (call at start of each root search).
+#[allow(clippy::needless_range_loop)]
 pub fn clear_move_ordering_data() {
     unsafe {
         for k in 0..MAX_DEPTH_SLOTS * 2 {
             KILLER_TILE_ID[k] = -1;
             KILLER_END[k] = -2;
         }
-        for h in 0..28 {
+        for h in 0..MAX_SET_TILES {
             HISTORY_SCORE[h] = [0, 0, 0];
         }
     }
@@ -67,13 +66,14 @@ pub fn record_history(tile_idx: i8, end: i8, depth: i32) {
 ///
 /// # Safety
 /// Reads/writes global move buffers and ordering state.
-pub unsafe fn order_moves_at_ply(
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn order_moves_at_ply<S: DominoSet>(
     ply: usize,
     num_moves: usize,
     is_ai: bool,
     depth: i32,
-    ai_hand: i32,
-    human_hand: i32,
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
     left: i8,
     right: i8,
 ) {
@@ -81,7 +81,8 @@ pub unsafe fn order_moves_at_ply(
         return;
     }
 
-    let base = ply * 28;
+    let base = ply * S::NUM_TILES;
+    let t = S::tables();
     let my_hand = if is_ai { ai_hand } else { human_hand };
     let opp_hand = if is_ai { human_hand } else { ai_hand };
 
@@ -92,7 +93,7 @@ pub unsafe fn order_moves_at_ply(
         let mut s: f64 = 0.0;
 
         // Domino bonus (last tile)
-        if popcount(my_hand) == 1 {
+        if my_hand.count() == 1 {
             s += MO_DOMINO;
         }
 
@@ -110,29 +111,23 @@ pub unsafe fn order_moves_at_ply(
         s += HISTORY_SCORE[t_idx][(end + 1) as usize] as f64;
 
         // Double bonus
-        if TILE_IS_DOUBLE[t_idx] {
+        if t.is_double[t_idx] {
             s += MO_DOUBLE;
         }
 
         // Pip multiplier (prefer playing high-pip tiles)
-        s += TILE_PIPS[t_idx] as f64 * MO_PIP_MULT;
+        s += t.pips[t_idx] as f64 * MO_PIP_MULT;
 
         // Force-pass bonus
-        let (new_l, new_r) = if left == 7 {
-            (TILE_LOW[t_idx], TILE_HIGH[t_idx])
-        } else if end == 0 {
-            (NEW_END_LEFT[t_idx * 8 + left as usize], right)
-        } else {
-            (left, NEW_END_RIGHT[t_idx * 8 + right as usize])
-        };
-        if count_moves_bb(opp_hand, new_l, new_r) == 0 {
+        let (new_l, new_r) = compute_new_ends::<S>(t_idx, end, left, right);
+        if count_moves_bb::<S>(opp_hand, new_l, new_r) == 0 {
             s += MO_FORCE_PASS;
         }
 
         // Ghost activation bonus
-        if is_ai && (opp_hand & TILE_00_BIT) != 0 {
-            let new_both = (my_hand ^ (1 << t_idx)) | opp_hand;
-            if (new_both & ZERO_SUIT_NO_00) == 0 {
+        if is_ai && !(opp_hand & t.tile_00_bit).is_zero() {
+            let new_both = (my_hand ^ S::Bits::bit(t_idx)) | opp_hand;
+            if (new_both & t.zero_suit_no_00).is_zero() {
                 s += MO_GHOST;
             }
         }
@@ -163,14 +158,16 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn test_clear_ordering() {
+        let _guard = crate::search::lock_engine();
         clear_move_ordering_data();
         unsafe {
             for k in 0..MAX_DEPTH_SLOTS * 2 {
                 assert_eq!(KILLER_TILE_ID[k], -1);
                 assert_eq!(KILLER_END[k], -2);
             }
-            for h in 0..28 {
+            for h in 0..MAX_SET_TILES {
                 assert_eq!(HISTORY_SCORE[h], [0, 0, 0]);
             }
         }
@@ -178,6 +175,7 @@ mod tests {
 
     #[test]
     fn test_record_killer_two_slots() {
+        let _guard = crate::search::lock_engine();
         clear_move_ordering_data();
         record_killer(3, 5, 0);
         unsafe {
@@ -196,6 +194,7 @@ mod tests {
 
     #[test]
     fn test_history_cap() {
+        let _guard = crate::search::lock_engine();
         clear_move_ordering_data();
         // Record huge depth to check cap
         for _ in 0..200 {
@@ -0,0 +1,336 @@
+//! Perft: count every move sequence from a position, to the end of the hand
+//! or to a fixed number of plies, through the engine's own `generate_moves`
+//! and hand-end rules. `perft_naive` counts the same tree from pip values
+//! alone, so disagreements point at movegen, the end tables or the terminal
+//! checks.
+//!
+//! A ply is one placement or one forced pass. A hand ends on a domino (the
+//! placer's hand is empty) or a block (neither side can play after a
+//! placement, or a second consecutive pass).
+
+use std::ops::AddAssign;
+
+use crate::game::HandState;
+use crate::lookup::{NUM_TILES, TILE_HIGH, TILE_LOW};
+use crate::movegen::{count_moves_bb, generate_moves, MAX_PLY, MOVE_END_BUF, MOVE_TILE_BUF};
+use crate::search::{compute_new_ends, lock_engine};
+use crate::set::DoubleSix;
+
+/// Tree totals. `leaves` = `dominoes` + `blocks` + sequences cut by the
+/// depth limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerftCounts {
+    pub leaves: u64,
+    pub placements: u64,
+    pub passes: u64,
+    pub dominoes: u64,
+    pub blocks: u64,
+}
+
+impl AddAssign for PerftCounts {
+    fn add_assign(&mut self, o: Self) {
+        self.leaves += o.leaves;
+        self.placements += o.placements;
+        self.passes += o.passes;
+        self.dominoes += o.dominoes;
+        self.blocks += o.blocks;
+    }
+}
+
+impl PerftCounts {
+    fn domino() -> Self {
+        PerftCounts { leaves: 1, dominoes: 1, ..Default::default() }
+    }
+
+    fn block() -> Self {
+        PerftCounts { leaves: 1, blocks: 1, ..Default::default() }
+    }
+
+    fn cut() -> Self {
+        PerftCounts { leaves: 1, ..Default::default() }
+    }
+}
+
+/// Whether the hand in `pos` is already over (a side out of tiles, both
+/// sides stuck, or two passes in a row).
+fn root_result(pos: &HandState) -> Option<PerftCounts> {
+    if pos.hands[0] == 0 || pos.hands[1] == 0 {
+        Some(PerftCounts::domino())
+    } else if pos.cons_pass >= 2 || !(pos.can_move(0) || pos.can_move(1)) {
+        Some(PerftCounts::block())
+    } else {
+        None
+    }
+}
+
+/// Count the tree below `pos`, `depth` plies deep (`None` = to the end of
+/// the hand).
+pub fn perft(pos: &HandState, depth: Option<u32>) -> PerftCounts {
+    if let Some(c) = root_result(pos) {
+        return c;
+    }
+    let depth = depth.unwrap_or(u32::MAX);
+    let _guard = lock_engine();
+    // SAFETY: the engine lock is held, so the per-ply buffers are ours.
+    unsafe {
+        perft_bb(pos.hands, pos.left, pos.right, pos.to_move as usize, pos.cons_pass, depth, 0)
+    }
+}
+
+/// Counts split by root move, as ((tile, end), counts); a forced pass is
+/// listed as tile -1.
+pub fn perft_divide(pos: &HandState, depth: Option<u32>) -> Vec<((i8, i8), PerftCounts)> {
+    if root_result(pos).is_some() || depth == Some(0) {
+        return Vec::new();
+    }
+    let child_depth = depth.map(|d| d - 1);
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let n = pos.legal_moves(&mut buf);
+    if n == 0 {
+        let mut after = *pos;
+        after.pass();
+        let mut c = perft(&after, child_depth);
+        c.passes += 1;
+        return vec![((-1, 0), c)];
+    }
+    buf[..n].iter().map(|&(t, e)| {
+        let mut after = *pos;
+        after.place(t, e);
+        let mut c = perft(&after, child_depth);
+        c.placements += 1;
+        ((t, e), c)
+    }).collect()
+}
+
+unsafe fn perft_bb(
+    hands: [i32; 2], left: i8, right: i8, who: usize, cons_pass: i32, depth: u32, ply: usize,
+) -> PerftCounts {
+    if depth == 0 {
+        return PerftCounts::cut();
+    }
+    assert!(ply < MAX_PLY, "perft deeper than the move buffers");
+    let n = generate_moves::<DoubleSix>(hands[who], left, right, ply);
+    if n == 0 {
+        if cons_pass + 1 >= 2 {
+            return PerftCounts::block();
+        }
+        let mut c = perft_bb(hands, left, right, 1 - who, cons_pass + 1, depth - 1, ply + 1);
+        c.passes += 1;
+        return c;
+    }
+
+    let base = ply * NUM_TILES;
+    let mut total = PerftCounts { placements: n as u64, ..Default::default() };
+    for i in 0..n {
+        let t = MOVE_TILE_BUF[base + i] as usize;
+        let end = MOVE_END_BUF[base + i];
+        let mut h = hands;
+        h[who] ^= 1 << t;
+        let (nl, nr) = compute_new_ends::<DoubleSix>(t, end, left, right);
+        total += if h[who] == 0 {
+            PerftCounts::domino()
+        } else if count_moves_bb::<DoubleSix>(h[0], nl, nr) == 0 && count_moves_bb::<DoubleSix>(h[1], nl, nr) == 0 {
+            PerftCounts::block()
+        } else {
+            perft_bb(h, nl, nr, 1 - who, 0, depth - 1, ply + 1)
+        };
+    }
+    total
+}
+
+// ---------------------------------------------------------------------
+// Naive reference
+// ---------------------------------------------------------------------
+
+/// Position as plain pip pairs, independent of bitboards and lookup tables.
+#[derive(Clone, Debug)]
+struct NaivePos {
+    hands: [Vec<(i8, i8)>; 2],
+    /// `None` while the board is empty
+    ends: Option<(i8, i8)>,
+    to_move: usize,
+    cons_pass: i32,
+}
+
+impl NaivePos {
+    fn from_state(pos: &HandState) -> Self {
+        let tiles = |mask: i32| (0..NUM_TILES)
+            .filter(|&t| mask & (1 << t) != 0)
+            .map(|t| (TILE_LOW[t], TILE_HIGH[t]))
+            .collect();
+        NaivePos {
+            hands: [tiles(pos.hands[0]), tiles(pos.hands[1])],
+            ends: if pos.left == 7 { None } else { Some((pos.left, pos.right)) },
+            to_move: pos.to_move as usize,
+            cons_pass: pos.cons_pass,
+        }
+    }
+
+    /// Every placement for `who` as (hand slot, new ends). On equal ends
+    /// both sides give the same board, so only one is listed.
+    fn moves(&self, who: usize) -> Vec<(usize, (i8, i8))> {
+        let mut out = Vec::new();
+        for (i, &(a, b)) in self.hands[who].iter().enumerate() {
+            match self.ends {
+                None => out.push((i, (a, b))),
+                Some((l, r)) => {
+                    let other = |pip: i8| if a == pip { Some(b) } else if b == pip { Some(a) } else { None };
+                    if let Some(x) = other(l) {
+                        out.push((i, (x, r)));
+                    }
+                    if l != r {
+                        if let Some(x) = other(r) {
+                            out.push((i, (l, x)));
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Same counts as `perft`, by brute force over pip values.
+pub fn perft_naive(pos: &HandState, depth: Option<u32>) -> PerftCounts {
+    let p = NaivePos::from_state(pos);
+    if p.hands.iter().any(|h| h.is_empty()) {
+        return PerftCounts::domino();
+    }
+    if p.cons_pass >= 2 || (p.moves(0).is_empty() && p.moves(1).is_empty()) {
+        return PerftCounts::block();
+    }
+    naive_rec(&p, depth.unwrap_or(u32::MAX))
+}
+
+fn naive_rec(p: &NaivePos, depth: u32) -> PerftCounts {
+    if depth == 0 {
+        return PerftCounts::cut();
+    }
+    let who = p.to_move;
+    let moves = p.moves(who);
+    if moves.is_empty() {
+        if p.cons_pass == 1 {
+            return PerftCounts::block();
+        }
+        let mut next = p.clone();
+        next.to_move = 1 - who;
+        next.cons_pass += 1;
+        let mut c = naive_rec(&next, depth - 1);
+        c.passes += 1;
+        return c;
+    }
+
+    let mut total = PerftCounts::default();
+    for (slot, ends) in moves {
+        let mut next = p.clone();
+        next.hands[who].remove(slot);
+        next.ends = Some(ends);
+        next.to_move = 1 - who;
+        next.cons_pass = 0;
+        total.placements += 1;
+        total += if next.hands[who].is_empty() {
+            PerftCounts::domino()
+        } else if next.moves(0).is_empty() && next.moves(1).is_empty() {
+            PerftCounts::block()
+        } else {
+            naive_rec(&next, depth - 1)
+        };
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::deal;
+    use crate::fixtures::{bit, six_or_lock};
+    use crate::game::{AI, HUMAN};
+    use crate::lookup::{tile_id_to_index, NEW_END_LEFT, NEW_END_RIGHT};
+    use crate::zobrist::Xorshift32;
+
+    /// Play `plies` random moves from deal `seed`, stopping early at the end
+    /// of the hand.
+    fn random_position(seed: u32, plies: u32) -> HandState {
+        let [human, ai] = deal(seed);
+        let mut pos = HandState::new(human, ai, (seed & 1) as i8);
+        let mut rng = Xorshift32::new(seed | 1);
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        for _ in 0..plies {
+            if root_result(&pos).is_some() {
+                break;
+            }
+            let n = pos.legal_moves(&mut buf);
+            if n == 0 {
+                pos.pass();
+            } else {
+                let (t, e) = buf[(rng.next() % n as u32) as usize];
+                pos.place(t, e);
+            }
+        }
+        pos
+    }
+
+    #[test]
+    fn test_end_tables_match_pips() {
+        for t in 0..NUM_TILES {
+            let (a, b) = (TILE_LOW[t], TILE_HIGH[t]);
+            for v in 0..7i8 {
+                let expect = if a == v { b } else if b == v { a } else { -1 };
+                assert_eq!(NEW_END_LEFT[t * 8 + v as usize], expect, "left t={} v={}", t, v);
+                assert_eq!(NEW_END_RIGHT[t * 8 + v as usize], expect, "right t={} v={}", t, v);
+            }
+            assert_eq!(NEW_END_LEFT[t * 8 + 7], a);
+            assert_eq!(NEW_END_RIGHT[t * 8 + 7], b);
+        }
+    }
+
+    #[test]
+    fn test_known_counts() {
+        // AI: [6-6] [0-1], human: [5-6], AI leads.
+        //   [6-6] → human [5-6] → AI stuck on 5|6, human out: 1 domino
+        //   [0-1] → nobody can play: 1 block
+        let pos = six_or_lock();
+        let c = perft(&pos, None);
+        assert_eq!(c, PerftCounts { leaves: 2, placements: 3, passes: 0, dominoes: 1, blocks: 1 });
+
+        // Opening deal: one root move per tile
+        let [human, ai] = deal(5);
+        let pos = HandState::new(human, ai, HUMAN);
+        assert_eq!(perft(&pos, Some(1)).leaves, 14);
+        assert_eq!(perft_divide(&pos, Some(2)).len(), 14);
+    }
+
+    #[test]
+    fn test_pass_is_a_ply() {
+        // AI leads [6-6]; human holds no 6 and must pass
+        let mut pos = HandState::new(bit(0, 1) | bit(2, 3), bit(6, 6) | bit(5, 6) | bit(1, 5), AI);
+        pos.place(tile_id_to_index(6, 6) as i8, 0);
+        let c = perft(&pos, None);
+        assert_eq!(c, perft_naive(&pos, None));
+        assert!(c.passes > 0);
+        let divide = perft_divide(&pos, None);
+        assert_eq!(divide.len(), 1);
+        assert_eq!(divide[0].0, (-1, 0));
+        assert_eq!(divide[0].1, c);
+        assert_eq!(perft(&pos, Some(1)), PerftCounts { leaves: 1, passes: 1, ..Default::default() });
+    }
+
+    #[test]
+    fn test_matches_naive_to_depth() {
+        for seed in 1..=6 {
+            let pos = random_position(seed, (seed % 3) * 2);
+            for depth in 1..=4 {
+                assert_eq!(perft(&pos, Some(depth)), perft_naive(&pos, Some(depth)),
+                           "seed {} depth {}", seed, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_naive_to_end() {
+        for seed in 1..=12 {
+            let pos = random_position(seed, 18);
+            assert_eq!(perft(&pos, None), perft_naive(&pos, None), "seed {}", seed);
+        }
+    }
+}
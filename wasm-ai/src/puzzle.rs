@@ -0,0 +1,304 @@
+//! Puzzle generator: "find the only good move" positions for daily puzzles.
+//!
+//! Seeded deals (`deal::deal`) are played forward by the engine. At every
+//! decision point with at most `max_tiles` tiles left, each legal move is
+//! solved on its own (`SearchLimits::root_moves`). The position is a puzzle
+//! when one move beats every alternative by at least `min_margin` points and
+//!
+//! - it is the only move that wins the hand (`Theme::Win`), or
+//! - the gap is a Ghost 13 or puppeteer trap: solved again with that rule
+//!   off, the gap shrinks below the margin (`Theme::Ghost13`,
+//!   `Theme::Puppeteer`).
+//!
+//! Each deal gives at most one puzzle. The solution line is the move and
+//! then best play for both sides to the end of the hand; its length rates
+//! the difficulty. As in `training`, every search is node-capped and starts
+//! from an empty TT, so a seed yields the same puzzles (and `Puzzle::id`s)
+//! on any machine.
+
+use crate::deal;
+use crate::equity::Objective;
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::NUM_TILES;
+use crate::notation::Position;
+use crate::rules::RuleSet;
+use crate::search::SearchLimits;
+use crate::training::UNTIMED;
+
+/// Why the solution is the only good move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    /// The only move that wins the hand
+    Win,
+    /// The alternatives lose to the [0-0] counting 13
+    Ghost13,
+    /// The alternatives lose to the puppeteer rule's block blame
+    Puppeteer,
+}
+
+impl Theme {
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Win => "win",
+            Theme::Ghost13 => "ghost13",
+            Theme::Puppeteer => "puppeteer",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PuzzleOptions {
+    /// First deal seed
+    pub seed: u32,
+    /// Deals to play
+    pub deals: u32,
+    /// Only positions with at most this many tiles left in both hands
+    pub max_tiles: i32,
+    /// Points every alternative must lose against the solution
+    pub min_margin: i32,
+    /// Node cap per move when playing the deals forward
+    pub play_nodes: u32,
+    /// Node cap per solve; a position whose solves do not finish is skipped
+    pub solve_nodes: u32,
+    pub rules: RuleSet,
+}
+
+impl Default for PuzzleOptions {
+    fn default() -> Self {
+        PuzzleOptions {
+            seed: 1,
+            deals: 100,
+            max_tiles: 14,
+            min_margin: 10,
+            play_nodes: 20_000,
+            solve_nodes: 20_000_000,
+            rules: RuleSet::STANDARD,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    /// Deal seed and the number of moves (passes included) played before
+    pub seed: u32,
+    pub ply: usize,
+    /// The position; its side to move is the solver
+    pub position: Position,
+    pub theme: Theme,
+    /// The solution move, then best play to the end of the hand, as
+    /// (tile, end) with tile -1 for a pass
+    pub line: Vec<(i8, i8)>,
+    /// Hand points for the side to move after the solution
+    pub score: i32,
+    /// Points the best alternative loses against the solution
+    pub margin: i32,
+    /// 1 (easy) – 5 (hard), from the length of `line`
+    pub difficulty: u8,
+}
+
+impl Puzzle {
+    /// Identifier that is stable across runs and machines: `<seed>-<ply>`.
+    pub fn id(&self) -> String {
+        format!("{}-{}", self.seed, self.ply)
+    }
+
+    /// One-line JSON, also accepted as `SearchInput` (`position`, `rules`).
+    pub fn to_json(&self, rules: &RuleSet) -> String {
+        let desc = crate::PuzzleDesc {
+            id: self.id(),
+            position: self.position.to_text(),
+            rules: crate::RulesDesc::from_rule_set(rules),
+            to_move: crate::analysis::side_name(self.position.state.to_move).to_string(),
+            theme: self.theme.name().to_string(),
+            solution: crate::line_descs(self.position.state.to_move, &self.line),
+            score: self.score,
+            margin: self.margin,
+            depth: self.line.len(),
+            difficulty: self.difficulty,
+        };
+        serde_json::to_string(&desc).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Play deals `seed..seed + deals` forward and report each puzzle found as
+/// soon as it is found. The leader alternates with the seed (AI on even).
+pub fn generate(opts: &PuzzleOptions, mut found: impl FnMut(Puzzle)) {
+    let play = SearchLimits { max_nodes: opts.play_nodes, fresh: true, ..SearchLimits::NONE };
+    for seed in opts.seed..opts.seed.saturating_add(opts.deals) {
+        let [human, ai] = deal::deal(seed);
+        let leader = if seed & 1 == 0 { AI } else { HUMAN };
+        let mut pos = Position::from_state(HandState::new(human, ai, leader));
+        let mut ply = 0;
+        while pos.state.hand_end().is_none() {
+            let mover = pos.state.to_move;
+            let mut buf = [(0i8, 0i8); NUM_TILES];
+            let n = pos.state.legal_moves(&mut buf);
+            if n == 0 {
+                pos.pass();
+                ply += 1;
+                continue;
+            }
+            if n >= 2 && pos.state.tiles_left() <= opts.max_tiles {
+                if let Some(p) = find_puzzle(&pos, opts) {
+                    found(Puzzle { seed, ply, ..p });
+                    break;
+                }
+            }
+            let r = pos.state.search_limited(mover, UNTIMED, &opts.rules, Objective::Points, 0, &play);
+            let (t, e) = pos.state.find_legal(r.best_tile_idx, r.best_end).unwrap_or(buf[0]);
+            pos.place(t, e);
+            ply += 1;
+        }
+    }
+}
+
+/// The puzzle in `pos`, if it is one (`seed` and `ply` left at 0).
+pub fn find_puzzle(pos: &Position, opts: &PuzzleOptions) -> Option<Puzzle> {
+    let state = &pos.state;
+    let values = solve_moves(state, &opts.rules, opts.solve_nodes)?;
+    let (tile, end, score) = values.iter().copied().max_by_key(|&(_, _, sc)| sc)?;
+    let margin = gap(&values, tile, end);
+    if margin < opts.min_margin {
+        return None;
+    }
+
+    let theme = if score > 0 && values.iter().filter(|&&(_, _, sc)| sc > 0).count() == 1 {
+        Theme::Win
+    } else {
+        let traps = [
+            (Theme::Ghost13, opts.rules.ghost13, RuleSet { ghost13: false, ..opts.rules }),
+            (Theme::Puppeteer, opts.rules.puppeteer, RuleSet { puppeteer: false, ..opts.rules }),
+        ];
+        traps.into_iter().filter(|&(_, on, _)| on).find_map(|(theme, _, off)| {
+            let values = solve_moves(state, &off, opts.solve_nodes)?;
+            (gap(&values, tile, end) < opts.min_margin).then_some(theme)
+        })?
+    };
+
+    let line = solution_line(state, tile, end, &opts.rules, opts.solve_nodes);
+    Some(Puzzle {
+        seed: 0,
+        ply: 0,
+        position: pos.clone(),
+        theme,
+        difficulty: line.len().div_ceil(3).clamp(1, 5) as u8,
+        line,
+        score,
+        margin,
+    })
+}
+
+/// Exact value of every legal move for the side to move, as (tile, end,
+/// points), or `None` if a solve does not finish within `max_nodes`.
+pub fn solve_moves(state: &HandState, rules: &RuleSet, max_nodes: u32) -> Option<Vec<(i8, i8, i32)>> {
+    let mover = state.to_move;
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let n = state.legal_moves(&mut buf);
+    buf[..n].iter().map(|&(t, e)| {
+        let limits = SearchLimits {
+            max_nodes,
+            root_moves: SearchLimits::root_move_bit(t, e),
+            fresh: true,
+            ..SearchLimits::NONE
+        };
+        let r = state.search_limited(mover, UNTIMED, rules, Objective::Points, 0, &limits);
+        (r.depth >= state.tiles_left()).then_some((t, e, r.best_score))
+    }).collect()
+}
+
+/// How much better (`tile`, `end`) is than the best other move.
+fn gap(values: &[(i8, i8, i32)], tile: i8, end: i8) -> i32 {
+    let own = values.iter().find(|v| (v.0, v.1) == (tile, end)).map_or(i32::MIN, |v| v.2);
+    let rest = values.iter().filter(|v| (v.0, v.1) != (tile, end)).map(|v| v.2).max();
+    rest.map_or(i32::MAX, |r| own.saturating_sub(r))
+}
+
+/// Play `tile`/`end`, then the engine's move for whichever side is to move
+/// until the hand ends.
+fn solution_line(state: &HandState, tile: i8, end: i8, rules: &RuleSet, max_nodes: u32) -> Vec<(i8, i8)> {
+    let limits = SearchLimits { max_nodes, fresh: true, ..SearchLimits::NONE };
+    let mut s = *state;
+    s.place(tile, end);
+    let mut line = vec![(tile, end)];
+    while s.hand_end().is_none() {
+        let mover = s.to_move;
+        if !s.can_move(mover) {
+            s.pass();
+            line.push((-1, -1));
+            continue;
+        }
+        let r = s.search_limited(mover, UNTIMED, rules, Objective::Points, 0, &limits);
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        s.legal_moves(&mut buf);
+        let (t, e) = s.find_legal(r.best_tile_idx, r.best_end).unwrap_or(buf[0]);
+        s.place(t, e);
+        line.push((t, e));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_puzzles_hold() {
+        let opts = PuzzleOptions { seed: 8, deals: 6, max_tiles: 12, solve_nodes: 2_000_000, ..Default::default() };
+        let mut puzzles = Vec::new();
+        generate(&opts, |p| puzzles.push(p));
+        // Node caps, not the clock, decide every search, so the same
+        // puzzles come out on any machine and in debug builds
+        let ids: Vec<String> = puzzles.iter().map(Puzzle::id).collect();
+        assert_eq!(ids, ["9-21", "10-20", "12-23", "13-17"]);
+        for p in &puzzles {
+            // The line is legal, ends the hand, and scores what it claims
+            let mut s = p.position.state;
+            let mover = s.to_move;
+            for &(t, e) in &p.line {
+                if t < 0 {
+                    assert!(!s.can_move(s.to_move));
+                    s.pass();
+                } else {
+                    assert_eq!(s.find_legal(t, e), Some((t, e)), "{}", p.id());
+                    s.place(t, e);
+                }
+            }
+            assert_eq!(s.result_for(mover, &opts.rules), Some(p.score), "{}", p.id());
+            assert!(p.margin >= opts.min_margin);
+            assert!((1..=5).contains(&p.difficulty));
+
+            // No alternative comes within the margin
+            let values = solve_moves(&p.position.state, &opts.rules, opts.solve_nodes).unwrap();
+            let (t, e) = p.line[0];
+            assert!(values.iter().all(|v| (v.0, v.1) == (t, e) || v.2 <= p.score - opts.min_margin));
+        }
+    }
+
+    fn trap(text: &str, tile: &str, end: i8) -> (Puzzle, PuzzleOptions) {
+        let pos = Position::parse(text).unwrap();
+        let opts = PuzzleOptions { solve_nodes: 2_000_000, ..Default::default() };
+        let p = find_puzzle(&pos, &opts).expect("a puzzle");
+        assert_eq!((crate::tile_name(p.line[0].0), p.line[0].1), (tile.to_string(), end));
+        assert!(p.margin >= opts.min_margin);
+        (p, opts)
+    }
+
+    #[test]
+    fn test_ghost13_theme() {
+        let (p, opts) = trap("12/00011144 0225511665533666644333322226600555544003311442 a 0 h24@02,a@04 0-0", "0-0", 0);
+        assert_eq!(p.theme, Theme::Ghost13);
+        assert_eq!(p.score, -11);
+        // Without the rule the double-blank is no longer the only move
+        let off = PuzzleOptions { rules: RuleSet { ghost13: false, ..opts.rules }, ..opts };
+        assert!(find_puzzle(&p.position, &off).is_none_or(|q| q.theme != Theme::Ghost13));
+    }
+
+    #[test]
+    fn test_puppeteer_theme() {
+        let (p, opts) = trap("111444/4566 5330000223311660055110044333366222244665555221 h 0 a35@51,h@31 0-0", "1-1", 1);
+        assert_eq!(p.theme, Theme::Puppeteer);
+        assert_eq!(p.score, 24);
+        let off = PuzzleOptions { rules: RuleSet { puppeteer: false, ..opts.rules }, ..opts };
+        assert!(find_puzzle(&p.position, &off).is_none_or(|q| q.theme != Theme::Puppeteer));
+    }
+}
@@ -0,0 +1,70 @@
+//! House-rule configuration for the two-player engine.
+//! `RuleSet::STANDARD` is the ruleset of Game_Req.txt and the shipped game.
+
+/// How a blocked hand is scored once the aggressor is known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockScoring {
+    /// Successful block scores twice the opponent's pips; a failed block gives
+    /// the opponent all pips in both hands (Game_Req.txt §9.2–9.3).
+    DoubleOpponent,
+    /// The block winner (same aggressor test) scores all pips in both hands.
+    AllPips,
+}
+
+/// How a domino is scored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DominoScoring {
+    /// Winner scores the loser's remaining pips (Game_Req.txt §9.1).
+    LoserPips,
+    /// Winner scores all opponents' remaining pips rounded to the nearest 5.
+    AllOpponentsRounded,
+}
+
+/// Rule switches read by scoring, evaluation and search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleSet {
+    /// [0-0] counts 13 when every other zero-suit tile is on the board.
+    pub ghost13: bool,
+    /// The player who forced the blocking move is the aggressor (§8.1).
+    pub puppeteer: bool,
+    pub block_scoring: BlockScoring,
+    pub domino_scoring: DominoScoring,
+    /// Match target (first to reach it wins).
+    pub target_score: i32,
+}
+
+impl RuleSet {
+    pub const STANDARD: RuleSet = RuleSet {
+        ghost13: true,
+        puppeteer: true,
+        block_scoring: BlockScoring::DoubleOpponent,
+        domino_scoring: DominoScoring::LoserPips,
+        target_score: 100,
+    };
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Round to the nearest multiple of 5 (halves round up).
+#[inline]
+pub fn round_to_five(pips: i32) -> i32 {
+    (pips + 2) / 5 * 5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_to_five() {
+        assert_eq!(round_to_five(0), 0);
+        assert_eq!(round_to_five(2), 0);
+        assert_eq!(round_to_five(3), 5);
+        assert_eq!(round_to_five(12), 10);
+        assert_eq!(round_to_five(13), 15);
+    }
+}
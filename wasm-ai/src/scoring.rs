@@ -1,30 +1,26 @@
-/// Terminal scoring: pip counting, domino win, block scoring, puppeteer rule.
+//! Terminal scoring: pip counting, domino win, block scoring, puppeteer rule.
 
-use crate::lookup::{
-    TILE_PIPS, TILE_LOW, TILE_HIGH, TILE_00_BIT, ZERO_SUIT_NO_00,
-    SUIT_MASK, NEW_END_LEFT, NEW_END_RIGHT, popcount,
-};
 use crate::movegen::count_moves_bb;
+use crate::rules::{RuleSet, BlockScoring, DominoScoring, round_to_five};
+use crate::set::{bits, Bitboard, DominoSet};
 
-/// Total pip count for a hand bitmask. Applies Ghost 13 rule:
+/// Total pip count for a hand bitmask. Applies Ghost 13 rule when `ghost13`:
 /// if hand holds [0-0] AND all other zero-suit tiles are gone from
 /// both hands combined, [0-0] counts as 13 pips.
 #[inline]
-pub fn total_pips_bb(hand: i32, both_hands: i32) -> i32 {
-    let ghost13 = (hand & TILE_00_BIT) != 0
-        && (both_hands & ZERO_SUIT_NO_00) == 0;
+pub fn total_pips_bb<S: DominoSet>(hand: S::Bits, both_hands: S::Bits, ghost13: bool) -> i32 {
+    let t = S::tables();
+    let ghost13 = ghost13
+        && !(hand & t.tile_00_bit).is_zero()
+        && (both_hands & t.zero_suit_no_00).is_zero();
 
     let mut sum = 0i32;
-    let mut h = hand;
-    while h != 0 {
-        let bit = h & h.wrapping_neg();
-        let idx = bit.trailing_zeros() as usize;
+    for idx in bits(hand) {
         if idx == 0 && ghost13 {
             sum += 13;
         } else {
-            sum += TILE_PIPS[idx] as i32;
+            sum += t.pips[idx] as i32;
         }
-        h ^= bit;
     }
     sum
 }
@@ -33,8 +29,11 @@ pub fn total_pips_bb(hand: i32, both_hands: i32) -> i32 {
 /// `winner_is_ai`: true if AI won, false if human won.
 /// Returns positive if good for AI, negative if bad.
 #[inline]
-pub fn score_domino_bb(winner_is_ai: bool, loser_hand: i32) -> i32 {
-    let pips = total_pips_bb(loser_hand, loser_hand);
+pub fn score_domino_bb<S: DominoSet>(winner_is_ai: bool, loser_hand: S::Bits, rules: &RuleSet) -> i32 {
+    let mut pips = total_pips_bb::<S>(loser_hand, loser_hand, rules.ghost13);
+    if rules.domino_scoring == DominoScoring::AllOpponentsRounded {
+        pips = round_to_five(pips);
+    }
     if winner_is_ai { pips } else { -pips }
 }
 
@@ -44,43 +43,47 @@ pub fn score_domino_bb(winner_is_ai: bool, loser_hand: i32) -> i32 {
 /// The puppeteer rule: if the last placer (P1) forced the second-to-last
 /// placer (P2) into their only legal move, AND that forced move led to
 /// the block, then P2 is the real aggressor (the puppeteer).
-pub fn detect_aggressor_bb(
+#[allow(clippy::too_many_arguments, clippy::if_same_then_else)]
+pub fn detect_aggressor_bb<S: DominoSet>(
     p1_who: i8, _p1_l: i8, _p1_r: i8, p1_tile: i8,
     p2_who: i8, p2_l: i8, p2_r: i8,
-    last_placer_hand: i32, other_hand: i32,
+    last_placer_hand: S::Bits, other_hand: S::Bits,
 ) -> i8 {
     if p2_who == -1 || p1_tile == -1 {
         return p1_who;
     }
+    let t = S::tables();
+    let empty = S::EMPTY_END;
+    let stride = S::END_STRIDE;
 
     // Reconstruct the hand P2 had BEFORE their forced move
-    let forced_hand = last_placer_hand | (1 << p1_tile);
+    let forced_hand = last_placer_hand | S::Bits::bit(p1_tile as usize);
 
     // Count how many legal moves P2 had at that point
-    let legal_mask = if p2_l == 7 {
+    let legal_mask = if p2_l == empty {
         forced_hand
     } else if p2_l == p2_r {
-        SUIT_MASK[p2_l as usize] & forced_hand
+        t.suit_mask[p2_l as usize] & forced_hand
     } else {
-        (SUIT_MASK[p2_l as usize] | SUIT_MASK[p2_r as usize]) & forced_hand
+        (t.suit_mask[p2_l as usize] | t.suit_mask[p2_r as usize]) & forced_hand
     };
 
-    let legal_count = popcount(legal_mask);
+    let legal_count = legal_mask.count();
     if legal_count != 1 {
         return p1_who;
     }
 
     // P2 had exactly one legal move — check if it led to a block
-    let the_tile_idx = (legal_mask & legal_mask.wrapping_neg()).trailing_zeros() as usize;
-    let lo = TILE_LOW[the_tile_idx];
-    let hi = TILE_HIGH[the_tile_idx];
+    let the_tile_idx = legal_mask.lowest();
+    let lo = t.low[the_tile_idx];
+    let hi = t.high[the_tile_idx];
 
-    let can_l = if p2_l == 7 {
+    let can_l = if p2_l == empty {
         true
     } else {
         lo == p2_l || hi == p2_l
     };
-    let can_r = if p2_l == 7 {
+    let can_r = if p2_l == empty {
         false
     } else if p2_l == p2_r && can_l {
         false
@@ -91,23 +94,23 @@ pub fn detect_aggressor_bb(
     let forced_hand_after = last_placer_hand; // P2's hand after playing that tile
 
     if can_l {
-        let new_l = NEW_END_LEFT[the_tile_idx * 8 + if p2_l == 7 { 7 } else { p2_l as usize }];
-        let new_r = if p2_l == 7 {
-            NEW_END_RIGHT[the_tile_idx * 8 + 7]
+        let new_l = t.new_end_left[the_tile_idx * stride + p2_l as usize];
+        let new_r = if p2_l == empty {
+            t.new_end_right[the_tile_idx * stride + empty as usize]
         } else {
             p2_r
         };
-        if count_moves_bb(other_hand, new_l, new_r) > 0
-            || count_moves_bb(forced_hand_after, new_l, new_r) > 0
+        if count_moves_bb::<S>(other_hand, new_l, new_r) > 0
+            || count_moves_bb::<S>(forced_hand_after, new_l, new_r) > 0
         {
             return p1_who;
         }
     }
     if can_r {
-        let new_r2 = NEW_END_RIGHT[the_tile_idx * 8 + p2_r as usize];
+        let new_r2 = t.new_end_right[the_tile_idx * stride + p2_r as usize];
         let new_l2 = p2_l;
-        if count_moves_bb(other_hand, new_l2, new_r2) > 0
-            || count_moves_bb(forced_hand_after, new_l2, new_r2) > 0
+        if count_moves_bb::<S>(other_hand, new_l2, new_r2) > 0
+            || count_moves_bb::<S>(forced_hand_after, new_l2, new_r2) > 0
         {
             return p1_who;
         }
@@ -117,15 +120,14 @@ pub fn detect_aggressor_bb(
 }
 
 /// Score a blocked game using aggressor detection + pip comparison.
-/// Uses global state for hands and puppeteer history.
-///
-/// # Safety
-/// Reads from global mutable state (gAiHand, gHumanHand, gP1*, gP2*).
-pub unsafe fn score_block_bb(
-    ai_hand: i32,
-    human_hand: i32,
+/// The puppeteer history (P1/P2) is passed in by the search.
+#[allow(clippy::too_many_arguments)]
+pub fn score_block_bb<S: DominoSet>(
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
     p1_who: i8, p1_l: i8, p1_r: i8, p1_tile: i8,
     p2_who: i8, p2_l: i8, p2_r: i8,
+    rules: &RuleSet,
 ) -> i32 {
     let (last_placer_hand, other_hand) = if p1_who == 1 {
         (ai_hand, human_hand)
@@ -133,21 +135,28 @@ pub unsafe fn score_block_bb(
         (human_hand, ai_hand)
     };
 
-    let aggressor = detect_aggressor_bb(
-        p1_who, p1_l, p1_r, p1_tile,
-        p2_who, p2_l, p2_r,
-        last_placer_hand, other_hand,
-    );
+    let aggressor = if rules.puppeteer {
+        detect_aggressor_bb::<S>(
+            p1_who, p1_l, p1_r, p1_tile,
+            p2_who, p2_l, p2_r,
+            last_placer_hand, other_hand,
+        )
+    } else {
+        p1_who
+    };
 
     let both_hands = ai_hand | human_hand;
-    let ai_pips = total_pips_bb(ai_hand, both_hands);
-    let human_pips = total_pips_bb(human_hand, both_hands);
+    let ai_pips = total_pips_bb::<S>(ai_hand, both_hands, rules.ghost13);
+    let human_pips = total_pips_bb::<S>(human_hand, both_hands, rules.ghost13);
 
     let aggr_pips = if aggressor == 1 { ai_pips } else { human_pips };
     let opp_pips = if aggressor == 1 { human_pips } else { ai_pips };
 
     if aggr_pips <= opp_pips {
-        let pts = opp_pips * 2;
+        let pts = match rules.block_scoring {
+            BlockScoring::DoubleOpponent => opp_pips * 2,
+            BlockScoring::AllPips => ai_pips + human_pips,
+        };
         if aggressor == 1 { pts } else { -pts }
     } else {
         let pts = ai_pips + human_pips;
@@ -158,12 +167,14 @@ pub unsafe fn score_block_bb(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::set::DoubleSix;
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_total_pips_simple() {
         // Tile 0 = (0,0) = 0 pips, Tile 1 = (0,1) = 1 pip, Tile 2 = (0,2) = 2 pips
         let hand = (1 << 0) | (1 << 1) | (1 << 2);
-        assert_eq!(total_pips_bb(hand, hand), 0 + 1 + 2);
+        assert_eq!(total_pips_bb::<DoubleSix>(hand, hand, true), 0 + 1 + 2);
     }
 
     #[test]
@@ -173,26 +184,69 @@ mod tests {
         // If both_hands has none of those, ghost 13 triggers
         let hand = 1 << 0; // just [0-0]
         let both = 1 << 0; // only [0-0] in either hand
-        assert_eq!(total_pips_bb(hand, both), 13);
+        assert_eq!(total_pips_bb::<DoubleSix>(hand, both, true), 13);
+        assert_eq!(total_pips_bb::<DoubleSix>(hand, both, false), 0);
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_total_pips_no_ghost13() {
         // [0-0] + [0-1] — zero suit not exhausted
         let hand = (1 << 0) | (1 << 1);
-        assert_eq!(total_pips_bb(hand, hand), 0 + 1); // normal pips
+        assert_eq!(total_pips_bb::<DoubleSix>(hand, hand, true), 0 + 1); // normal pips
     }
 
     #[test]
     fn test_score_domino_ai_wins() {
         // AI wins, human has 10 pips
         let human_hand = 1 << 27; // tile 27 = (6,6) = 12 pips
-        assert_eq!(score_domino_bb(true, human_hand), 12);
+        assert_eq!(score_domino_bb::<DoubleSix>(true, human_hand, &RuleSet::STANDARD), 12);
     }
 
     #[test]
     fn test_score_domino_human_wins() {
         let ai_hand = 1 << 27; // tile 27 = (6,6) = 12 pips
-        assert_eq!(score_domino_bb(false, ai_hand), -12);
+        assert_eq!(score_domino_bb::<DoubleSix>(false, ai_hand, &RuleSet::STANDARD), -12);
+    }
+
+    #[test]
+    fn test_score_domino_rounded() {
+        let rules = RuleSet { domino_scoring: DominoScoring::AllOpponentsRounded, ..RuleSet::STANDARD };
+        // (6,6) + (0,1) = 13 → 15
+        let human_hand = (1 << 27) | (1 << 1);
+        assert_eq!(score_domino_bb::<DoubleSix>(true, human_hand, &rules), 15);
+    }
+
+    #[test]
+    fn test_score_block_house_rules() {
+        // AI last placed [1-1] (idx 7) onto 1/1, nobody can move.
+        // AI keeps (0,1) = 1 pip, human keeps (6,6) = 12 pips.
+        let ai = 1 << 1;
+        let human = 1 << 27;
+        let standard = score_block_bb::<DoubleSix>(ai, human, 1, 1, 1, 7, 0, 1, 1, &RuleSet::STANDARD);
+        assert_eq!(standard, 24);
+        let all = RuleSet { block_scoring: BlockScoring::AllPips, ..RuleSet::STANDARD };
+        let all_pips = score_block_bb::<DoubleSix>(ai, human, 1, 1, 1, 7, 0, 1, 1, &all);
+        assert_eq!(all_pips, 13);
+    }
+
+    #[test]
+    fn test_puppeteer_switch() {
+        // Human (P2) played onto 5/3 leaving AI exactly one legal tile, [3-6],
+        // and every placement of it locks the board: human is the puppeteer.
+        use crate::lookup::tile_id_to_index;
+        let ai_after = 1 << tile_id_to_index(0, 1);
+        let human = 1 << tile_id_to_index(1, 3);
+        let t36 = tile_id_to_index(3, 6) as i8;
+        let args = (1i8, 5i8, 6i8, t36, 0i8, 5i8, 3i8);
+        let aggr = detect_aggressor_bb::<DoubleSix>(args.0, args.1, args.2, args.3, args.4, args.5, args.6, ai_after, human);
+        assert_eq!(aggr, 0);
+        let on = score_block_bb::<DoubleSix>(ai_after, human, args.0, args.1, args.2, args.3, args.4, args.5, args.6, &RuleSet::STANDARD);
+        let off_rules = RuleSet { puppeteer: false, ..RuleSet::STANDARD };
+        let off = score_block_bb::<DoubleSix>(ai_after, human, args.0, args.1, args.2, args.3, args.4, args.5, args.6, &off_rules);
+        // Puppeteer (human, 4 pips) fails against AI's 1 pip: AI scores 5.
+        assert_eq!(on, 5);
+        // Without the rule AI is the aggressor and succeeds: 4 * 2.
+        assert_eq!(off, 8);
     }
 }
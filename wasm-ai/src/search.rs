@@ -1,48 +1,73 @@
-/// Core search engine: minimax with alpha-beta pruning, iterative deepening,
-/// aspiration windows, PVS at root, quiescence extensions.
-/// Direct port of ai-worker.js chooseMoveHard + minimaxBB.
-
-use crate::lookup::{
-    TILE_LOW, TILE_HIGH, NEW_END_LEFT, NEW_END_RIGHT, popcount,
-};
+//! Core search engine: minimax with alpha-beta pruning, iterative deepening,
+//! aspiration windows, PVS at root, quiescence extensions.
+//! Direct port of ai-worker.js chooseMoveHard + minimaxBB, generic over the
+//! domino set: `choose_move` is the double-six game (with its opening book),
+//! `choose_move_set` runs the same search on any `DominoSet`.
+
+use crate::book;
+use crate::set::{Bitboard, DominoSet, DoubleSix, MAX_SET_TILES};
 use crate::zobrist;
 use crate::tt::{self, TT_EXACT, TT_LOWER, TT_UPPER};
 use crate::movegen::{
-    generate_moves, count_moves_bb,
+    generate_moves, collect_moves, count_moves_bb,
     MOVE_TILE_BUF, MOVE_END_BUF,
 };
 use crate::scoring::{score_domino_bb, score_block_bb};
-use crate::eval::evaluate_bb;
+use crate::eval::{self, evaluate_bb, EvalParams};
+use crate::rules::RuleSet;
+use crate::equity::{self, Objective, RootEquity};
 use crate::ordering::{
     order_moves_at_ply, clear_move_ordering_data,
     record_killer, record_history,
 };
+use std::cell::Cell;
+use std::ptr::addr_of;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
 // =====================================================================
 // Global mutable state (WASM is single-threaded, safe to use static mut)
 // =====================================================================
 
-static mut G_AI_HAND: i32 = 0;
-static mut G_HUMAN_HAND: i32 = 0;
-static mut G_LEFT: i8 = 7;
-static mut G_RIGHT: i8 = 7;
-static mut G_HASH: i32 = 0;
-static mut G_PLY: usize = 0;
-static mut G_CONS_PASS: i32 = 0;
 static mut G_MATCH_DIFF: i32 = 0;
-
-// Puppeteer history
-static mut G_P1_WHO: i8 = -1;
-static mut G_P1_L: i8 = 0;
-static mut G_P1_R: i8 = 0;
-static mut G_P1_TILE: i8 = -1;
-static mut G_P2_WHO: i8 = -1;
-static mut G_P2_L: i8 = 0;
-static mut G_P2_R: i8 = 0;
+static mut G_RULES: RuleSet = RuleSet::STANDARD;
+// Rules the TT values were scored under
+static mut G_TT_RULES: RuleSet = RuleSet::STANDARD;
+// Highest pip of the set the TT was filled for (the sets share one key stream)
+static mut G_TT_SET: i8 = 6;
+// Evaluation parameters the TT values were scored under
+static mut G_TT_PARAMS: EvalParams = EvalParams::DEFAULT;
+
+// Match-equity objective: hand results are mapped through G_ROOT_EQUITY
+static mut G_EQUITY: bool = false;
+static mut G_ROOT_EQUITY: RootEquity = RootEquity::EMPTY;
+// TT holds equity-scaled values (cleared whenever either search used equity)
+static mut G_TT_EQUITY: bool = false;
+
+/// Position the search makes and unmakes moves on.
+struct SearchState<S: DominoSet> {
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
+    left: i8,
+    right: i8,
+    hash: i32,
+    ply: usize,
+    cons_pass: i32,
+    // Puppeteer history
+    p1_who: i8,
+    p1_l: i8,
+    p1_r: i8,
+    p1_tile: i8,
+    p2_who: i8,
+    p2_l: i8,
+    p2_r: i8,
+}
 
 // Search counters
 static mut NODE_COUNT: u32 = 0;
 const NODE_LIMIT: u32 = 20_000_000;
+// Per-iteration node cap: NODE_LIMIT, or what is left of a SearchLimits budget
+static mut G_NODE_LIMIT: u32 = NODE_LIMIT;
 
 // TT diagnostic counters
 static mut TT_PROBE_COUNT: u32 = 0;
@@ -54,14 +79,39 @@ static mut TT_HINT_COUNT: u32 = 0;   // returned move hint only
 static mut TIME_START: f64 = 0.0;
 static mut TIME_BUDGET_MS: f64 = 20000.0;
 
+// Set from another thread to end the running search early (polled every
+// 1024 nodes); sticky until cleared
+static STOP: AtomicBool = AtomicBool::new(false);
+
+// `now_ms` time at which the running search stops (0 = none), copied from
+// the starting thread's `DEADLINE` when the search begins
+static mut G_DEADLINE: f64 = 0.0;
+
+thread_local! {
+    static DEADLINE: Cell<f64> = const { Cell::new(0.0) };
+}
+
+// Called after every completed iteration (native tools)
+static mut G_INFO_HOOK: Option<fn(&IterationInfo)> = None;
+
+/// Serializes access to the global search state above. WASM is single-threaded,
+/// but native callers (tests, tools) may search from several threads.
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Acquire the engine lock. `choose_move` takes it internally; anything else
+/// that touches the global buffers or TT directly must hold it.
+pub fn lock_engine() -> MutexGuard<'static, ()> {
+    ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 /// Get current time in milliseconds (via js_sys in WASM, or std in native).
 #[cfg(target_arch = "wasm32")]
-fn now_ms() -> f64 {
+pub(crate) fn now_ms() -> f64 {
     js_sys::Date::now()
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn now_ms() -> f64 {
+pub(crate) fn now_ms() -> f64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -77,11 +127,19 @@ fn now_ms() -> f64 {
 pub struct SearchResult {
     pub best_tile_idx: i8,
     pub best_end: i8,
+    /// Hand points (or equity) for the side searched
     pub best_score: i32,
     pub depth: i32,
     pub nodes: u32,
+    /// Tile placements still to come before the hand ends under best play
+    /// (passes do not count), when the search solved the hand (and its
+    /// result is not zero)
+    pub placements_left: Option<i32>,
     /// Per-move scores: (tile_idx, end, score)
     pub analysis: Vec<(i8, i8, i32)>,
+    /// Expected line from the root, starting with the best move; tile -1 is
+    /// a pass. Read back from the TT, so it can stop short of the horizon.
+    pub pv: Vec<(i8, i8)>,
     // TT diagnostics
     pub tt_probes: u32,
     pub tt_hits: u32,
@@ -89,75 +147,216 @@ pub struct SearchResult {
     pub tt_hints: u32,
 }
 
+/// Caps on the root search on top of the time budget (weaker skill levels).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Deepest iteration (0 = no cap)
+    pub max_depth: i32,
+    /// Nodes over all iterations (0 = no cap)
+    pub max_nodes: u32,
+    /// Root moves to search, one `root_move_bit` each (0 = every legal move)
+    pub root_moves: u64,
+    /// Start from an empty TT, so node-capped results do not depend on
+    /// earlier searches
+    pub fresh: bool,
+}
+
+impl SearchLimits {
+    pub const NONE: SearchLimits = SearchLimits { max_depth: 0, max_nodes: 0, root_moves: 0, fresh: false };
+
+    /// Full strength for callers that want the position's value rather than
+    /// a game move: the opening book answers from its solved deals only,
+    /// never from its suit-profile averages.
+    pub const SOLVE: SearchLimits = SearchLimits { fresh: true, ..SearchLimits::NONE };
+
+    /// `root_moves` bit for placing `tile_idx` on `end`
+    pub fn root_move_bit(tile_idx: i8, end: i8) -> u64 {
+        1 << (2 * tile_idx as u32 + end as u32)
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Progress of the root search after a completed iteration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IterationInfo {
+    pub depth: i32,
+    pub score: i32,
+    pub best_tile_idx: i8,
+    pub best_end: i8,
+    /// Nodes searched in this iteration, as in `SearchResult::nodes`
+    pub nodes: u32,
+    pub elapsed_ms: u32,
+}
+
+/// Ask a search running on another thread to return its best move so far.
+/// The flag stays set (and cuts every later search short) until cleared
+/// with `set_stop(false)`, so clear it before starting a search, not after.
+pub fn set_stop(stop: bool) {
+    STOP.store(stop, Ordering::Relaxed);
+}
+
+/// Stop every search started from this thread at `deadline` (a `now_ms`
+/// time), returning its best move so far; `None` removes the deadline.
+/// Unlike `set_stop` this only affects the calling thread's searches.
+pub fn set_deadline(deadline: Option<f64>) {
+    DEADLINE.with(|d| d.set(deadline.unwrap_or(0.0)));
+}
+
+#[inline]
+unsafe fn stop_requested() -> bool {
+    STOP.load(Ordering::Relaxed) || (G_DEADLINE > 0.0 && now_ms() >= G_DEADLINE)
+}
+
+/// Report every completed iteration of later searches to `hook`.
+pub fn set_info_hook(hook: Option<fn(&IterationInfo)>) {
+    let _guard = lock_engine();
+    unsafe {
+        G_INFO_HOOK = hook;
+    }
+}
+
+// =====================================================================
+// Objective mapping
+// =====================================================================
+
+/// Search values are the objective (hand points, or equity) times
+/// `SCORE_SCALE`. A finished hand adds the tiles still in hand as a
+/// tie-break towards the winner: among equal results the winner prefers the
+/// hand that ends after the fewest placements and the loser the one with
+/// the most (passes do not count). The
+/// tie-break belongs to the position, not to the distance from the root, so
+/// TT entries stay valid from one search to the next.
+pub const SCORE_SCALE: i32 = 32;
+
+/// Largest objective value the search scales (equity's ±`EQUITY_SCALE`
+/// included); ±1000 × 32 + 31 tiles still fits the i16 TT slot.
+const MAX_OBJECTIVE: i32 = 1000;
+
+/// Search value of a finished hand worth `value` to the AI with
+/// `tiles_left` tiles still in the two hands. The tie-break saturates at
+/// `SCORE_SCALE - 1` tiles, which only the larger sets' deals reach.
+#[inline(always)]
+fn encode_terminal(value: i32, tiles_left: i32) -> i32 {
+    let v = value.clamp(-MAX_OBJECTIVE, MAX_OBJECTIVE) * SCORE_SCALE;
+    v + value.signum() * tiles_left.min(SCORE_SCALE - 1)
+}
+
+/// Objective value (points or equity) of a search value.
+#[inline]
+pub fn decode_score(score: i32) -> i32 {
+    score / SCORE_SCALE
+}
+
+/// Tiles still in hand when the hand ends, for an exact search value; `None`
+/// when the value carries no tie-break (a zero result).
+#[inline]
+pub fn decode_tiles_left(score: i32) -> Option<i32> {
+    (score.abs() >= SCORE_SCALE).then_some(score.abs() % SCORE_SCALE)
+}
+
+/// Map a finished hand (points, AI perspective) to the search objective.
+#[inline(always)]
+unsafe fn terminal_value<S: DominoSet>(st: &SearchState<S>, points: i32) -> i32 {
+    let value = if G_EQUITY {
+        (*addr_of!(G_ROOT_EQUITY)).terminal(points)
+    } else {
+        points
+    };
+    encode_terminal(value, st.ai_hand.count() + st.human_hand.count())
+}
+
+/// Static evaluation of the search position in the search objective's units.
+#[inline(always)]
+unsafe fn leaf_value<S: DominoSet>(st: &SearchState<S>, rules: &RuleSet) -> i32 {
+    let ev = evaluate_bb::<S>(st.ai_hand, st.human_hand, st.left, st.right, G_MATCH_DIFF, rules);
+    if G_EQUITY {
+        let tiles_left = st.ai_hand.count() + st.human_hand.count();
+        (*addr_of!(G_ROOT_EQUITY)).estimate(ev, tiles_left) * SCORE_SCALE
+    } else {
+        (ev.clamp(-MAX_OBJECTIVE as f64, MAX_OBJECTIVE as f64) * SCORE_SCALE as f64) as i32
+    }
+}
+
 // =====================================================================
 // Inner minimax
 // =====================================================================
 
 /// Minimax with alpha-beta pruning, TT, quiescence extensions.
 /// `is_ai`: true if maximizing (AI's turn), false if minimizing.
-unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32, mut ext: i32) -> i32 {
+#[allow(clippy::if_same_then_else)]
+unsafe fn minimax_bb<S: DominoSet>(st: &mut SearchState<S>, is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32, mut ext: i32) -> i32 {
     NODE_COUNT += 1;
+    let rules = G_RULES;
 
-    if NODE_COUNT >= NODE_LIMIT {
-        return evaluate_bb(G_AI_HAND, G_HUMAN_HAND, G_LEFT, G_RIGHT, G_MATCH_DIFF) as i32;
+    if NODE_COUNT & 0x3ff == 0 && stop_requested() {
+        G_NODE_LIMIT = NODE_COUNT;
+    }
+    if NODE_COUNT >= G_NODE_LIMIT {
+        return leaf_value(st, &rules);
     }
 
-    let my_hand = if is_ai { G_AI_HAND } else { G_HUMAN_HAND };
-    let num_moves = generate_moves(my_hand, G_LEFT, G_RIGHT, G_PLY);
+    let my_hand = if is_ai { st.ai_hand } else { st.human_hand };
+    let num_moves = generate_moves::<S>(my_hand, st.left, st.right, st.ply);
 
     // --- No legal moves: must pass ---
     if num_moves == 0 {
-        let new_cons_pass = G_CONS_PASS + 1;
+        let new_cons_pass = st.cons_pass + 1;
         if new_cons_pass >= 2 {
-            return score_block_bb(
-                G_AI_HAND, G_HUMAN_HAND,
-                G_P1_WHO, G_P1_L, G_P1_R, G_P1_TILE,
-                G_P2_WHO, G_P2_L, G_P2_R,
-            );
+            return terminal_value(st, score_block_bb::<S>(
+                st.ai_hand, st.human_hand,
+                st.p1_who, st.p1_l, st.p1_r, st.p1_tile,
+                st.p2_who, st.p2_l, st.p2_r,
+                &rules,
+            ));
         }
 
-        let saved_cons_pass = G_CONS_PASS;
-        let saved_hash = G_HASH;
+        let saved_cons_pass = st.cons_pass;
+        let saved_hash = st.hash;
 
-        G_HASH ^= zobrist::side_hash();
-        if G_CONS_PASS > 0 { G_HASH ^= zobrist::conspass_hash(1); }
-        G_CONS_PASS = new_cons_pass;
-        if G_CONS_PASS > 0 { G_HASH ^= zobrist::conspass_hash(1); }
+        st.hash ^= zobrist::side_hash::<S>();
+        if st.cons_pass > 0 { st.hash ^= zobrist::conspass_hash::<S>(1); }
+        st.cons_pass = new_cons_pass;
+        if st.cons_pass > 0 { st.hash ^= zobrist::conspass_hash::<S>(1); }
 
-        let score = minimax_bb(!is_ai, depth, alpha, beta, ext);
+        let score = minimax_bb(st, !is_ai, depth, alpha, beta, ext);
 
-        G_HASH = saved_hash;
-        G_CONS_PASS = saved_cons_pass;
+        st.hash = saved_hash;
+        st.cons_pass = saved_cons_pass;
         return score;
     }
 
     // --- Quiescence: extend if forced / tactical ---
     if depth <= 0 {
-        let total_remaining = popcount(G_AI_HAND) + popcount(G_HUMAN_HAND);
+        let total_remaining = st.ai_hand.count() + st.human_hand.count();
         let max_ext = 6 + (12 - total_remaining).max(0);
         let mut extended = false;
         if ext < max_ext {
             if num_moves == 1 {
                 extended = true;
-            } else if G_CONS_PASS > 0 {
+            } else if st.cons_pass > 0 {
                 extended = true;
             } else if total_remaining <= 8 {
-                let opp_hand = if is_ai { G_HUMAN_HAND } else { G_AI_HAND };
-                if count_moves_bb(opp_hand, G_LEFT, G_RIGHT) <= 1 {
+                let opp_hand = if is_ai { st.human_hand } else { st.ai_hand };
+                if count_moves_bb::<S>(opp_hand, st.left, st.right) <= 1 {
                     extended = true;
                 }
             }
         }
         if extended {
             depth = 1;
-            ext = ext + 1; // Match JS: ext = ext + 1
+            ext += 1; // Match JS: ext = ext + 1
         } else {
-            return evaluate_bb(G_AI_HAND, G_HUMAN_HAND, G_LEFT, G_RIGHT, G_MATCH_DIFF) as i32;
+            return leaf_value(st, &rules);
         }
     }
 
     // --- TT probe ---
-    let tt_hit = tt::tt_probe(G_HASH, depth, alpha, beta);
+    let tt_hit = tt::tt_probe(st.hash, depth, alpha, beta);
     let mut tt_best_tile: i8 = -1;
     let mut tt_best_end_val: i8 = -1;
     TT_PROBE_COUNT += 1;
@@ -174,13 +373,13 @@ unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32,
 
     // --- Move ordering ---
     if num_moves > 2 {
-        order_moves_at_ply(G_PLY, num_moves, is_ai, depth,
-                          G_AI_HAND, G_HUMAN_HAND, G_LEFT, G_RIGHT);
+        order_moves_at_ply::<S>(st.ply, num_moves, is_ai, depth,
+                          st.ai_hand, st.human_hand, st.left, st.right);
     }
 
     // TT best move to front
     if tt_best_tile >= 0 {
-        let base = G_PLY * 28;
+        let base = st.ply * S::NUM_TILES;
         for mi in 1..num_moves {
             if MOVE_TILE_BUF[base + mi] == tt_best_tile
                 && MOVE_END_BUF[base + mi] == tt_best_end_val
@@ -197,20 +396,20 @@ unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32,
     }
 
     // --- Save state ---
-    let saved_left = G_LEFT;
-    let saved_right = G_RIGHT;
-    let saved_hash = G_HASH;
-    let saved_cons_pass = G_CONS_PASS;
-    let saved_p1_who = G_P1_WHO;
-    let saved_p1_l = G_P1_L;
-    let saved_p1_r = G_P1_R;
-    let saved_p1_tile = G_P1_TILE;
-    let saved_p2_who = G_P2_WHO;
-    let saved_p2_l = G_P2_L;
-    let saved_p2_r = G_P2_R;
-    let saved_ply = G_PLY;
-
-    let base = G_PLY * 28;
+    let saved_left = st.left;
+    let saved_right = st.right;
+    let saved_hash = st.hash;
+    let saved_cons_pass = st.cons_pass;
+    let saved_p1_who = st.p1_who;
+    let saved_p1_l = st.p1_l;
+    let saved_p1_r = st.p1_r;
+    let saved_p1_tile = st.p1_tile;
+    let saved_p2_who = st.p2_who;
+    let saved_p2_l = st.p2_l;
+    let saved_p2_r = st.p2_r;
+    let saved_ply = st.ply;
+
+    let base = st.ply * S::NUM_TILES;
     let orig_alpha = alpha;
     let orig_beta = beta;
     let mut best_move_idx: i8 = -1;
@@ -222,64 +421,65 @@ unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32,
         for i in 0..num_moves {
             let t_idx = MOVE_TILE_BUF[base + i] as usize;
             let end = MOVE_END_BUF[base + i];
-            let bit = 1i32 << t_idx;
+            let bit = S::Bits::bit(t_idx);
 
-            G_AI_HAND ^= bit;
+            st.ai_hand ^= bit;
 
-            let (new_l, new_r) = compute_new_ends(t_idx, end, saved_left, saved_right);
-            G_LEFT = new_l;
-            G_RIGHT = new_r;
+            let (new_l, new_r) = compute_new_ends::<S>(t_idx, end, saved_left, saved_right);
+            st.left = new_l;
+            st.right = new_r;
 
             // Update hash
-            G_HASH = saved_hash;
-            G_HASH ^= zobrist::tile_hash(t_idx, 0);
-            G_HASH ^= zobrist::left_hash(saved_left as usize);
-            G_HASH ^= zobrist::left_hash(new_l as usize);
-            G_HASH ^= zobrist::right_hash(saved_right as usize);
-            G_HASH ^= zobrist::right_hash(new_r as usize);
-            G_HASH ^= zobrist::side_hash();
-            if saved_cons_pass > 0 { G_HASH ^= zobrist::conspass_hash(1); }
-            G_CONS_PASS = 0;
+            st.hash = saved_hash;
+            st.hash ^= zobrist::tile_hash::<S>(t_idx, 0);
+            st.hash ^= zobrist::left_hash::<S>(saved_left as usize);
+            st.hash ^= zobrist::left_hash::<S>(new_l as usize);
+            st.hash ^= zobrist::right_hash::<S>(saved_right as usize);
+            st.hash ^= zobrist::right_hash::<S>(new_r as usize);
+            st.hash ^= zobrist::side_hash::<S>();
+            if saved_cons_pass > 0 { st.hash ^= zobrist::conspass_hash::<S>(1); }
+            st.cons_pass = 0;
 
             // Update puppeteer
-            G_P2_WHO = saved_p1_who;
-            G_P2_L = saved_p1_l;
-            G_P2_R = saved_p1_r;
-            G_P1_WHO = 1;
-            G_P1_L = new_l;
-            G_P1_R = new_r;
-            G_P1_TILE = t_idx as i8;
-
-            G_PLY = saved_ply + 1;
-
-            let sc = if G_AI_HAND == 0 {
-                score_domino_bb(true, G_HUMAN_HAND)
-            } else if count_moves_bb(G_HUMAN_HAND, new_l, new_r) == 0
-                && count_moves_bb(G_AI_HAND, new_l, new_r) == 0
+            st.p2_who = saved_p1_who;
+            st.p2_l = saved_p1_l;
+            st.p2_r = saved_p1_r;
+            st.p1_who = 1;
+            st.p1_l = new_l;
+            st.p1_r = new_r;
+            st.p1_tile = t_idx as i8;
+
+            st.ply = saved_ply + 1;
+
+            let sc = if st.ai_hand.is_zero() {
+                terminal_value(st, score_domino_bb::<S>(true, st.human_hand, &rules))
+            } else if count_moves_bb::<S>(st.human_hand, new_l, new_r) == 0
+                && count_moves_bb::<S>(st.ai_hand, new_l, new_r) == 0
             {
-                score_block_bb(
-                    G_AI_HAND, G_HUMAN_HAND,
-                    G_P1_WHO, G_P1_L, G_P1_R, G_P1_TILE,
-                    G_P2_WHO, G_P2_L, G_P2_R,
-                )
+                terminal_value(st, score_block_bb::<S>(
+                    st.ai_hand, st.human_hand,
+                    st.p1_who, st.p1_l, st.p1_r, st.p1_tile,
+                    st.p2_who, st.p2_l, st.p2_r,
+                    &rules,
+                ))
             } else {
-                minimax_bb(false, depth - 1, alpha, beta, ext)
+                minimax_bb(st, false, depth - 1, alpha, beta, ext)
             };
 
             // Unmake
-            G_AI_HAND ^= bit;
-            G_LEFT = saved_left;
-            G_RIGHT = saved_right;
-            G_HASH = saved_hash;
-            G_CONS_PASS = saved_cons_pass;
-            G_P1_WHO = saved_p1_who;
-            G_P1_L = saved_p1_l;
-            G_P1_R = saved_p1_r;
-            G_P1_TILE = saved_p1_tile;
-            G_P2_WHO = saved_p2_who;
-            G_P2_L = saved_p2_l;
-            G_P2_R = saved_p2_r;
-            G_PLY = saved_ply;
+            st.ai_hand ^= bit;
+            st.left = saved_left;
+            st.right = saved_right;
+            st.hash = saved_hash;
+            st.cons_pass = saved_cons_pass;
+            st.p1_who = saved_p1_who;
+            st.p1_l = saved_p1_l;
+            st.p1_r = saved_p1_r;
+            st.p1_tile = saved_p1_tile;
+            st.p2_who = saved_p2_who;
+            st.p2_l = saved_p2_l;
+            st.p2_r = saved_p2_r;
+            st.ply = saved_ply;
 
             if sc > best {
                 best = sc;
@@ -302,7 +502,7 @@ unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32,
         } else {
             TT_EXACT
         };
-        tt::tt_store(G_HASH, depth, tt_flag, best, best_move_idx, best_move_end);
+        tt::tt_store(st.hash, depth, tt_flag, best, best_move_idx, best_move_end);
         best
     } else {
         // === MINIMIZING ===
@@ -310,62 +510,63 @@ unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32,
         for i in 0..num_moves {
             let t_idx = MOVE_TILE_BUF[base + i] as usize;
             let end = MOVE_END_BUF[base + i];
-            let bit = 1i32 << t_idx;
-
-            G_HUMAN_HAND ^= bit;
-
-            let (new_l, new_r) = compute_new_ends(t_idx, end, saved_left, saved_right);
-            G_LEFT = new_l;
-            G_RIGHT = new_r;
-
-            G_HASH = saved_hash;
-            G_HASH ^= zobrist::tile_hash(t_idx, 1);
-            G_HASH ^= zobrist::left_hash(saved_left as usize);
-            G_HASH ^= zobrist::left_hash(new_l as usize);
-            G_HASH ^= zobrist::right_hash(saved_right as usize);
-            G_HASH ^= zobrist::right_hash(new_r as usize);
-            G_HASH ^= zobrist::side_hash();
-            if saved_cons_pass > 0 { G_HASH ^= zobrist::conspass_hash(1); }
-            G_CONS_PASS = 0;
-
-            G_P2_WHO = saved_p1_who;
-            G_P2_L = saved_p1_l;
-            G_P2_R = saved_p1_r;
-            G_P1_WHO = 0;
-            G_P1_L = new_l;
-            G_P1_R = new_r;
-            G_P1_TILE = t_idx as i8;
-
-            G_PLY = saved_ply + 1;
-
-            let sc = if G_HUMAN_HAND == 0 {
-                score_domino_bb(false, G_AI_HAND)
-            } else if count_moves_bb(G_AI_HAND, new_l, new_r) == 0
-                && count_moves_bb(G_HUMAN_HAND, new_l, new_r) == 0
+            let bit = S::Bits::bit(t_idx);
+
+            st.human_hand ^= bit;
+
+            let (new_l, new_r) = compute_new_ends::<S>(t_idx, end, saved_left, saved_right);
+            st.left = new_l;
+            st.right = new_r;
+
+            st.hash = saved_hash;
+            st.hash ^= zobrist::tile_hash::<S>(t_idx, 1);
+            st.hash ^= zobrist::left_hash::<S>(saved_left as usize);
+            st.hash ^= zobrist::left_hash::<S>(new_l as usize);
+            st.hash ^= zobrist::right_hash::<S>(saved_right as usize);
+            st.hash ^= zobrist::right_hash::<S>(new_r as usize);
+            st.hash ^= zobrist::side_hash::<S>();
+            if saved_cons_pass > 0 { st.hash ^= zobrist::conspass_hash::<S>(1); }
+            st.cons_pass = 0;
+
+            st.p2_who = saved_p1_who;
+            st.p2_l = saved_p1_l;
+            st.p2_r = saved_p1_r;
+            st.p1_who = 0;
+            st.p1_l = new_l;
+            st.p1_r = new_r;
+            st.p1_tile = t_idx as i8;
+
+            st.ply = saved_ply + 1;
+
+            let sc = if st.human_hand.is_zero() {
+                terminal_value(st, score_domino_bb::<S>(false, st.ai_hand, &rules))
+            } else if count_moves_bb::<S>(st.ai_hand, new_l, new_r) == 0
+                && count_moves_bb::<S>(st.human_hand, new_l, new_r) == 0
             {
-                score_block_bb(
-                    G_AI_HAND, G_HUMAN_HAND,
-                    G_P1_WHO, G_P1_L, G_P1_R, G_P1_TILE,
-                    G_P2_WHO, G_P2_L, G_P2_R,
-                )
+                terminal_value(st, score_block_bb::<S>(
+                    st.ai_hand, st.human_hand,
+                    st.p1_who, st.p1_l, st.p1_r, st.p1_tile,
+                    st.p2_who, st.p2_l, st.p2_r,
+                    &rules,
+                ))
             } else {
-                minimax_bb(true, depth - 1, alpha, beta, ext)
+                minimax_bb(st, true, depth - 1, alpha, beta, ext)
             };
 
             // Unmake
-            G_HUMAN_HAND ^= bit;
-            G_LEFT = saved_left;
-            G_RIGHT = saved_right;
-            G_HASH = saved_hash;
-            G_CONS_PASS = saved_cons_pass;
-            G_P1_WHO = saved_p1_who;
-            G_P1_L = saved_p1_l;
-            G_P1_R = saved_p1_r;
-            G_P1_TILE = saved_p1_tile;
-            G_P2_WHO = saved_p2_who;
-            G_P2_L = saved_p2_l;
-            G_P2_R = saved_p2_r;
-            G_PLY = saved_ply;
+            st.human_hand ^= bit;
+            st.left = saved_left;
+            st.right = saved_right;
+            st.hash = saved_hash;
+            st.cons_pass = saved_cons_pass;
+            st.p1_who = saved_p1_who;
+            st.p1_l = saved_p1_l;
+            st.p1_r = saved_p1_r;
+            st.p1_tile = saved_p1_tile;
+            st.p2_who = saved_p2_who;
+            st.p2_l = saved_p2_l;
+            st.p2_r = saved_p2_r;
+            st.ply = saved_ply;
 
             if sc < best {
                 best = sc;
@@ -387,20 +588,21 @@ unsafe fn minimax_bb(is_ai: bool, mut depth: i32, mut alpha: i32, mut beta: i32,
         } else {
             TT_EXACT
         };
-        tt::tt_store(G_HASH, depth, tt_flag, best, best_move_idx, best_move_end);
+        tt::tt_store(st.hash, depth, tt_flag, best, best_move_idx, best_move_end);
         best
     }
 }
 
 /// Compute new board ends after placing tile `t_idx` on `end` (0=left, 1=right).
 #[inline(always)]
-fn compute_new_ends(t_idx: usize, end: i8, left: i8, right: i8) -> (i8, i8) {
-    if left == 7 {
-        (TILE_LOW[t_idx], TILE_HIGH[t_idx])
+pub(crate) fn compute_new_ends<S: DominoSet>(t_idx: usize, end: i8, left: i8, right: i8) -> (i8, i8) {
+    let t = S::tables();
+    if left == S::EMPTY_END {
+        (t.low[t_idx], t.high[t_idx])
     } else if end == 0 {
-        (NEW_END_LEFT[t_idx * 8 + left as usize], right)
+        (t.new_end_left[t_idx * S::END_STRIDE + left as usize], right)
     } else {
-        (left, NEW_END_RIGHT[t_idx * 8 + right as usize])
+        (left, t.new_end_right[t_idx * S::END_STRIDE + right as usize])
     }
 }
 
@@ -420,6 +622,17 @@ fn compute_new_ends(t_idx: usize, end: i8, left: i8, right: i8) -> (i8, i8) {
 /// * `p1_who`, `p1_l`, `p1_r`, `p1_tile` — Last placer info
 /// * `p2_who`, `p2_l`, `p2_r` — Second-to-last placer info
 /// * `time_budget` — Time budget in ms (0 = use default)
+/// * `rules` — House rules for scoring and evaluation
+/// * `objective` — Maximize hand points, or match-win probability from the
+///   running score (scores are then equity in ±`EQUITY_SCALE`)
+/// * `limits` — Depth and node caps (`SearchLimits::NONE` for full strength)
+///
+/// The opening lead of a 14/14 deal comes from the `book` when it has the
+/// deal or the leader's suit profile (see `book::root_move` for when it
+/// applies). A deal entry reports full depth; a profile entry is not a
+/// solve and reports depth 0 with its mean score. Pass `SearchLimits::SOLVE`
+/// for a solve.
+#[allow(clippy::too_many_arguments)]
 pub fn choose_move(
     ai_hand: i32,
     human_hand: i32,
@@ -430,271 +643,455 @@ pub fn choose_move(
     p1_who: i8, p1_l: i8, p1_r: i8, p1_tile: i8,
     p2_who: i8, p2_l: i8, p2_r: i8,
     time_budget: f64,
+    rules: &RuleSet,
+    objective: Objective,
+    limits: &SearchLimits,
 ) -> SearchResult {
-    unsafe {
-        // Initialize global state
-        G_AI_HAND = ai_hand;
-        G_HUMAN_HAND = human_hand;
-        G_LEFT = left;
-        G_RIGHT = right;
-        G_PLY = 0;
-        G_CONS_PASS = cons_pass;
-        G_MATCH_DIFF = match_diff;
-
-        G_P1_WHO = p1_who;
-        G_P1_L = p1_l;
-        G_P1_R = p1_r;
-        G_P1_TILE = p1_tile;
-        G_P2_WHO = p2_who;
-        G_P2_L = p2_l;
-        G_P2_R = p2_r;
-
-        let total_tiles = popcount(ai_hand) + popcount(human_hand);
-        G_HASH = zobrist::compute_root_hash(ai_hand, human_hand, left, right, true, 0);
-
-        // Advance TT generation (reuse entries from prev searches)
-        tt::tt_new_generation();
-        clear_move_ordering_data();
-
-        TIME_START = now_ms();
-        let budget = if time_budget > 0.0 { time_budget } else { 20000.0 };
-
-        // Adaptive time budget
-        let move_budget = if total_tiles >= 24 {
-            budget * 2.0
-        } else if total_tiles >= 18 {
-            budget * 1.2
-        } else if total_tiles >= 12 {
-            budget
+    let _guard = lock_engine();
+    if let Some(e) = book::root_move(ai_hand, human_hand, left, cons_pass, rules, objective, limits) {
+        let score = e.score as i32;
+        return SearchResult {
+            best_tile_idx: e.tile,
+            best_end: 0,
+            best_score: score,
+            depth: if e.exact { ai_hand.count() + human_hand.count() } else { 0 },
+            nodes: 0,
+            placements_left: None,
+            analysis: vec![(e.tile, 0, score)],
+            pv: vec![(e.tile, 0)],
+            tt_probes: 0,
+            tt_hits: 0,
+            tt_cutoffs: 0,
+            tt_hints: 0,
+        };
+    }
+    let st = SearchState::<DoubleSix> {
+        ai_hand, human_hand, left, right, hash: 0, ply: 0, cons_pass,
+        p1_who, p1_l, p1_r, p1_tile, p2_who, p2_l, p2_r,
+    };
+    unsafe { search_root(st, match_diff, time_budget, rules, objective, limits) }
+}
+
+/// `choose_move` for any domino set: hands are `S::Bits` bitmasks and an
+/// empty board is `S::EMPTY_END`. There is no opening book for the larger
+/// sets, and `limits.root_moves` can only name double-six tile indices.
+#[allow(clippy::too_many_arguments)]
+pub fn choose_move_set<S: DominoSet>(
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
+    left: i8,
+    right: i8,
+    cons_pass: i32,
+    match_diff: i32,
+    p1_who: i8, p1_l: i8, p1_r: i8, p1_tile: i8,
+    p2_who: i8, p2_l: i8, p2_r: i8,
+    time_budget: f64,
+    rules: &RuleSet,
+    objective: Objective,
+    limits: &SearchLimits,
+) -> SearchResult {
+    debug_assert!(limits.root_moves == 0 || S::NUM_TILES <= 32);
+    let _guard = lock_engine();
+    let st = SearchState::<S> {
+        ai_hand, human_hand, left, right, hash: 0, ply: 0, cons_pass,
+        p1_who, p1_l, p1_r, p1_tile, p2_who, p2_l, p2_r,
+    };
+    unsafe { search_root(st, match_diff, time_budget, rules, objective, limits) }
+}
+
+/// Iterative deepening from the root position `st`.
+///
+/// # Safety
+/// Uses the global search buffers and TT; the caller holds the engine lock.
+unsafe fn search_root<S: DominoSet>(
+    mut st: SearchState<S>,
+    match_diff: i32,
+    time_budget: f64,
+    rules: &RuleSet,
+    objective: Objective,
+    limits: &SearchLimits,
+) -> SearchResult {
+    let st = &mut st;
+    let (ai_hand, human_hand, left, right) = (st.ai_hand, st.human_hand, st.left, st.right);
+    G_MATCH_DIFF = match_diff;
+    G_RULES = *rules;
+
+    // The equity table already accounts for the match score, so the
+    // ±target/2 evaluation heuristic is switched off in that mode.
+    G_EQUITY = false;
+    if let Objective::MatchEquity { ai_score, human_score } = objective {
+        G_EQUITY = true;
+        G_MATCH_DIFF = 0;
+        G_ROOT_EQUITY = equity::shared(rules).at(ai_score, human_score);
+    }
+
+    let total_tiles = ai_hand.count() + human_hand.count();
+    st.hash = zobrist::compute_root_hash::<S>(ai_hand, human_hand, left, right, true, 0);
+
+    // Advance TT generation (reuse entries from prev searches). Values
+    // from a search under the other objective are on a different scale,
+    // and ones scored under other rules, other evaluation parameters or for
+    // another set are wrong.
+    let params = eval::params();
+    if G_EQUITY || G_TT_EQUITY || G_TT_RULES != *rules || G_TT_SET != S::MAX_PIP
+        || G_TT_PARAMS != params || limits.fresh
+    {
+        tt::tt_clear();
+    }
+    G_TT_EQUITY = G_EQUITY;
+    G_TT_RULES = *rules;
+    G_TT_SET = S::MAX_PIP;
+    G_TT_PARAMS = params;
+    tt::tt_new_generation();
+    clear_move_ordering_data();
+
+    TIME_START = now_ms();
+    G_DEADLINE = DEADLINE.with(Cell::get);
+    let budget = if time_budget > 0.0 { time_budget } else { 20000.0 };
+
+    // Adaptive time budget; a node cap replaces the short-endgame cap so
+    // that node-capped searches give the same answer on any machine
+    let move_budget = if total_tiles >= 24 {
+        budget * 2.0
+    } else if total_tiles >= 18 {
+        budget * 1.2
+    } else if total_tiles >= 12 || limits.max_nodes > 0 {
+        budget
+    } else {
+        budget.min(1000.0)
+    };
+    TIME_BUDGET_MS = move_budget;
+
+    let mut best_tile_idx: i8 = -1;
+    let mut best_end: i8 = -1;
+    let mut prev_score: i32 = 0;
+    let mut last_depth: i32 = 0;
+    let mut last_nodes: u32 = 0;
+    let mut solved = false;
+    let mut committed_scores: Vec<(i8, i8, i32)> = Vec::new();
+
+    // Reset TT diagnostics for entire search
+    TT_PROBE_COUNT = 0;
+    TT_HIT_COUNT = 0;
+    TT_CUTOFF_COUNT = 0;
+    TT_HINT_COUNT = 0;
+
+    let max_depth = if limits.max_depth > 0 { limits.max_depth.min(50) } else { 50 };
+    let mut nodes_used: u32 = 0;
+
+    // Iterative deepening
+    for iter_depth in 1..=max_depth {
+        NODE_COUNT = 0;
+        G_NODE_LIMIT = if limits.max_nodes > 0 {
+            NODE_LIMIT.min(limits.max_nodes - nodes_used)
         } else {
-            budget.min(1000.0)
+            NODE_LIMIT
         };
-        TIME_BUDGET_MS = move_budget;
-
-        let mut best_tile_idx: i8 = -1;
-        let mut best_end: i8 = -1;
-        let mut prev_score: i32 = 0;
-        let mut last_depth: i32 = 0;
-        let mut last_nodes: u32 = 0;
-        let mut committed_scores: Vec<(i8, i8, i32)> = Vec::new();
-
-        // Reset TT diagnostics for entire search
-        TT_PROBE_COUNT = 0;
-        TT_HIT_COUNT = 0;
-        TT_CUTOFF_COUNT = 0;
-        TT_HINT_COUNT = 0;
-
-        // Iterative deepening
-        for iter_depth in 1..=50 {
-            NODE_COUNT = 0;
-
-            let num_moves = generate_moves(G_AI_HAND, G_LEFT, G_RIGHT, 0);
-
-            if num_moves > 2 {
-                order_moves_at_ply(0, num_moves, true, iter_depth,
-                                  G_AI_HAND, G_HUMAN_HAND, G_LEFT, G_RIGHT);
-            }
 
-            // TT PV move to front
-            let pv_hit = tt::tt_probe(G_HASH, 0, -100000, 100000);
-            if let Some(ref hit) = pv_hit {
-                if hit.best_idx >= 0 {
-                    for mi in 1..num_moves {
-                        if MOVE_TILE_BUF[mi] == hit.best_idx
-                            && MOVE_END_BUF[mi] == hit.best_end
-                        {
-                            let tmp_t = MOVE_TILE_BUF[0];
-                            let tmp_e = MOVE_END_BUF[0];
-                            MOVE_TILE_BUF[0] = MOVE_TILE_BUF[mi];
-                            MOVE_END_BUF[0] = MOVE_END_BUF[mi];
-                            MOVE_TILE_BUF[mi] = tmp_t;
-                            MOVE_END_BUF[mi] = tmp_e;
-                            break;
-                        }
-                    }
+        let mut num_moves = generate_moves::<S>(st.ai_hand, st.left, st.right, 0);
+        if limits.root_moves != 0 {
+            let mut kept = 0;
+            for mi in 0..num_moves {
+                let (t, e) = (MOVE_TILE_BUF[mi], MOVE_END_BUF[mi]);
+                if limits.root_moves & SearchLimits::root_move_bit(t, e) != 0 {
+                    MOVE_TILE_BUF[kept] = t;
+                    MOVE_END_BUF[kept] = e;
+                    kept += 1;
                 }
             }
+            num_moves = kept;
+        }
 
-            // Aspiration window
-            let asp_window = if iter_depth >= 6 { 15 } else { 30 };
-            let (mut alpha_w, mut beta_w) = if iter_depth <= 1 {
-                (-100000, 100000)
-            } else {
-                (prev_score - asp_window, prev_score + asp_window)
-            };
+        if num_moves > 2 {
+            order_moves_at_ply::<S>(0, num_moves, true, iter_depth,
+                              st.ai_hand, st.human_hand, st.left, st.right);
+        }
 
-            let mut iter_best_score: i32 = -100000;
-            let mut iter_best_tile_idx: i8 = -1;
-            let mut iter_best_end: i8 = -1;
-            let mut iter_complete = true;
-            let mut root_scores: Vec<(i8, i8, i32)> = Vec::new();
-
-            for _asp_retry in 0..3 {
-                iter_best_score = -100000;
-                iter_best_tile_idx = -1;
-                iter_best_end = -1;
-                iter_complete = true;
-                root_scores.clear();
-                let mut cur_alpha = alpha_w;
-
-                let root_ai_hand = G_AI_HAND;
-                let root_hash = G_HASH;
-
-                for i in 0..num_moves {
-                    let t_idx = MOVE_TILE_BUF[i] as usize;
-                    let end = MOVE_END_BUF[i];
-                    let bit = 1i32 << t_idx;
-
-                    G_AI_HAND = root_ai_hand ^ bit;
-
-                    let (new_l, new_r) = compute_new_ends(t_idx, end, G_LEFT, G_RIGHT);
-                    let saved_root_left = G_LEFT;
-                    let saved_root_right = G_RIGHT;
-                    G_LEFT = new_l;
-                    G_RIGHT = new_r;
-
-                    G_HASH = root_hash;
-                    G_HASH ^= zobrist::tile_hash(t_idx, 0);
-                    G_HASH ^= zobrist::left_hash(saved_root_left as usize);
-                    G_HASH ^= zobrist::left_hash(new_l as usize);
-                    G_HASH ^= zobrist::right_hash(saved_root_right as usize);
-                    G_HASH ^= zobrist::right_hash(new_r as usize);
-                    G_HASH ^= zobrist::side_hash();
-
-                    G_CONS_PASS = 0;
-
-                    let saved_rp1_who = G_P1_WHO;
-                    let saved_rp1_l = G_P1_L;
-                    let saved_rp1_r = G_P1_R;
-                    let saved_rp1_tile = G_P1_TILE;
-                    let saved_rp2_who = G_P2_WHO;
-                    let saved_rp2_l = G_P2_L;
-                    let saved_rp2_r = G_P2_R;
-
-                    G_P2_WHO = G_P1_WHO;
-                    G_P2_L = G_P1_L;
-                    G_P2_R = G_P1_R;
-                    G_P1_WHO = 1;
-                    G_P1_L = new_l;
-                    G_P1_R = new_r;
-                    G_P1_TILE = t_idx as i8;
-
-                    G_PLY = 1;
-
-                    let score = if G_AI_HAND == 0 {
-                        score_domino_bb(true, G_HUMAN_HAND)
-                    } else if count_moves_bb(G_HUMAN_HAND, new_l, new_r) == 0
-                        && count_moves_bb(G_AI_HAND, new_l, new_r) == 0
+        // TT PV move to front
+        let pv_hit = tt::tt_probe(st.hash, 0, -100000, 100000);
+        if let Some(ref hit) = pv_hit {
+            if hit.best_idx >= 0 {
+                for mi in 1..num_moves {
+                    if MOVE_TILE_BUF[mi] == hit.best_idx
+                        && MOVE_END_BUF[mi] == hit.best_end
                     {
-                        score_block_bb(
-                            G_AI_HAND, G_HUMAN_HAND,
-                            G_P1_WHO, G_P1_L, G_P1_R, G_P1_TILE,
-                            G_P2_WHO, G_P2_L, G_P2_R,
-                        )
-                    } else if i == 0 {
-                        // Full window for first move
-                        minimax_bb(false, iter_depth - 1, cur_alpha, beta_w, 0)
-                    } else {
-                        // PVS: null window first
-                        let mut sc = minimax_bb(false, iter_depth - 1, cur_alpha, cur_alpha + 1, 0);
-                        if sc > cur_alpha && sc < beta_w {
-                            sc = minimax_bb(false, iter_depth - 1, cur_alpha, beta_w, 0);
-                        }
-                        sc
-                    };
-
-                    // Unmake root
-                    G_AI_HAND = root_ai_hand;
-                    G_LEFT = saved_root_left;
-                    G_RIGHT = saved_root_right;
-                    G_HASH = root_hash;
-                    G_P1_WHO = saved_rp1_who;
-                    G_P1_L = saved_rp1_l;
-                    G_P1_R = saved_rp1_r;
-                    G_P1_TILE = saved_rp1_tile;
-                    G_P2_WHO = saved_rp2_who;
-                    G_P2_L = saved_rp2_l;
-                    G_P2_R = saved_rp2_r;
-                    G_PLY = 0;
-                    G_CONS_PASS = 0;
-
-                    root_scores.push((t_idx as i8, end, score));
-
-                    if score > iter_best_score {
-                        iter_best_score = score;
-                        iter_best_tile_idx = t_idx as i8;
-                        iter_best_end = end;
-                    }
-                    if score > cur_alpha {
-                        cur_alpha = score;
-                    }
-
-                    if NODE_COUNT >= NODE_LIMIT {
-                        iter_complete = false;
+                        let tmp_t = MOVE_TILE_BUF[0];
+                        let tmp_e = MOVE_END_BUF[0];
+                        MOVE_TILE_BUF[0] = MOVE_TILE_BUF[mi];
+                        MOVE_END_BUF[0] = MOVE_END_BUF[mi];
+                        MOVE_TILE_BUF[mi] = tmp_t;
+                        MOVE_END_BUF[mi] = tmp_e;
                         break;
                     }
                 }
+            }
+        }
+
+        // Aspiration window
+        let asp_window = if iter_depth >= 6 { 15 * SCORE_SCALE } else { 30 * SCORE_SCALE };
+        let (mut alpha_w, mut beta_w) = if iter_depth <= 1 {
+            (-100000, 100000)
+        } else {
+            (prev_score - asp_window, prev_score + asp_window)
+        };
 
-                // Aspiration re-search
-                if iter_complete && iter_best_score <= alpha_w {
-                    alpha_w = -100000;
-                    continue;
+        let mut iter_best_score: i32 = -100000;
+        let mut iter_best_tile_idx: i8 = -1;
+        let mut iter_best_end: i8 = -1;
+        let mut iter_complete = true;
+        let mut root_scores: Vec<(i8, i8, i32)> = Vec::new();
+
+        for _asp_retry in 0..3 {
+            iter_best_score = -100000;
+            iter_best_tile_idx = -1;
+            iter_best_end = -1;
+            iter_complete = true;
+            root_scores.clear();
+            let mut cur_alpha = alpha_w;
+
+            let root_ai_hand = st.ai_hand;
+            let root_hash = st.hash;
+
+            for i in 0..num_moves {
+                let t_idx = MOVE_TILE_BUF[i] as usize;
+                let end = MOVE_END_BUF[i];
+                let bit = S::Bits::bit(t_idx);
+
+                st.ai_hand = root_ai_hand ^ bit;
+
+                let (new_l, new_r) = compute_new_ends::<S>(t_idx, end, st.left, st.right);
+                let saved_root_left = st.left;
+                let saved_root_right = st.right;
+                st.left = new_l;
+                st.right = new_r;
+
+                st.hash = root_hash;
+                st.hash ^= zobrist::tile_hash::<S>(t_idx, 0);
+                st.hash ^= zobrist::left_hash::<S>(saved_root_left as usize);
+                st.hash ^= zobrist::left_hash::<S>(new_l as usize);
+                st.hash ^= zobrist::right_hash::<S>(saved_root_right as usize);
+                st.hash ^= zobrist::right_hash::<S>(new_r as usize);
+                st.hash ^= zobrist::side_hash::<S>();
+
+                st.cons_pass = 0;
+
+                let saved_rp1_who = st.p1_who;
+                let saved_rp1_l = st.p1_l;
+                let saved_rp1_r = st.p1_r;
+                let saved_rp1_tile = st.p1_tile;
+                let saved_rp2_who = st.p2_who;
+                let saved_rp2_l = st.p2_l;
+                let saved_rp2_r = st.p2_r;
+
+                st.p2_who = st.p1_who;
+                st.p2_l = st.p1_l;
+                st.p2_r = st.p1_r;
+                st.p1_who = 1;
+                st.p1_l = new_l;
+                st.p1_r = new_r;
+                st.p1_tile = t_idx as i8;
+
+                st.ply = 1;
+
+                let score = if st.ai_hand.is_zero() {
+                    terminal_value(st, score_domino_bb::<S>(true, st.human_hand, rules))
+                } else if count_moves_bb::<S>(st.human_hand, new_l, new_r) == 0
+                    && count_moves_bb::<S>(st.ai_hand, new_l, new_r) == 0
+                {
+                    terminal_value(st, score_block_bb::<S>(
+                        st.ai_hand, st.human_hand,
+                        st.p1_who, st.p1_l, st.p1_r, st.p1_tile,
+                        st.p2_who, st.p2_l, st.p2_r,
+                        rules,
+                    ))
+                } else if i == 0 {
+                    // Full window for first move
+                    minimax_bb(st, false, iter_depth - 1, cur_alpha, beta_w, 0)
+                } else {
+                    // PVS: null window first
+                    let mut sc = minimax_bb(st, false, iter_depth - 1, cur_alpha, cur_alpha + 1, 0);
+                    if sc > cur_alpha && sc < beta_w {
+                        sc = minimax_bb(st, false, iter_depth - 1, cur_alpha, beta_w, 0);
+                    }
+                    sc
+                };
+
+                // Unmake root
+                st.ai_hand = root_ai_hand;
+                st.left = saved_root_left;
+                st.right = saved_root_right;
+                st.hash = root_hash;
+                st.p1_who = saved_rp1_who;
+                st.p1_l = saved_rp1_l;
+                st.p1_r = saved_rp1_r;
+                st.p1_tile = saved_rp1_tile;
+                st.p2_who = saved_rp2_who;
+                st.p2_l = saved_rp2_l;
+                st.p2_r = saved_rp2_r;
+                st.ply = 0;
+                st.cons_pass = 0;
+
+                root_scores.push((t_idx as i8, end, score));
+
+                if score > iter_best_score {
+                    iter_best_score = score;
+                    iter_best_tile_idx = t_idx as i8;
+                    iter_best_end = end;
                 }
-                if iter_complete && iter_best_score >= beta_w {
-                    beta_w = 100000;
-                    continue;
+                if score > cur_alpha {
+                    cur_alpha = score;
+                }
+
+                if NODE_COUNT >= G_NODE_LIMIT {
+                    iter_complete = false;
+                    break;
                 }
-                break;
             }
 
-            // Update best result
-            if iter_best_tile_idx >= 0 {
-                if iter_complete {
+            // Aspiration re-search
+            if iter_complete && iter_best_score <= alpha_w {
+                alpha_w = -100000;
+                continue;
+            }
+            if iter_complete && iter_best_score >= beta_w {
+                beta_w = 100000;
+                continue;
+            }
+            break;
+        }
+
+        // Update best result
+        if iter_best_tile_idx >= 0 {
+            if iter_complete {
+                best_tile_idx = iter_best_tile_idx;
+                best_end = iter_best_end;
+                prev_score = iter_best_score;
+                last_depth = iter_depth;
+                last_nodes = NODE_COUNT;
+                committed_scores = root_scores;
+                solved = NODE_COUNT < G_NODE_LIMIT && iter_depth >= total_tiles;
+            } else {
+                // Incomplete: only update if same move or clearly winning
+                // (worth the match on its own, or 95% match equity), or
+                // nothing has completed yet under a node cap
+                let clearly_winning = if G_EQUITY { 900 } else { rules.target_score } * SCORE_SCALE;
+                if best_tile_idx < 0 || iter_best_tile_idx == best_tile_idx
+                    || iter_best_score > clearly_winning
+                {
                     best_tile_idx = iter_best_tile_idx;
                     best_end = iter_best_end;
-                    prev_score = iter_best_score;
-                    last_depth = iter_depth;
-                    last_nodes = NODE_COUNT;
-                    committed_scores = root_scores;
-                } else {
-                    // Incomplete: only update if same move or clearly winning
-                    if iter_best_tile_idx == best_tile_idx || iter_best_score > 500 {
-                        best_tile_idx = iter_best_tile_idx;
-                        best_end = iter_best_end;
-                    }
                 }
             }
+        }
 
-            if iter_complete && iter_best_tile_idx >= 0 {
-                tt::tt_store(G_HASH, iter_depth, TT_EXACT, iter_best_score,
+        if iter_complete && iter_best_tile_idx >= 0 {
+            // A restricted root is not the position's value
+            if limits.root_moves == 0 {
+                tt::tt_store(st.hash, iter_depth, TT_EXACT, iter_best_score,
                             iter_best_tile_idx, iter_best_end);
             }
+            if let Some(hook) = G_INFO_HOOK {
+                hook(&IterationInfo {
+                    depth: iter_depth,
+                    score: decode_score(iter_best_score),
+                    best_tile_idx: iter_best_tile_idx,
+                    best_end: iter_best_end,
+                    nodes: NODE_COUNT,
+                    elapsed_ms: (now_ms() - TIME_START) as u32,
+                });
+            }
+        }
+
+        // Full solve achieved
+        if iter_complete && NODE_COUNT < G_NODE_LIMIT && iter_depth >= total_tiles {
+            break;
+        }
+
+        nodes_used = nodes_used.saturating_add(NODE_COUNT);
+        if limits.max_nodes > 0 && nodes_used >= limits.max_nodes {
+            break;
+        }
+        if stop_requested() {
+            break;
+        }
+
+        // Time check
+        let elapsed = now_ms() - TIME_START;
+        if elapsed > move_budget * 0.75 {
+            break;
+        }
+    }
+
+    SearchResult {
+        best_tile_idx,
+        best_end,
+        best_score: decode_score(prev_score),
+        depth: last_depth,
+        nodes: last_nodes,
+        placements_left: if solved {
+            decode_tiles_left(prev_score)
+                .filter(|&left| left < SCORE_SCALE - 1)
+                .map(|left| total_tiles - left)
+        } else {
+            None
+        },
+        analysis: committed_scores.into_iter().map(|(t, e, sc)| (t, e, decode_score(sc))).collect(),
+        pv: if best_tile_idx >= 0 { principal_variation(st, best_tile_idx, best_end) } else { Vec::new() },
+        tt_probes: TT_PROBE_COUNT,
+        tt_hits: TT_HIT_COUNT,
+        tt_cutoffs: TT_CUTOFF_COUNT,
+        tt_hints: TT_HINT_COUNT,
+    }
+}
 
-            // Full solve achieved
-            if iter_complete && NODE_COUNT < NODE_LIMIT && iter_depth >= total_tiles {
+/// Follow TT best moves from the root `st`, starting with `tile_idx`/`end`
+/// for the AI. Makes moves on copies of the state, hashed the way
+/// `minimax_bb` hashes them; stops at the end of the hand, on a TT miss, or
+/// on a stored move that is not legal here.
+unsafe fn principal_variation<S: DominoSet>(st: &SearchState<S>, tile_idx: i8, end: i8) -> Vec<(i8, i8)> {
+    let mut hands = [st.ai_hand, st.human_hand];
+    let (mut left, mut right) = (st.left, st.right);
+    let mut hash = st.hash;
+    let mut cons_pass = st.cons_pass;
+    let mut side = 0;
+    let mut next = Some((tile_idx, end));
+    let mut pv = Vec::new();
+    let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+    while pv.len() < 2 * S::NUM_TILES {
+        let n = collect_moves::<S>(hands[side], left, right, &mut buf);
+        if n == 0 {
+            if cons_pass > 0 {
                 break;
             }
-
-            // Time check
-            let elapsed = now_ms() - TIME_START;
-            if elapsed > move_budget * 0.75 {
+            hash ^= zobrist::side_hash::<S>() ^ zobrist::conspass_hash::<S>(1);
+            cons_pass = 1;
+            pv.push((-1, -1));
+        } else {
+            let Some((t, e)) = next.filter(|m| buf[..n].contains(m)) else { break };
+            let (new_l, new_r) = compute_new_ends::<S>(t as usize, e, left, right);
+            hands[side] ^= S::Bits::bit(t as usize);
+            hash ^= zobrist::tile_hash::<S>(t as usize, side)
+                ^ zobrist::left_hash::<S>(left as usize) ^ zobrist::left_hash::<S>(new_l as usize)
+                ^ zobrist::right_hash::<S>(right as usize) ^ zobrist::right_hash::<S>(new_r as usize)
+                ^ zobrist::side_hash::<S>();
+            if cons_pass > 0 {
+                hash ^= zobrist::conspass_hash::<S>(1);
+            }
+            cons_pass = 0;
+            (left, right) = (new_l, new_r);
+            pv.push((t, e));
+            if hands[side].is_zero()
+                || count_moves_bb::<S>(hands[0], left, right) + count_moves_bb::<S>(hands[1], left, right) == 0
+            {
                 break;
             }
         }
-
-        SearchResult {
-            best_tile_idx,
-            best_end,
-            best_score: prev_score,
-            depth: last_depth,
-            nodes: last_nodes,
-            analysis: committed_scores,
-            tt_probes: TT_PROBE_COUNT,
-            tt_hits: TT_HIT_COUNT,
-            tt_cutoffs: TT_CUTOFF_COUNT,
-            tt_hints: TT_HINT_COUNT,
-        }
+        side = 1 - side;
+        next = tt::tt_probe(hash, 0, -100000, 100000)
+            .filter(|hit| hit.best_idx >= 0)
+            .map(|hit| (hit.best_idx, hit.best_end));
     }
+    pv
 }
 
 #[cfg(test)]
@@ -703,6 +1100,51 @@ mod tests {
     #[allow(unused_imports)]
     use crate::lookup::TILE_ID_MAP;
 
+    #[test]
+    fn test_terminal_encoding() {
+        // Points decide; then the winner wants more tiles left, the loser fewer
+        assert!(encode_terminal(10, 0) > encode_terminal(9, 20));
+        assert!(encode_terminal(10, 12) > encode_terminal(10, 4));
+        assert!(encode_terminal(-10, 4) > encode_terminal(-10, 12));
+        assert!(encode_terminal(-9, 20) > encode_terminal(-10, 0));
+        for (value, left) in [(23, 5), (-23, 5), (1, 27), (-1000, 27), (1000, 0)] {
+            let v = encode_terminal(value, left);
+            assert!(v.abs() <= i16::MAX as i32);
+            assert_eq!((decode_score(v), decode_tiles_left(v)), (value, Some(left)));
+        }
+        assert_eq!(decode_tiles_left(encode_terminal(0, 9)), None);
+
+        // AI: [6-6] [0-1], human: [5-6], empty board. [6-6] lets the human
+        // go out on the next tile for -1; [0-1] locks at once for -23.
+        let (t66, t01, t56) = (27, 1, 26);
+        let r = choose_move(1 << t66 | 1 << t01, 1 << t56, 7, 7, 0, 0, -1, 0, 0, -1, -1, 0, 0,
+                            500.0, &RuleSet::STANDARD, Objective::Points, &SearchLimits::NONE);
+        assert_eq!((r.best_tile_idx, r.best_score, r.placements_left), (t66, -1, Some(2)));
+        assert!(r.analysis.contains(&(t01, 0, -23)));
+    }
+
+    #[test]
+    fn test_equal_points_prefer_faster_win_and_slower_loss() {
+        use crate::lookup::tile_id_to_index;
+        use crate::notation::Position;
+
+        // AI to move: [0-3] left, [3-3] left and [0-3] right all win 36,
+        // but only [0-3] left goes out at once
+        let s = Position::parse("222444/03121433 3:0 a 0 h35@30,a@50").unwrap().state;
+        let r = s.search(s.to_move, 1000.0, &RuleSet::STANDARD, Objective::Points, 0);
+        assert_eq!((r.best_tile_idx, r.best_end, r.best_score, r.placements_left),
+                   (tile_id_to_index(0, 3) as i8, 0, 36, Some(1)));
+        assert!(r.analysis.contains(&(tile_id_to_index(3, 3) as i8, 0, 36)));
+
+        // Human to move: [3-4] left and [0-0] right both lose 1; [0-0]
+        // makes the AI place one more tile before it goes out
+        let s = Position::parse("000134/14 3:0 h 0 a02@30,h@32").unwrap().state;
+        let r = s.search(s.to_move, 1000.0, &RuleSet::STANDARD, Objective::Points, 0);
+        assert_eq!((r.best_tile_idx, r.best_end, r.best_score, r.placements_left),
+                   (tile_id_to_index(0, 0) as i8, 1, -1, Some(3)));
+        assert!(r.analysis.contains(&(tile_id_to_index(3, 4) as i8, 0, -1)));
+    }
+
     #[test]
     fn test_choose_move_simple() {
         // AI has 2 tiles, human has 2 tiles, board has ends
@@ -718,6 +1160,9 @@ mod tests {
             -1, 0, 0, -1,
             -1, 0, 0,
             1000.0,
+            &RuleSet::STANDARD,
+            Objective::Points,
+            &SearchLimits::NONE,
         );
 
         assert!(result.best_tile_idx >= 0);
@@ -744,8 +1189,8 @@ mod tests {
             human_hand |= 1 << tile_id_to_index(lo, hi);
         }
 
-        eprintln!("AI hand: 0x{:08X} (popcount={})", ai_hand, popcount(ai_hand));
-        eprintln!("Human hand: 0x{:08X} (popcount={})", human_hand, popcount(human_hand));
+        eprintln!("AI hand: 0x{:08X} (popcount={})", ai_hand, ai_hand.count());
+        eprintln!("Human hand: 0x{:08X} (popcount={})", human_hand, human_hand.count());
 
         let result = choose_move(
             ai_hand, human_hand,
@@ -754,6 +1199,10 @@ mod tests {
             -1, 0, 0, -1, // p1
             -1, 0, 0,     // p2
             20000.0,       // 20s budget (matches browser default)
+            &RuleSet::STANDARD,
+            Objective::Points,
+            // The book has no solve of this deal, and SOLVE skips its profiles
+            &SearchLimits::SOLVE,
         );
 
         eprintln!("\n=== WASM (Rust native) Search Results ===");
@@ -807,10 +1256,169 @@ mod tests {
             -1, 0, 0, -1,
             -1, 0, 0,
             1000.0,
+            &RuleSet::STANDARD,
+            Objective::Points,
+            &SearchLimits::NONE,
         );
 
         assert_eq!(result.best_tile_idx, 1); // tile (0,1)
         assert_eq!(result.best_end, 0); // left end (matches 0)
         assert!(result.best_score > 0); // winning
     }
+
+    #[test]
+    fn test_choose_move_match_equity() {
+        // Same domino win as above ([6-6] left = 12 points), scored as equity
+        let ai_hand = 1 << 1;
+        let human_hand = 1 << 27;
+        let search = |ai_score, human_score| choose_move(
+            ai_hand, human_hand,
+            0, 3, 0, 0,
+            -1, 0, 0, -1,
+            -1, 0, 0,
+            1000.0,
+            &RuleSet::STANDARD,
+            Objective::MatchEquity { ai_score, human_score },
+            &SearchLimits::NONE,
+        );
+
+        // 12 points finishes the match from 95
+        let closing = search(95, 80);
+        assert_eq!(closing.best_tile_idx, 1);
+        assert_eq!(closing.best_score, equity::EQUITY_SCALE);
+
+        // From 0-0 the same hand is only a partial edge
+        let opening = search(0, 0);
+        assert!(opening.best_score > 0 && opening.best_score < equity::EQUITY_SCALE);
+
+        // Back to points: the TT must not leak equity-scaled values
+        let points = choose_move(
+            ai_hand, human_hand,
+            0, 3, 0, 0,
+            -1, 0, 0, -1,
+            -1, 0, 0,
+            1000.0,
+            &RuleSet::STANDARD,
+            Objective::Points,
+            &SearchLimits::NONE,
+        );
+        assert_eq!(points.best_score, 12);
+    }
+
+    #[test]
+    fn test_deadline_stops_search() {
+        use crate::game::HandState;
+
+        // A full deal after the lead: far from solved within the budget
+        let [human, ai] = crate::deal::deal(7);
+        let mut state = HandState::new(human, ai, 1);
+        let mut buf = [(0i8, 0i8); crate::lookup::NUM_TILES];
+        state.legal_moves(&mut buf);
+        state.place(buf[0].0, buf[0].1);
+
+        set_deadline(Some(now_ms()));
+        let start = now_ms();
+        let r = state.search(0, 60_000.0, &RuleSet::STANDARD, Objective::Points, 0);
+        set_deadline(None);
+        assert!(now_ms() - start < 5_000.0);
+        assert!(r.best_tile_idx >= 0);
+
+        // Other threads' searches are unaffected
+        let r = std::thread::spawn(move || {
+            state.search_limited(0, 60_000.0, &RuleSet::STANDARD, Objective::Points, 0,
+                                 &SearchLimits { max_nodes: 50_000, fresh: true, ..SearchLimits::NONE })
+        }).join().unwrap();
+        assert!(r.depth >= 1);
+    }
+
+    #[test]
+    fn test_eval_params_change_clears_tt() {
+        use crate::game::HandState;
+
+        // A dealt hand after the lead, node-capped so the evaluation decides
+        let [human, ai] = crate::deal::deal(11);
+        let mut state = HandState::new(human, ai, 1);
+        let mut buf = [(0i8, 0i8); crate::lookup::NUM_TILES];
+        state.legal_moves(&mut buf);
+        state.place(buf[0].0, buf[0].1);
+        let search = |fresh: bool| unsafe {
+            let st = SearchState::<DoubleSix> {
+                ai_hand: state.hands[0], human_hand: state.hands[1], left: state.left, right: state.right,
+                hash: 0, ply: 0, cons_pass: 0,
+                p1_who: 0, p1_l: state.left, p1_r: state.right, p1_tile: buf[0].0, p2_who: -1, p2_l: 0, p2_r: 0,
+            };
+            let limits = SearchLimits { max_nodes: 100_000, fresh, ..SearchLimits::NONE };
+            let r = search_root(st, 0, 60_000.0, &RuleSet::STANDARD, Objective::Points, &limits);
+            (r.best_tile_idx, r.best_score, r.nodes, r.analysis)
+        };
+
+        // Held throughout, so no other test searches under the skewed params
+        let _guard = lock_engine();
+        let mut skewed = EvalParams::DEFAULT;
+        for (_, v) in skewed.fields_mut() {
+            *v = -3.0 * *v + 1.0;
+        }
+        unsafe {
+            eval::set_params_locked(&skewed);
+            search(true);
+            eval::set_params_locked(&EvalParams::DEFAULT);
+        }
+        // Nothing scored under `skewed` survives into this search
+        let after = search(false);
+        assert_eq!(after, search(true));
+    }
+
+    #[test]
+    fn test_choose_move_larger_sets() {
+        use crate::lookup::tile_index;
+        use crate::movegen::collect_moves;
+        use crate::set::{bits, DoubleNine, DoubleTwelve};
+        use crate::zobrist::Xorshift32;
+
+        // The 3-tile fixture one set up: the top double lets the human out
+        // for -1, [0-1] locks with the AI heavier (all pips to the human)
+        fn fixture<S: DominoSet>() -> SearchResult {
+            let n = S::MAX_PIP;
+            let t = |a, b| S::Bits::bit(tile_index::<S>(a, b).unwrap());
+            choose_move_set::<S>(t(n, n) | t(0, 1), t(n - 1, n), S::EMPTY_END, S::EMPTY_END, 0, 0,
+                                 -1, 0, 0, -1, -1, 0, 0, 500.0, &RuleSet::STANDARD, Objective::Points,
+                                 &SearchLimits::NONE)
+        }
+        let r = fixture::<DoubleNine>();
+        assert_eq!((r.best_tile_idx as usize, r.best_score, r.placements_left), (DoubleNine::NUM_TILES - 1, -1, Some(2)));
+        assert!(r.analysis.contains(&(1, 0, -(18 + 17))));
+        let r = fixture::<DoubleTwelve>();
+        assert_eq!((r.best_tile_idx as usize, r.best_score), (DoubleTwelve::NUM_TILES - 1, -1));
+        assert!(r.analysis.contains(&(1, 0, -(24 + 23))));
+
+        // Double-six through the generic entry point is the engine the game
+        // plays (no book on a mid-hand position)
+        let ai = (1 << 3) | (1 << 9) | (1 << 15) | (1 << 22);
+        let human = (1 << 5) | (1 << 12) | (1 << 20) | (1 << 26);
+        let a = choose_move(ai, human, 3, 5, 0, 0, -1, 0, 0, -1, -1, 0, 0, 1000.0,
+                            &RuleSet::STANDARD, Objective::Points, &SearchLimits::NONE);
+        let b = choose_move_set::<DoubleSix>(ai, human, 3, 5, 0, 0, -1, 0, 0, -1, -1, 0, 0, 1000.0,
+                                             &RuleSet::STANDARD, Objective::Points, &SearchLimits::NONE);
+        assert_eq!((a.best_tile_idx, a.best_end, a.best_score), (b.best_tile_idx, b.best_end, b.best_score));
+        assert_eq!(a.analysis, b.analysis);
+
+        // A node-capped double-nine deal: 10 tiles each from a shuffled set
+        let mut rng = Xorshift32::new(9);
+        let mut tiles: Vec<usize> = (0..DoubleNine::NUM_TILES).collect();
+        for i in (1..tiles.len()).rev() {
+            tiles.swap(i, rng.next() as usize % (i + 1));
+        }
+        let hand = |ts: &[usize]| ts.iter().fold(0u64, |h, &t| h | 1 << t);
+        let (ai, human) = (hand(&tiles[..10]), hand(&tiles[10..20]));
+        let limits = SearchLimits { max_nodes: 200_000, fresh: true, ..SearchLimits::NONE };
+        let r = choose_move_set::<DoubleNine>(ai, human, DoubleNine::EMPTY_END, DoubleNine::EMPTY_END, 0, 0,
+                                              -1, 0, 0, -1, -1, 0, 0, 1e9, &RuleSet::STANDARD,
+                                              Objective::Points, &limits);
+        let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+        let n = collect_moves::<DoubleNine>(ai, DoubleNine::EMPTY_END, DoubleNine::EMPTY_END, &mut buf);
+        assert_eq!(n, bits(ai).count());
+        assert!(buf[..n].contains(&(r.best_tile_idx, r.best_end)));
+        assert!(r.depth >= 4);
+        assert_eq!(r.analysis.len(), n);
+    }
 }
@@ -0,0 +1,323 @@
+//! Local HTTP/JSON server (feature `server`) for tools that run outside the
+//! browser. Every endpoint takes a `POST` with the `SearchInput` JSON that
+//! `wasm_choose_move` accepts (or a bare `notation` position) and answers
+//! with `SearchOutput` JSON:
+//!
+//! - `/choose-move`: `wasm_choose_move`, budget capped at the request timeout
+//! - `/analyze`: full strength, every root move with its evaluation breakdown
+//! - `/solve`: full strength with the whole request timeout as budget and
+//!   no opening book; the result is exact when `depth` covers every tile
+//!   left
+//! - `/legal-moves`: no search; `analysis` lists the side to move's legal
+//!   moves with score 0 (empty when it must pass)
+//! - `/adjudicate`: value of the position for the side to move under best
+//!   play (`bestScore`), including finished hands and forced passes; no move
+//!
+//! Errors come back as `{"error": "..."}` with a 4xx/5xx status: 400 for bad
+//! input, 500 if the request's worker fails, 503 when `max_concurrent`
+//! requests are already running, 504 when a request outlives its timeout.
+//! The engine itself is single-threaded, so concurrent requests queue for
+//! it; the timeout counts that wait, and a search still running at the
+//! timeout stops there instead of holding the engine.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::game::HandState;
+use crate::notation::Position;
+use crate::search::{self, SearchLimits};
+use crate::{lookup, parse_search_input, run_choose_move, AnalysisEntry, RulesDesc, SearchInput, SearchOutput};
+
+/// Largest request body accepted
+const MAX_BODY: usize = 1 << 20;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Listen address; keep it on localhost, there is no authentication
+    pub addr: String,
+    /// Per-request limit in ms, from accept to reply
+    pub timeout_ms: u64,
+    /// Requests allowed to run (or wait for the engine) at once
+    pub max_concurrent: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { addr: "127.0.0.1:8765".to_string(), timeout_ms: 30_000, max_concurrent: 4 }
+    }
+}
+
+/// Serve until the listener fails.
+pub fn run(config: &Config) -> Result<(), String> {
+    let listener = TcpListener::bind(&config.addr).map_err(|e| format!("{}: {}", config.addr, e))?;
+    serve(listener, config)
+}
+
+/// Serve connections from an already bound listener.
+pub fn serve(listener: TcpListener, config: &Config) -> Result<(), String> {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|e| e.to_string())?;
+        let (config, active) = (config.clone(), Arc::clone(&active));
+        std::thread::spawn(move || handle_connection(stream, &config, active));
+    }
+    Ok(())
+}
+
+/// Frees a concurrency slot when the request's work is done.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, config: &Config, active: Arc<AtomicUsize>) {
+    let deadline = search::now_ms() + config.timeout_ms as f64;
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let _ = stream.set_read_timeout(Some(timeout));
+    let (status, body) = match read_request(&stream) {
+        Err(e) => e,
+        Ok((method, path, body)) => {
+            if method != "POST" {
+                (405, error_json("use POST"))
+            } else if active.fetch_add(1, Ordering::SeqCst) >= config.max_concurrent {
+                active.fetch_sub(1, Ordering::SeqCst);
+                (503, error_json("too many requests in progress"))
+            } else {
+                // The slot stays taken until the search finishes, even
+                // after a timeout reply, so abandoned work still counts;
+                // the deadline ends that search and frees the engine
+                let slot = Slot(active);
+                let (tx, rx) = mpsc::channel();
+                let timeout_ms = config.timeout_ms;
+                std::thread::spawn(move || {
+                    search::set_deadline(Some(deadline));
+                    let out = route(&path, &body, timeout_ms);
+                    // Free the slot before the reply can reach the client
+                    drop(slot);
+                    let _ = tx.send(out);
+                });
+                match rx.recv_timeout(timeout) {
+                    Ok(Ok(out)) => (200, serde_json::to_string(&out).unwrap_or_else(|_| "{}".to_string())),
+                    Ok(Err(e)) => e,
+                    Err(RecvTimeoutError::Timeout) => (504, error_json("request timed out")),
+                    Err(RecvTimeoutError::Disconnected) => (500, error_json("internal error")),
+                }
+            }
+        }
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Gateway Timeout",
+    };
+    let _ = write!(stream, "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                           Connection: close\r\n\r\n{}", status, reason, body.len(), body);
+}
+
+/// (method, path, body)
+fn read_request(stream: &TcpStream) -> Result<(String, String, String), (u16, String)> {
+    let bad = |msg: &str| (400, error_json(msg));
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| bad(&e.to_string()))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad("bad request line"));
+    };
+    let (method, path) = (method.to_string(), target.split('?').next().unwrap_or("").to_string());
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| bad(&e.to_string()))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| bad("bad Content-Length"))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err((413, error_json("request body too large")));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| bad(&e.to_string()))?;
+    let body = String::from_utf8(body).map_err(|_| bad("body is not UTF-8"))?;
+    Ok((method, path, body))
+}
+
+/// Run one endpoint on a request body.
+fn route(path: &str, body: &str, timeout_ms: u64) -> Result<SearchOutput, (u16, String)> {
+    let endpoint: fn(SearchInput, &Position, f64) -> SearchOutput = match path {
+        "/choose-move" => choose_move,
+        "/analyze" => analyze,
+        "/solve" => solve,
+        "/legal-moves" => legal_moves,
+        "/adjudicate" => adjudicate,
+        _ => return Err((404, error_json(&format!("no endpoint {}", path)))),
+    };
+    let (input, pos) = parse_search_input(body).map_err(|e| (400, error_json(&e)))?;
+    Ok(endpoint(input, &pos, timeout_ms as f64))
+}
+
+fn choose_move(mut input: SearchInput, pos: &Position, timeout_ms: f64) -> SearchOutput {
+    input.time_budget = Some(input.time_budget.unwrap_or(5000.0).min(timeout_ms));
+    run_choose_move(&input, pos)
+}
+
+fn analyze(mut input: SearchInput, pos: &Position, timeout_ms: f64) -> SearchOutput {
+    input.explain = true;
+    input.skill_level = None;
+    choose_move(input, pos, timeout_ms)
+}
+
+fn solve(mut input: SearchInput, pos: &Position, timeout_ms: f64) -> SearchOutput {
+    input.skill_level = None;
+    input.time_budget = Some(timeout_ms);
+    input.solve = true;
+    run_choose_move(&input, pos)
+}
+
+fn legal_moves(_input: SearchInput, pos: &Position, _timeout_ms: f64) -> SearchOutput {
+    let mut buf = [(0i8, 0i8); lookup::NUM_TILES];
+    let n = pos.state.legal_moves(&mut buf);
+    let analysis = buf[..n].iter().map(|&(t, e)| AnalysisEntry {
+        tile_id: crate::tile_name(t),
+        end: crate::end_name(e),
+        score: 0,
+        explain: None,
+    }).collect();
+    SearchOutput { analysis, ..SearchOutput::empty() }
+}
+
+fn adjudicate(input: SearchInput, pos: &Position, timeout_ms: f64) -> SearchOutput {
+    let rules = input.rules.as_ref().map(RulesDesc::to_rule_set).unwrap_or_default();
+    let state: &HandState = &pos.state;
+    let mover = state.to_move;
+    if let Some(sc) = state.result_for(mover, &rules) {
+        return SearchOutput { best_score: sc, ..SearchOutput::empty() };
+    }
+    let budget = input.time_budget.unwrap_or(timeout_ms).min(timeout_ms);
+    let match_diff = pos.score[mover as usize] - pos.score[1 - mover as usize];
+    let objective = crate::equity::Objective::Points;
+    let (best_score, r) = if state.can_move(mover) {
+        let r = state.search_limited(mover, budget, &rules, objective, match_diff, &SearchLimits::SOLVE);
+        (r.best_score, r)
+    } else {
+        let mut after = *state;
+        after.pass();
+        let r = after.search_limited(1 - mover, budget, &rules, objective, -match_diff, &SearchLimits::SOLVE);
+        (-r.best_score, r)
+    };
+    SearchOutput { best_score, depth: r.depth, nodes: r.nodes, ..SearchOutput::empty() }
+}
+
+fn error_json(msg: &str) -> String {
+    serde_json::json!({ "error": msg }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SIX_OR_LOCK;
+    use serde_json::Value;
+
+    fn post(addr: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut s = TcpStream::connect(addr).unwrap();
+        write!(s, "POST {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", path, body.len(), body).unwrap();
+        let mut reply = String::new();
+        s.read_to_string(&mut reply).unwrap();
+        let status = reply[9..12].parse().unwrap();
+        let json = reply.split_once("\r\n\r\n").unwrap().1;
+        (status, serde_json::from_str(json).unwrap())
+    }
+
+    /// Address of a server running `timeout_ms` and `max_concurrent`.
+    fn start(timeout_ms: u64, max_concurrent: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let config = Config { addr: addr.clone(), timeout_ms, max_concurrent };
+        std::thread::spawn(move || serve(listener, &config));
+        addr
+    }
+
+    #[test]
+    fn test_endpoints_over_http() {
+        let addr = start(5000, 2);
+        let pos = SIX_OR_LOCK;
+        let (status, out) = post(&addr, "/choose-move", pos);
+        assert_eq!(status, 200);
+        assert_eq!(out["tileId"], "6-6");
+
+        let (_, out) = post(&addr, "/legal-moves", pos);
+        let tiles: Vec<&str> = out["analysis"].as_array().unwrap().iter()
+            .map(|a| a["tileId"].as_str().unwrap()).collect();
+        assert_eq!(tiles, ["0-1", "6-6"]);
+
+        let (_, solved) = post(&addr, "/solve", pos);
+        let (_, adjudged) = post(&addr, "/adjudicate", pos);
+        assert_eq!(adjudged["bestScore"], solved["bestScore"]);
+        assert_eq!(adjudged["tileId"], "");
+
+        // Finished hand: the human went out holding nothing
+        let (_, out) = post(&addr, "/adjudicate", "-/66 0:5 a 0 h05@05");
+        assert_eq!(out["bestScore"], -12);
+
+        assert_eq!(post(&addr, "/nope", pos).0, 404);
+        let (status, out) = post(&addr, "/choose-move", "{not json");
+        assert_eq!(status, 400);
+        assert!(out["error"].is_string());
+
+        // Out-of-range pips are bad input, not a crashed worker
+        let bad = r#"{"aiTiles":[{"low":6,"high":9}],"humanTiles":[{"low":5,"high":6}],"boardEmpty":true}"#;
+        let (status, out) = post(&addr, "/choose-move", bad);
+        assert_eq!(status, 400);
+        assert!(out["error"].is_string());
+    }
+
+    #[test]
+    fn test_busy_and_timed_out_requests() {
+        let addr = start(500, 1);
+        // With the engine held, the first request waits in its slot until
+        // the timeout; a second one finds no slot free
+        let engine = search::lock_engine();
+        let first = {
+            let addr = addr.clone();
+            std::thread::spawn(move || post(&addr, "/choose-move", SIX_OR_LOCK))
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        let (status, out) = post(&addr, "/choose-move", SIX_OR_LOCK);
+        assert_eq!(status, 503);
+        assert!(out["error"].is_string());
+        let (status, out) = first.join().unwrap();
+        assert_eq!(status, 504);
+        assert_eq!(out["error"], "request timed out");
+        drop(engine);
+
+        // The abandoned search ends at its deadline and gives the slot back
+        let mut status = 0;
+        for _ in 0..50 {
+            status = post(&addr, "/legal-moves", SIX_OR_LOCK).0;
+            if status != 503 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(status, 200);
+    }
+}
@@ -0,0 +1,190 @@
+//! Domino sets generic over the highest pip: double-six (28 tiles, `i32`
+//! bitboards), double-nine (55 tiles, `u64`) and double-twelve (91 tiles, `u128`).
+//!
+//! A set ties a bitboard type to its lookup tables (`lookup.rs`) and Zobrist
+//! keys (`zobrist.rs`). Move generation, scoring, evaluation and search are
+//! generic over `DominoSet`, so each set gets its own monomorphized engine;
+//! the double-six one is the engine the game plays.
+
+use crate::lookup::{self, SetTables};
+use crate::zobrist::{self, ZobristTables};
+use std::fmt::Debug;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+/// Largest supported set (double-twelve). Sizes fixed move buffers.
+pub const MAX_SET_TILES: usize = 91;
+
+/// Tile bitmask type: one bit per tile index.
+pub trait Bitboard:
+    Copy + Eq + Debug + Default + Send + Sync + 'static
+    + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+    + BitAndAssign + BitOrAssign + BitXorAssign
+{
+    const ZERO: Self;
+    fn bit(idx: usize) -> Self;
+    fn count(self) -> i32;
+    /// Index of the lowest set bit (`self` must be non-zero).
+    fn lowest(self) -> usize;
+    /// `self` with its lowest set bit cleared.
+    fn clear_lowest(self) -> Self;
+
+    #[inline(always)]
+    fn is_zero(self) -> bool {
+        self == Self::ZERO
+    }
+    #[inline(always)]
+    fn has(self, idx: usize) -> bool {
+        !(self & Self::bit(idx)).is_zero()
+    }
+}
+
+macro_rules! impl_bitboard {
+    ($t:ty, $u:ty) => {
+        impl Bitboard for $t {
+            const ZERO: Self = 0;
+            #[inline(always)]
+            fn bit(idx: usize) -> Self {
+                (1 as $t) << idx
+            }
+            #[inline(always)]
+            fn count(self) -> i32 {
+                (self as $u).count_ones() as i32
+            }
+            #[inline(always)]
+            fn lowest(self) -> usize {
+                self.trailing_zeros() as usize
+            }
+            #[inline(always)]
+            fn clear_lowest(self) -> Self {
+                self & self.wrapping_sub(1)
+            }
+        }
+    };
+}
+
+impl_bitboard!(i32, u32);
+impl_bitboard!(u64, u64);
+impl_bitboard!(u128, u128);
+
+/// Iterate the tile indices set in `b`, lowest first.
+#[inline]
+pub fn bits<B: Bitboard>(mut b: B) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if b.is_zero() {
+            None
+        } else {
+            let idx = b.lowest();
+            b = b.clear_lowest();
+            Some(idx)
+        }
+    })
+}
+
+/// A domino set: highest pip, bitboard type and its tables.
+pub trait DominoSet: Copy + Debug + Default + Send + Sync + 'static {
+    type Bits: Bitboard;
+    const MAX_PIP: i8;
+    const NUM_TILES: usize = (Self::MAX_PIP as usize + 1) * (Self::MAX_PIP as usize + 2) / 2;
+    /// End value meaning "empty board".
+    const EMPTY_END: i8 = Self::MAX_PIP + 1;
+    /// Stride of the `new_end_*` tables.
+    const END_STRIDE: usize = Self::MAX_PIP as usize + 2;
+
+    fn tables() -> &'static SetTables<Self::Bits>;
+    fn zobrist() -> &'static ZobristTables;
+}
+
+macro_rules! domino_set {
+    ($(#[$doc:meta])* $name:ident, $bits:ty, $max:expr, $tables:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct $name;
+
+        impl DominoSet for $name {
+            type Bits = $bits;
+            const MAX_PIP: i8 = $max;
+
+            #[inline(always)]
+            fn tables() -> &'static SetTables<$bits> {
+                &lookup::$tables
+            }
+
+            #[inline(always)]
+            fn zobrist() -> &'static ZobristTables {
+                &zobrist::$tables
+            }
+        }
+    };
+}
+
+domino_set!(
+    /// Standard double-six set (28 tiles).
+    DoubleSix, i32, 6, DOUBLE_SIX
+);
+domino_set!(
+    /// Double-nine set (55 tiles).
+    DoubleNine, u64, 9, DOUBLE_NINE
+);
+domino_set!(
+    /// Double-twelve set (91 tiles).
+    DoubleTwelve, u128, 12, DOUBLE_TWELVE
+);
+
+/// Every tile of the set as a bitmask.
+#[inline]
+pub fn full_set<S: DominoSet>() -> S::Bits {
+    (0..S::NUM_TILES).fold(S::Bits::ZERO, |b, t| b | S::Bits::bit(t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::tile_index;
+    use crate::movegen::{collect_moves, count_moves_bb};
+    use crate::scoring::total_pips_bb;
+    use crate::search::compute_new_ends;
+
+    #[test]
+    fn test_set_sizes() {
+        assert_eq!(DoubleSix::NUM_TILES, 28);
+        assert_eq!(DoubleNine::NUM_TILES, 55);
+        assert_eq!(DoubleTwelve::NUM_TILES, 91);
+        assert_eq!(DoubleSix::EMPTY_END, 7);
+        assert_eq!(DoubleTwelve::END_STRIDE, 14);
+        assert_eq!(full_set::<DoubleNine>().count(), 55);
+        assert_eq!(full_set::<DoubleTwelve>().count(), 91);
+        assert_eq!(DoubleTwelve::tables().suit_mask[12].count(), 13);
+        assert_eq!(DoubleNine::tables().double_mask.count(), 10);
+    }
+
+    #[test]
+    fn test_tile_index_range() {
+        assert_eq!(tile_index::<DoubleSix>(6, 5), Some(26));
+        assert_eq!(tile_index::<DoubleSix>(9, 1), None);
+        assert_eq!(tile_index::<DoubleSix>(-1, 1), None);
+        assert_eq!(tile_index::<DoubleNine>(9, 9), Some(54));
+        assert_eq!(tile_index::<DoubleTwelve>(13, 0), None);
+    }
+
+    #[test]
+    fn test_double_twelve_moves() {
+        // [9-12] and [12-12] on ends 12/5: both play left, nothing on the 5
+        let t = |a, b| tile_index::<DoubleTwelve>(a, b).unwrap();
+        let hand = u128::bit(t(9, 12)) | u128::bit(t(12, 12)) | u128::bit(t(0, 11));
+        let mut out = [(0i8, 0i8); MAX_SET_TILES];
+        let n = collect_moves::<DoubleTwelve>(hand, 12, 5, &mut out);
+        assert_eq!(n, 2);
+        assert_eq!(count_moves_bb::<DoubleTwelve>(hand, 12, 5), 2);
+        assert_eq!(compute_new_ends::<DoubleTwelve>(t(9, 12), 0, 12, 5), (9, 5));
+        assert_eq!(compute_new_ends::<DoubleTwelve>(t(9, 12), 0, 13, 13), (9, 12));
+        assert_eq!(count_moves_bb::<DoubleTwelve>(hand, 13, 13), 3);
+    }
+
+    #[test]
+    fn test_ghost13_generic() {
+        let t00 = u64::bit(0);
+        let t05 = u64::bit(tile_index::<DoubleNine>(0, 5).unwrap());
+        assert_eq!(total_pips_bb::<DoubleNine>(t00, t00, true), 13);
+        assert_eq!(total_pips_bb::<DoubleNine>(t00, t00 | t05, true), 0);
+    }
+}
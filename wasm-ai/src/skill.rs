@@ -0,0 +1,294 @@
+//! Graded strength levels 1–10, replacing ai.js's easy/hard switch.
+//!
+//! Each level below 10 combines:
+//! - depth and node caps on every search (`SearchLimits`),
+//! - a softmax pick among root moves: a move `gap` points worse than the
+//!   best is chosen with weight `exp(-gap / temperature)`,
+//! - at the lowest levels, a hidden human hand: the engine searches against
+//!   hands sampled from every tile outside its own, i.e. a player who does
+//!   not count the tiles already played.
+//!
+//! Level 10 is the unrestricted engine. Weakened levels play for hand
+//! points; match equity is only used at full strength.
+
+use crate::equity::Objective;
+use crate::game::HandState;
+use crate::lookup::NUM_TILES;
+use crate::rules::RuleSet;
+use crate::search::{SearchLimits, SearchResult};
+use crate::zobrist::Xorshift32;
+
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 10;
+
+/// Sampled opponent hands per decision when the hand is hidden.
+const HIDDEN_SAMPLES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkillLevel {
+    pub level: u8,
+    pub limits: SearchLimits,
+    /// Score gap (points) that divides a move's pick weight by e;
+    /// 0 always plays the best move
+    pub temperature: f64,
+    /// Search against sampled opponent hands instead of the real one
+    pub hide_hand: bool,
+}
+
+/// Capped levels search with a fresh TT, so what a level plays depends on
+/// the position and seed only, not on earlier searches.
+const fn lvl(level: u8, max_depth: i32, max_nodes: u32, temperature: f64, hide_hand: bool) -> SkillLevel {
+    let fresh = max_depth > 0 || max_nodes > 0;
+    SkillLevel { level, limits: SearchLimits { max_depth, max_nodes, root_moves: 0, fresh }, temperature, hide_hand }
+}
+
+const LEVELS: [SkillLevel; MAX_LEVEL as usize] = [
+    lvl(1, 1, 2_000, 20.0, true),
+    lvl(2, 1, 5_000, 14.0, true),
+    lvl(3, 2, 10_000, 10.0, true),
+    lvl(4, 2, 20_000, 7.0, false),
+    lvl(5, 3, 50_000, 5.0, false),
+    lvl(6, 4, 100_000, 3.5, false),
+    lvl(7, 6, 300_000, 2.0, false),
+    lvl(8, 8, 1_000_000, 1.0, false),
+    lvl(9, 12, 5_000_000, 0.5, false),
+    lvl(10, 0, 0, 0.0, false),
+];
+
+impl SkillLevel {
+    /// Settings for `level`, clamped to 1..=10.
+    pub fn new(level: u8) -> Self {
+        LEVELS[(level.clamp(MIN_LEVEL, MAX_LEVEL) - 1) as usize]
+    }
+
+    pub fn is_full_strength(&self) -> bool {
+        self.temperature == 0.0 && self.limits == SearchLimits::NONE && !self.hide_hand
+    }
+}
+
+/// Pick a move for the side to move in `pos` at `skill`. `seed` drives the
+/// sampling and the weighted pick, so a given seed always plays the same
+/// move. `objective` applies at full strength only. Below full strength the
+/// result's `analysis` holds the value of every root move (exact within the
+/// caps, averaged over sampled hands when hidden) and `nodes` is 0.
+pub fn choose_move_at_level(
+    pos: &HandState, skill: &SkillLevel, time_budget: f64, rules: &RuleSet,
+    objective: Objective, match_diff: i32, seed: u32,
+) -> SearchResult {
+    let mover = pos.to_move;
+    if skill.is_full_strength() {
+        return pos.search(mover, time_budget, rules, objective, match_diff);
+    }
+
+    let mut result = SearchResult {
+        best_tile_idx: -1,
+        best_end: -1,
+        best_score: 0,
+        depth: 0,
+        nodes: 0,
+        placements_left: None,
+        analysis: Vec::new(),
+        pv: Vec::new(),
+        tt_probes: 0,
+        tt_hits: 0,
+        tt_cutoffs: 0,
+        tt_hints: 0,
+    };
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let n = pos.legal_moves(&mut buf);
+    if n == 0 {
+        return result;
+    }
+    if n == 1 {
+        result.best_tile_idx = buf[0].0;
+        result.best_end = buf[0].1;
+        return result;
+    }
+
+    let mut rng = Xorshift32::new(seed.wrapping_mul(0x9E37_79B9) | 1);
+    let opp = 1 - mover as usize;
+    // Board tiles stay in the pool on purpose: this player does not count
+    // what has been played, so it may fear (or hope for) a tile that is gone
+    let views: Vec<HandState> = if skill.hide_hand {
+        (0..HIDDEN_SAMPLES).map(|_| {
+            let mut v = *pos;
+            v.hands[opp] = sample_hand(!pos.hands[mover as usize] & ((1 << NUM_TILES) - 1),
+                                       pos.hands[opp].count_ones(), &mut rng);
+            v
+        }).collect()
+    } else {
+        vec![*pos]
+    };
+
+    let budget = time_budget / (n * views.len()) as f64;
+    let mut solved = true;
+    for &(t, e) in &buf[..n] {
+        let mut total = 0;
+        for v in &views {
+            let mut after = *v;
+            after.place(t, e);
+            let (sc, s) = after.value_after_move(mover, budget, rules, match_diff, &skill.limits);
+            total += sc;
+            solved &= s;
+        }
+        result.analysis.push((t, e, (total as f64 / views.len() as f64).round() as i32));
+    }
+
+    let best = result.analysis.iter().map(|&(_, _, s)| s).max().unwrap_or(0);
+    let weights: Vec<f64> = result.analysis.iter()
+        .map(|&(_, _, s)| (-((best - s) as f64) / skill.temperature).exp())
+        .collect();
+    let mut r = rng.next() as f64 / 4_294_967_296.0 * weights.iter().sum::<f64>();
+    let mut pick = weights.len() - 1;
+    for (i, w) in weights.iter().enumerate() {
+        if r < *w {
+            pick = i;
+            break;
+        }
+        r -= w;
+    }
+
+    let (t, e, s) = result.analysis[pick];
+    result.best_tile_idx = t;
+    result.best_end = e;
+    result.best_score = s;
+    result.depth = if solved { pos.tiles_left() } else { skill.limits.max_depth };
+    result
+}
+
+/// `count` random tiles out of `pool`.
+fn sample_hand(pool: i32, count: u32, rng: &mut Xorshift32) -> i32 {
+    let mut tiles: Vec<usize> = (0..NUM_TILES).filter(|&t| pool & (1 << t) != 0).collect();
+    let mut hand = 0;
+    for _ in 0..count.min(tiles.len() as u32) {
+        let k = (rng.next() % tiles.len() as u32) as usize;
+        hand |= 1 << tiles.swap_remove(k);
+    }
+    hand
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::deal;
+    use crate::fixtures::{dealt, six_or_lock};
+    use crate::game::AI;
+    use crate::lookup::tile_id_to_index;
+    use crate::search::choose_move;
+
+    #[test]
+    fn test_levels_are_graded() {
+        assert_eq!(SkillLevel::new(0).level, 1);
+        assert_eq!(SkillLevel::new(11).level, 10);
+        assert!(SkillLevel::new(10).is_full_strength());
+        for l in MIN_LEVEL..MAX_LEVEL - 1 {
+            let (a, b) = (SkillLevel::new(l), SkillLevel::new(l + 1));
+            assert!(a.limits.max_depth <= b.limits.max_depth);
+            assert!(a.limits.max_nodes <= b.limits.max_nodes);
+            assert!(a.temperature >= b.temperature);
+            assert!(!a.is_full_strength());
+        }
+    }
+
+    #[test]
+    fn test_weak_levels_make_mistakes() {
+        // Leading [0-1] blocks for −23 instead of −1: a 22-point gap
+        let pos = six_or_lock();
+        let six = tile_id_to_index(6, 6) as i8;
+        let mut blunders = [0; 2];
+        for seed in 0..40 {
+            for (k, level) in [1u8, 9].into_iter().enumerate() {
+                let r = choose_move_at_level(&pos, &SkillLevel::new(level), 200.0,
+                                             &RuleSet::STANDARD, Objective::Points, 0, seed);
+                assert!(r.best_tile_idx >= 0);
+                if r.best_tile_idx != six {
+                    blunders[k] += 1;
+                }
+            }
+        }
+        assert!(blunders[0] > 0, "level 1 never errs");
+        assert!(blunders[0] < 40, "level 1 always errs");
+        assert_eq!(blunders[1], 0);
+
+        // Same seed, same move
+        let a = choose_move_at_level(&pos, &SkillLevel::new(3), 200.0, &RuleSet::STANDARD, Objective::Points, 0, 7);
+        let b = choose_move_at_level(&pos, &SkillLevel::new(3), 200.0, &RuleSet::STANDARD, Objective::Points, 0, 7);
+        assert_eq!((a.best_tile_idx, a.best_end), (b.best_tile_idx, b.best_end));
+    }
+
+    #[test]
+    fn test_search_limits_cap_depth_and_nodes() {
+        let [human, ai] = deal(11);
+        let search = |limits: SearchLimits| choose_move(
+            ai, human, 7, 7, 0, 0, -1, 0, 0, -1, -1, 0, 0,
+            5000.0, &RuleSet::STANDARD, Objective::Points, &limits,
+        );
+        let r = search(SearchLimits { max_depth: 2, ..SearchLimits::NONE });
+        assert_eq!(r.depth, 2);
+        let r = search(SearchLimits { max_nodes: 50, ..SearchLimits::NONE });
+        assert!(r.best_tile_idx >= 0);
+        assert!(r.depth <= 2);
+    }
+
+    #[test]
+    fn test_levels_ignore_earlier_searches() {
+        // A few plies in, past the opening book
+        let [human, ai] = deal(6);
+        let mut pos = HandState::new(human, ai, AI);
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        for _ in 0..4 {
+            pos.legal_moves(&mut buf);
+            pos.place(buf[0].0, buf[0].1);
+        }
+        let play = |level: u8| choose_move_at_level(&pos, &SkillLevel::new(level), 5000.0,
+                                                    &RuleSet::STANDARD, Objective::Points, 0, 3);
+        let before = play(6);
+        // A deeper search leaves its entries for the same lines
+        let deep = SearchLimits { max_nodes: 500_000, ..SearchLimits::NONE };
+        pos.search_limited(pos.to_move, 5000.0, &RuleSet::STANDARD, Objective::Points, 0, &deep);
+        let after = play(6);
+        assert_eq!(before.analysis, after.analysis);
+        assert_eq!((before.best_tile_idx, before.best_end), (after.best_tile_idx, after.best_end));
+    }
+
+    #[test]
+    fn test_sampled_board_tiles_score_sanely() {
+        // A sampled hand may hold tiles already on the board; searching such
+        // a layout must still give scores a real hand could reach
+        let all = (1 << NUM_TILES) - 1;
+        let skill = SkillLevel::new(3);
+        let mut buf = [(0i8, 0i8); NUM_TILES];
+        let mut impossible = 0;
+        for seed in 0..8 {
+            let pos = dealt(seed, 6 + seed as usize % 5).state;
+            let mover = pos.to_move;
+            let opp = 1 - mover as usize;
+            let board = all & !(pos.hands[0] | pos.hands[1]);
+            let mut rng = Xorshift32::new(seed | 1);
+            for _ in 0..HIDDEN_SAMPLES {
+                let mut v = pos;
+                v.hands[opp] = sample_hand(all & !pos.hands[mover as usize], pos.hands[opp].count_ones(), &mut rng);
+                if v.hands[opp] & board != 0 {
+                    impossible += 1;
+                }
+                let n = v.legal_moves(&mut buf);
+                for &(t, e) in &buf[..n] {
+                    let mut after = v;
+                    after.place(t, e);
+                    let (sc, _) = after.value_after_move(mover, 200.0, &RuleSet::STANDARD, 0, &skill.limits);
+                    assert!(sc.abs() <= 2 * 168, "seed {}: {}", seed, sc);
+                }
+            }
+        }
+        assert!(impossible > 0);
+    }
+
+    #[test]
+    fn test_sample_hand_draws_from_pool() {
+        let mut rng = Xorshift32::new(5);
+        let pool = 0x0ff_f0f0;
+        let h = sample_hand(pool, 6, &mut rng);
+        assert_eq!(h.count_ones(), 6);
+        assert_eq!(h & !pool, 0);
+    }
+}
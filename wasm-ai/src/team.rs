@@ -0,0 +1,362 @@
+//! Four-seat team search for the 2v2 and 1v3 modes of 4players/.
+//! Seating follows 4players/game.js (Human → AI-1 → AI-3 → AI-2), so in 2v2 the
+//! partners sit across (seats 0+2 vs 1+3) and in 1v3 seat 0 plays alone.
+//! Partners share one utility, which makes the hand zero-sum between the two
+//! teams: plain alpha-beta over the team score, on top of `multi::MultiPosition`.
+
+use crate::multi::{
+    MultiPosition, MultiSearchResult, MultiCtx, HandEnd, MAX_SIDES, order_moves_multi,
+};
+use crate::movegen::{collect_moves, count_moves_bb};
+use crate::scoring::total_pips_bb;
+use crate::set::{Bitboard, DominoSet, MAX_SET_TILES};
+
+// Team evaluation weights
+const W_PIP: f64 = 1.0;
+const W_TILE: f64 = 4.0;
+const W_MOBILITY: f64 = 2.0;
+const W_MIN_TILES: f64 = 6.0;
+const W_SQUEEZE: f64 = 5.0;
+const W_TEMPO: f64 = 4.0;
+
+/// Team layout of the four seats.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TeamConfig {
+    /// Seats 0+2 (team 0) vs 1+3 (team 1).
+    TwoVsTwo,
+    /// Seat 0 (team 0) vs seats 1, 2, 3 (team 1).
+    OneVsThree,
+}
+
+impl TeamConfig {
+    #[inline]
+    pub fn team_of(self, seat: usize) -> usize {
+        match self {
+            TeamConfig::TwoVsTwo => seat & 1,
+            TeamConfig::OneVsThree => (seat != 0) as usize,
+        }
+    }
+}
+
+/// How a blocked hand is scored between teams.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TeamBlockRule {
+    /// 4players/game.js: lowest combined team pips wins (ties to team 0) and
+    /// scores the losing team's pips minus its own.
+    LowestTeam,
+    /// Two-player block rule lifted to teams: the last placer's team is the
+    /// aggressor; it scores twice the other team's pips if its own combined
+    /// pips are lower or equal, otherwise the other team scores all pips.
+    Aggressor,
+}
+
+/// Team rules for one hand.
+#[derive(Clone, Copy, Debug)]
+pub struct TeamRules {
+    pub config: TeamConfig,
+    pub block_rule: TeamBlockRule,
+}
+
+/// Combined pips per team (Ghost 13 applies across all hands).
+#[inline]
+fn team_pips<S: DominoSet>(pos: &MultiPosition<S>, config: TeamConfig) -> [i32; 2] {
+    let all = pos.all_hands();
+    let mut pips = [0i32; 2];
+    for s in 0..pos.num_sides {
+        pips[config.team_of(s)] += total_pips_bb::<S>(pos.hands[s], all, true);
+    }
+    pips
+}
+
+/// Score a finished hand from team 0's perspective (positive = team 0 scores).
+/// Both members of the winning team are credited the same points.
+pub fn score_team<S: DominoSet>(pos: &MultiPosition<S>, end: HandEnd, rules: &TeamRules) -> i32 {
+    let pips = team_pips(pos, rules.config);
+    match end {
+        HandEnd::Domino(w) => {
+            if rules.config.team_of(w) == 0 { pips[1] } else { -pips[0] }
+        }
+        HandEnd::Block => match rules.block_rule {
+            TeamBlockRule::LowestTeam => pips[1] - pips[0],
+            TeamBlockRule::Aggressor => {
+                let aggr = if pos.last_placer >= 0 {
+                    rules.config.team_of(pos.last_placer as usize)
+                } else {
+                    0
+                };
+                let opp = 1 - aggr;
+                let pts = if pips[aggr] <= pips[opp] {
+                    pips[opp] * 2
+                } else {
+                    -(pips[0] + pips[1])
+                };
+                if aggr == 0 { pts } else { -pts }
+            }
+        },
+    }
+}
+
+/// Static team evaluation from team 0's perspective.
+/// Beyond per-seat pips/tiles/mobility it rewards the team member closest to
+/// dominoing, ends the enemy team cannot follow (squeeze), and penalizes the
+/// team whose seat is to move but stuck.
+pub fn evaluate_team<S: DominoSet>(pos: &MultiPosition<S>, config: TeamConfig) -> i32 {
+    let all = pos.all_hands();
+    let mut score = 0.0;
+    let mut min_tiles = [i32::MAX; 2];
+    let mut team_hand = [S::Bits::ZERO; 2];
+    for s in 0..pos.num_sides {
+        let h = pos.hands[s];
+        let team = config.team_of(s);
+        let sign = if team == 0 { 1.0 } else { -1.0 };
+        let pips = total_pips_bb::<S>(h, all, true) as f64;
+        let tiles = h.count();
+        let mob = count_moves_bb::<S>(h, pos.left, pos.right) as f64;
+        score += sign * (-pips * W_PIP - tiles as f64 * W_TILE + mob * W_MOBILITY);
+        min_tiles[team] = min_tiles[team].min(tiles);
+        team_hand[team] |= h;
+    }
+    score += (min_tiles[1] - min_tiles[0]) as f64 * W_MIN_TILES;
+
+    if pos.left != S::EMPTY_END {
+        let ends = if pos.left == pos.right { 1 } else { 2 };
+        for &v in [pos.left, pos.right].iter().take(ends) {
+            let suit = S::tables().suit_mask[v as usize];
+            if (suit & team_hand[1]).is_zero() {
+                score += W_SQUEEZE;
+            }
+            if (suit & team_hand[0]).is_zero() {
+                score -= W_SQUEEZE;
+            }
+        }
+        if count_moves_bb::<S>(pos.hands[pos.to_move], pos.left, pos.right) == 0 {
+            score += if config.team_of(pos.to_move) == 0 { -W_TEMPO } else { W_TEMPO };
+        }
+    }
+    score as i32
+}
+
+fn team_search<S: DominoSet>(
+    pos: &MultiPosition<S>, depth: i32, mut alpha: i32, mut beta: i32,
+    rules: &TeamRules, sign: i32, ctx: &mut MultiCtx,
+) -> i32 {
+    if ctx.tick() {
+        return sign * evaluate_team(pos, rules.config);
+    }
+    let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+    let n = collect_moves::<S>(pos.hands[pos.to_move], pos.left, pos.right, &mut buf);
+    if n == 0 {
+        let mut next = *pos;
+        return match next.pass() {
+            Some(end) => sign * score_team(&next, end, rules),
+            None => team_search(&next, depth, alpha, beta, rules, sign, ctx),
+        };
+    }
+    if depth <= 0 {
+        ctx.horizon = true;
+        return sign * evaluate_team(pos, rules.config);
+    }
+    order_moves_multi::<S>(&mut buf[..n]);
+
+    let root_team = rules.config.team_of(ctx.root_seat);
+    let maximizing = rules.config.team_of(pos.to_move) == root_team;
+    let mut best = if maximizing { i32::MIN } else { i32::MAX };
+    for &(t, e) in &buf[..n] {
+        let mut next = *pos;
+        let sc = match next.play(t as usize, e) {
+            Some(end) => sign * score_team(&next, end, rules),
+            None => team_search(&next, depth - 1, alpha, beta, rules, sign, ctx),
+        };
+        if maximizing {
+            if sc > best { best = sc; }
+            if best > alpha { alpha = best; }
+        } else {
+            if sc < best { best = sc; }
+            if best < beta { beta = best; }
+        }
+        if beta <= alpha {
+            break;
+        }
+    }
+    best
+}
+
+/// Iterative-deepening team search for the seat to move in a 4-seat `pos`.
+/// `values` in the result holds the root team's score for its members and the
+/// negation for the enemy seats. `time_budget` is in ms (0 = 2000 ms default).
+pub fn choose_move_team<S: DominoSet>(pos: &MultiPosition<S>, rules: &TeamRules, time_budget: f64) -> MultiSearchResult {
+    let budget = if time_budget > 0.0 { time_budget } else { 2000.0 };
+    let mut ctx = MultiCtx::new(pos.to_move, budget);
+    let root_team = rules.config.team_of(pos.to_move);
+    let sign = if root_team == 0 { 1 } else { -1 };
+
+    let mut result = MultiSearchResult {
+        best_tile_idx: -1,
+        best_end: -1,
+        values: [0; MAX_SIDES],
+        depth: 0,
+        nodes: 0,
+        exact: false,
+        analysis: Vec::new(),
+    };
+
+    let mut buf = [(0i8, 0i8); MAX_SET_TILES];
+    let n = collect_moves::<S>(pos.hands[pos.to_move], pos.left, pos.right, &mut buf);
+    if n == 0 {
+        return result;
+    }
+    order_moves_multi::<S>(&mut buf[..n]);
+
+    let max_depth = pos.all_hands().count();
+    for iter_depth in 1..=max_depth {
+        ctx.horizon = false;
+        let mut best = i32::MIN;
+        let mut best_move = (-1i8, -1i8);
+        let mut scores = Vec::with_capacity(n);
+
+        for &(t, e) in &buf[..n] {
+            let mut next = *pos;
+            let sc = match next.play(t as usize, e) {
+                Some(end) => sign * score_team(&next, end, rules),
+                None => team_search(&next, iter_depth - 1, best, i32::MAX, rules, sign, &mut ctx),
+            };
+            scores.push((t, e, sc));
+            if sc > best {
+                best = sc;
+                best_move = (t, e);
+            }
+            if ctx.aborted {
+                break;
+            }
+        }
+
+        if ctx.aborted && result.best_tile_idx >= 0 {
+            break;
+        }
+        result.best_tile_idx = best_move.0;
+        result.best_end = best_move.1;
+        for s in 0..pos.num_sides {
+            result.values[s] = if rules.config.team_of(s) == root_team { best } else { -best };
+        }
+        result.depth = iter_depth;
+        result.analysis = scores;
+        result.exact = !ctx.horizon && !ctx.aborted;
+
+        if ctx.aborted || result.exact {
+            break;
+        }
+        if let Some(pos_best) = buf[..n].iter().position(|&m| m == best_move) {
+            buf[..=pos_best].rotate_right(1);
+        }
+    }
+
+    result.nodes = ctx.nodes;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::{tile_id_to_index, NUM_TILES};
+    use crate::set::{DoubleSix, DoubleTwelve, full_set};
+
+    type Pos = MultiPosition<DoubleSix>;
+
+    fn hand(tiles: &[(i8, i8)]) -> i32 {
+        tiles.iter().fold(0, |h, &(a, b)| h | (1 << tile_id_to_index(a, b)))
+    }
+
+    const LOWEST_2V2: TeamRules = TeamRules {
+        config: TeamConfig::TwoVsTwo,
+        block_rule: TeamBlockRule::LowestTeam,
+    };
+
+    #[test]
+    fn test_team_layout() {
+        let two = TeamConfig::TwoVsTwo;
+        assert_eq!([two.team_of(0), two.team_of(1), two.team_of(2), two.team_of(3)], [0, 1, 0, 1]);
+        let one = TeamConfig::OneVsThree;
+        assert_eq!([one.team_of(0), one.team_of(1), one.team_of(2), one.team_of(3)], [0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_team_domino_scores_enemy_pips() {
+        let mut pos = Pos::new(
+            &[0, hand(&[(6, 6)]), hand(&[(5, 5)]), hand(&[(1, 2)])], 3, 4, 0,
+        );
+        pos.last_placer = 0;
+        // Team 0 (seats 0+2) dominoes: scores seats 1+3 = 12 + 3
+        assert_eq!(score_team(&pos, HandEnd::Domino(0), &LOWEST_2V2), 15);
+        // Team 1 dominoes: scores seats 0+2 = 10
+        assert_eq!(score_team(&pos, HandEnd::Domino(3), &LOWEST_2V2), -10);
+    }
+
+    #[test]
+    fn test_team_block_rules() {
+        let mut pos = Pos::new(
+            &[hand(&[(1, 1)]), hand(&[(6, 6)]), hand(&[(2, 2)]), hand(&[(0, 1)])], 3, 3, 0,
+        );
+        // Team pips: team 0 = 2 + 4 = 6, team 1 = 12 + 1 = 13
+        assert_eq!(score_team(&pos, HandEnd::Block, &LOWEST_2V2), 7);
+
+        let aggr = TeamRules { config: TeamConfig::TwoVsTwo, block_rule: TeamBlockRule::Aggressor };
+        pos.last_placer = 2;
+        assert_eq!(score_team(&pos, HandEnd::Block, &aggr), 26);
+        pos.last_placer = 1;
+        assert_eq!(score_team(&pos, HandEnd::Block, &aggr), 19);
+
+        let one = TeamRules { config: TeamConfig::OneVsThree, block_rule: TeamBlockRule::LowestTeam };
+        // 1v3: team 0 = 2, team 1 = 12 + 4 + 1
+        assert_eq!(score_team(&pos, HandEnd::Block, &one), 15);
+    }
+
+    #[test]
+    fn test_partner_domino_preferred() {
+        // Ends 5/2. Playing [5-6] leaves 6/2: seat 1 must answer with [6-6]
+        // and the partner (seat 2) dominoes with [2-5] on the 2.
+        let pos = Pos::new(
+            &[
+                hand(&[(2, 4), (5, 6)]),
+                hand(&[(3, 3), (6, 6)]),
+                hand(&[(2, 5)]),
+                hand(&[(1, 1), (4, 4)]),
+            ],
+            5, 2, 0,
+        );
+        let r = choose_move_team(&pos, &LOWEST_2V2, 500.0);
+        assert!(r.exact);
+        assert!(r.values[0] > 0, "team 0 should win, got {:?}", r.values);
+        assert_eq!(r.values[0], r.values[2]);
+        assert_eq!(r.values[1], -r.values[0]);
+    }
+
+    #[test]
+    fn test_choose_move_team_full_deal() {
+        let mut hands = [0i32; 4];
+        for t in 0..NUM_TILES {
+            hands[(t * 3 + t / 7) % 4] |= 1 << t;
+        }
+        for &config in &[TeamConfig::TwoVsTwo, TeamConfig::OneVsThree] {
+            let rules = TeamRules { config, block_rule: TeamBlockRule::LowestTeam };
+            let pos = Pos::new(&hands, 7, 7, 0);
+            let r = choose_move_team(&pos, &rules, 300.0);
+            assert!(r.best_tile_idx >= 0);
+            assert!(hands[0] & (1 << r.best_tile_idx) != 0);
+        }
+    }
+
+    #[test]
+    fn test_double_twelve_team_search() {
+        // Double-twelve 2v2, 12 tiles each from the top of the set
+        let mut hands = [0u128; 4];
+        for (i, t) in crate::set::bits(full_set::<DoubleTwelve>()).skip(43).enumerate() {
+            hands[i % 4] |= 1 << t;
+        }
+        let rules = TeamRules { config: TeamConfig::TwoVsTwo, block_rule: TeamBlockRule::LowestTeam };
+        let pos = MultiPosition::<DoubleTwelve>::new(&hands, DoubleTwelve::EMPTY_END, DoubleTwelve::EMPTY_END, 2);
+        let r = choose_move_team(&pos, &rules, 200.0);
+        assert!(r.best_tile_idx >= 0);
+        assert!(hands[2] & (1 << r.best_tile_idx) != 0);
+        assert_eq!(r.values[0], r.values[2]);
+    }
+}
@@ -0,0 +1,293 @@
+//! Training data for learned evaluations: self-play positions labelled with
+//! their full-depth search value, the static `evaluate_bb` value and the
+//! best move.
+//!
+//! Deal `d` (0-based, counted over all shards) gets its own PRNG stream from
+//! (`seed`, `d`); shard `k` of `n` plays the deals with `d % n == k`. The
+//! union of the shards is therefore the same dataset for any shard count.
+//! Each deal is played to the end by the engine (with `random_pct` percent
+//! random moves for variety), then up to `per_deal` of its decision points
+//! with `min_tiles..=max_tiles` tiles left are solved. Unsolved ones are
+//! dropped. Searches are capped by nodes rather than time and start from an
+//! empty TT, so the output does not depend on the machine or the shard.
+//!
+//! Every field is from the side to move's perspective. Binary records are
+//! `RECORD_SIZE` bytes, little-endian:
+//!
+//! | offset | type  | field                                              |
+//! |--------|-------|----------------------------------------------------|
+//! | 0      | u32   | mover's hand (bit = tile index)                    |
+//! | 4      | u32   | other hand                                         |
+//! | 8      | u8    | left end, right end (7 = empty board)              |
+//! | 10     | u8    | consecutive passes before this move                |
+//! | 11     | 4 × i8| last placer: who (1 mover, 0 other, -1 none), left, right, tile |
+//! | 15     | 3 × i8| second-to-last placer: who, left, right            |
+//! | 18     | 2 × i8| best move: tile, end (0 left, 1 right)             |
+//! | 20     | i16   | solved value, hand points                          |
+//! | 22     | u8    | ply (moves played in the deal, passes included)    |
+//! | 23     | u8    | 0                                                  |
+//! | 24     | f32   | `evaluate_bb`, match score 0                       |
+//! | 28     | u32   | deal index                                         |
+
+use crate::deal::deal_from_rng;
+use crate::equity::Objective;
+use crate::eval::evaluate_bb;
+use crate::game::{HandState, AI, HUMAN};
+use crate::lookup::NUM_TILES;
+use crate::notation::Position;
+use crate::rules::RuleSet;
+use crate::search::SearchLimits;
+use crate::set::DoubleSix;
+use crate::zobrist::Xorshift32;
+
+/// Bytes per binary record.
+pub const RECORD_SIZE: usize = 32;
+
+/// Time budget for node-capped searches; the nodes run out first.
+pub(crate) const UNTIMED: f64 = 600_000.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrainingOptions {
+    pub seed: u32,
+    /// Deals over all shards
+    pub deals: u32,
+    /// This process's shard, `0..shards`
+    pub shard: u32,
+    pub shards: u32,
+    /// Positions sampled per deal (at most)
+    pub per_deal: usize,
+    /// Sampled positions have between `min_tiles` and `max_tiles` tiles left
+    /// in both hands
+    pub min_tiles: i32,
+    pub max_tiles: i32,
+    /// Percentage of self-play moves picked at random
+    pub random_pct: u32,
+    /// Node cap per self-play move
+    pub play_nodes: u32,
+    /// Node cap per solve; positions not solved within it are dropped
+    pub solve_nodes: u32,
+    pub rules: RuleSet,
+}
+
+impl Default for TrainingOptions {
+    fn default() -> Self {
+        TrainingOptions {
+            seed: 1,
+            deals: 1000,
+            shard: 0,
+            shards: 1,
+            per_deal: 4,
+            min_tiles: 4,
+            max_tiles: 16,
+            random_pct: 10,
+            play_nodes: 20_000,
+            solve_nodes: 20_000_000,
+            rules: RuleSet::STANDARD,
+        }
+    }
+}
+
+/// A labelled position, from the side to move's perspective.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrainingSample {
+    pub deal: u32,
+    pub ply: u8,
+    pub mover_hand: i32,
+    pub other_hand: i32,
+    pub left: i8,
+    pub right: i8,
+    pub cons_pass: u8,
+    /// Last placer: (who, left, right, tile), who 1 = mover, 0 = other,
+    /// -1 = none
+    pub p1: (i8, i8, i8, i8),
+    /// Second-to-last placer: (who, left, right)
+    pub p2: (i8, i8, i8),
+    /// Solved hand points for the mover
+    pub value: i32,
+    /// `evaluate_bb` for the mover
+    pub static_eval: f32,
+    pub best_tile: i8,
+    pub best_end: i8,
+}
+
+impl TrainingSample {
+    /// The position with the mover as `AI`.
+    pub fn state(&self) -> HandState {
+        let abs = |who: i8| if who == 1 { AI } else if who == 0 { HUMAN } else { -1 };
+        HandState {
+            hands: [self.other_hand, self.mover_hand],
+            left: self.left,
+            right: self.right,
+            to_move: AI,
+            cons_pass: self.cons_pass as i32,
+            p1: (abs(self.p1.0), self.p1.1, self.p1.2, self.p1.3),
+            p2: (abs(self.p2.0), self.p2.1, self.p2.2),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut b = [0u8; RECORD_SIZE];
+        b[0..4].copy_from_slice(&(self.mover_hand as u32).to_le_bytes());
+        b[4..8].copy_from_slice(&(self.other_hand as u32).to_le_bytes());
+        let (w1, l1, r1, t1) = self.p1;
+        let (w2, l2, r2) = self.p2;
+        for (i, v) in [self.left, self.right, self.cons_pass as i8, w1, l1, r1, t1, w2, l2, r2,
+                       self.best_tile, self.best_end].into_iter().enumerate() {
+            b[8 + i] = v as u8;
+        }
+        b[20..22].copy_from_slice(&(self.value as i16).to_le_bytes());
+        b[22] = self.ply;
+        b[24..28].copy_from_slice(&self.static_eval.to_le_bytes());
+        b[28..32].copy_from_slice(&self.deal.to_le_bytes());
+        b
+    }
+
+    pub fn from_bytes(b: &[u8; RECORD_SIZE]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+        let i8_at = |i: usize| b[i] as i8;
+        TrainingSample {
+            deal: u32_at(28),
+            ply: b[22],
+            mover_hand: u32_at(0) as i32,
+            other_hand: u32_at(4) as i32,
+            left: i8_at(8),
+            right: i8_at(9),
+            cons_pass: b[10],
+            p1: (i8_at(11), i8_at(12), i8_at(13), i8_at(14)),
+            p2: (i8_at(15), i8_at(16), i8_at(17)),
+            value: i16::from_le_bytes([b[20], b[21]]) as i32,
+            static_eval: f32::from_le_bytes([b[24], b[25], b[26], b[27]]),
+            best_tile: i8_at(18),
+            best_end: i8_at(19),
+        }
+    }
+
+    /// One JSONL line; `position` (mover as the AI) is a `SearchInput`.
+    pub fn to_json(&self) -> String {
+        let desc = crate::TrainingSampleDesc {
+            deal: self.deal,
+            ply: self.ply,
+            position: Position::from_state(self.state()).to_text(),
+            value: self.value,
+            static_eval: self.static_eval,
+            best_move: crate::move_desc(self.best_tile, self.best_end),
+        };
+        serde_json::to_string(&desc).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Generate this shard's samples, handing each to `sample` as it is
+/// labelled. Returns the number of positions dropped as unsolved.
+pub fn generate(opts: &TrainingOptions, mut sample: impl FnMut(&TrainingSample)) -> usize {
+    let mut unsolved = 0;
+    let shards = opts.shards.max(1);
+    for deal in (opts.shard..opts.deals).step_by(shards as usize) {
+        let mut rng = Xorshift32::new((opts.seed ^ deal.wrapping_mul(0x85EB_CA6B)).wrapping_mul(0x9E37_79B9) | 1);
+        for (ply, state) in sample_points(opts, &mut rng) {
+            match label(opts, deal, ply, &state) {
+                Some(s) => sample(&s),
+                None => unsolved += 1,
+            }
+        }
+    }
+    unsolved
+}
+
+/// Play one deal from `rng` and pick up to `per_deal` of its decision
+/// points in the tile window, in game order.
+fn sample_points(opts: &TrainingOptions, rng: &mut Xorshift32) -> Vec<(u8, HandState)> {
+    let [human, ai] = deal_from_rng(rng);
+    let mut state = HandState::new(human, ai, (rng.next() & 1) as i8);
+    let play = SearchLimits { max_nodes: opts.play_nodes, fresh: true, ..SearchLimits::NONE };
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let mut points = Vec::new();
+    let mut ply = 0u8;
+    while state.hand_end().is_none() {
+        let n = state.legal_moves(&mut buf);
+        if n == 0 {
+            state.pass();
+        } else {
+            if n >= 2 && (opts.min_tiles..=opts.max_tiles).contains(&state.tiles_left()) {
+                points.push((ply, state));
+            }
+            let (t, e) = if rng.next() % 100 < opts.random_pct {
+                buf[(rng.next() % n as u32) as usize]
+            } else {
+                let mover = state.to_move;
+                let r = state.search_limited(mover, UNTIMED, &opts.rules, Objective::Points, 0, &play);
+                state.find_legal(r.best_tile_idx, r.best_end).unwrap_or(buf[0])
+            };
+            state.place(t, e);
+        }
+        ply += 1;
+    }
+
+    // Partial Fisher–Yates from the front, then back to game order
+    let k = opts.per_deal.min(points.len());
+    for i in 0..k {
+        let j = i + (rng.next() % (points.len() - i) as u32) as usize;
+        points.swap(i, j);
+    }
+    points.truncate(k);
+    points.sort_by_key(|&(ply, _)| ply);
+    points
+}
+
+fn label(opts: &TrainingOptions, deal: u32, ply: u8, state: &HandState) -> Option<TrainingSample> {
+    let mover = state.to_move;
+    let solve = SearchLimits { max_nodes: opts.solve_nodes, fresh: true, ..SearchLimits::NONE };
+    let r = state.search_limited(mover, UNTIMED, &opts.rules, Objective::Points, 0, &solve);
+    if r.depth < state.tiles_left() || r.best_tile_idx < 0 {
+        return None;
+    }
+    let (mover_hand, other_hand) = (state.hands[mover as usize], state.hands[1 - mover as usize]);
+    let rel = |who: i8| if who < 0 { -1 } else { (who == mover) as i8 };
+    let (w1, l1, r1, t1) = state.p1;
+    let (w2, l2, r2) = state.p2;
+    Some(TrainingSample {
+        deal,
+        ply,
+        mover_hand,
+        other_hand,
+        left: state.left,
+        right: state.right,
+        cons_pass: state.cons_pass as u8,
+        p1: (rel(w1), l1, r1, t1),
+        p2: (rel(w2), l2, r2),
+        value: r.best_score,
+        static_eval: evaluate_bb::<DoubleSix>(mover_hand, other_hand, state.left, state.right, 0, &opts.rules) as f32,
+        best_tile: r.best_tile_idx,
+        best_end: r.best_end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shards_make_the_same_dataset() {
+        let opts = TrainingOptions { deals: 4, per_deal: 2, max_tiles: 8, ..Default::default() };
+        let mut whole = Vec::new();
+        generate(&opts, |s| whole.push(*s));
+        assert!(!whole.is_empty());
+
+        let mut sharded = Vec::new();
+        for shard in 0..2 {
+            generate(&TrainingOptions { shard, shards: 2, ..opts.clone() }, |s| sharded.push(*s));
+        }
+        sharded.sort_by_key(|s| (s.deal, s.ply));
+        assert_eq!(sharded, whole);
+
+        for s in &whole {
+            assert_eq!(TrainingSample::from_bytes(&s.to_bytes()), *s);
+            // The label is exact and the best move achieves it
+            let state = s.state();
+            assert!((opts.min_tiles..=opts.max_tiles).contains(&state.tiles_left()));
+            let mut after = state;
+            after.place(s.best_tile, s.best_end);
+            let (value, solved) = after.value_after_move(AI, 1000.0, &opts.rules, 0, &SearchLimits::NONE);
+            assert_eq!((value, solved), (s.value, true));
+        }
+    }
+}
@@ -1,5 +1,5 @@
-/// Transposition table — 4M entries, struct-of-arrays layout.
-/// Uses generation counter for aging (never needs clearing).
+//! Transposition table — 4M entries, struct-of-arrays layout.
+//! Uses generation counter for aging (never needs clearing).
 
 pub const TT_SIZE: usize = 1 << 22; // 4,194,304 entries
 const TT_MASK: usize = TT_SIZE - 1;
@@ -35,7 +35,9 @@ pub fn tt_new_generation() {
     }
 }
 
-/// Clear the TT completely (rarely needed with generation counter).
+/// Clear the TT completely (needed only when the rules or the score scale
+/// change).
+#[allow(clippy::needless_range_loop)]
 pub fn tt_clear() {
     unsafe {
         for i in 0..TT_SIZE {
@@ -47,6 +49,7 @@ pub fn tt_clear() {
 /// Probe the TT. Returns `None` if no entry, otherwise returns move hint
 /// and optionally a usable score.
 #[inline]
+#[allow(clippy::if_same_then_else)]
 pub fn tt_probe(hash: i32, depth: i32, alpha: i32, beta: i32) -> Option<TtHit> {
     unsafe {
         let idx = (hash as u32 as usize) & TT_MASK;
@@ -110,6 +113,7 @@ mod tests {
 
     #[test]
     fn test_tt_store_and_probe() {
+        let _guard = crate::search::lock_engine();
         tt_clear();
         tt_new_generation();
 
@@ -126,6 +130,7 @@ mod tests {
 
     #[test]
     fn test_tt_depth_insufficient() {
+        let _guard = crate::search::lock_engine();
         tt_clear();
         tt_new_generation();
 
@@ -142,6 +147,7 @@ mod tests {
 
     #[test]
     fn test_tt_lower_bound() {
+        let _guard = crate::search::lock_engine();
         tt_clear();
         tt_new_generation();
 
@@ -161,6 +167,7 @@ mod tests {
 
     #[test]
     fn test_tt_generation_replacement() {
+        let _guard = crate::search::lock_engine();
         tt_clear();
         tt_new_generation();
 
@@ -0,0 +1,256 @@
+//! Evaluation-weight tuner (native replacement for tune-weights.js).
+//!
+//! 1. `generate_dataset` plays seeded deals with random legal moves and keeps
+//!    mid-game positions whose full-depth search value is exact.
+//! 2. `fit` adjusts `EvalParams` by coordinate descent on the mean squared
+//!    error between `evaluate_with` and those values (Texel-style, with the
+//!    solved score as target instead of a game result).
+//!
+//! The midgame phase multipliers are the reference bucket and stay fixed,
+//! since a weight and its phase multiplier are otherwise interchangeable.
+
+use crate::deal::deal_from_rng;
+use crate::equity::Objective;
+use crate::eval::{evaluate_with, EvalParams, NUM_EVAL_PARAMS};
+use crate::game::HandState;
+use crate::lookup::NUM_TILES;
+use crate::rules::RuleSet;
+use crate::set::DoubleSix;
+use crate::zobrist::Xorshift32;
+
+/// A position from the side to move's perspective, labelled with its
+/// solved value for that side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TuneSample {
+    pub mover_hand: i32,
+    pub other_hand: i32,
+    pub left: i8,
+    pub right: i8,
+    pub score: i32,
+}
+
+impl TuneSample {
+    /// One dataset line: `mover other left right score` (hands in hex).
+    pub fn to_line(&self) -> String {
+        format!("{:07x} {:07x} {} {} {}", self.mover_hand, self.other_hand, self.left, self.right, self.score)
+    }
+
+    pub fn parse_line(line: &str) -> Result<Self, String> {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        if f.len() != 5 {
+            return Err(format!("expected 5 fields, got {}", f.len()));
+        }
+        let hex = |s: &str| i32::from_str_radix(s, 16).map_err(|_| format!("bad hand '{}'", s));
+        let num = |s: &str| s.parse::<i32>().map_err(|_| format!("bad number '{}'", s));
+        Ok(TuneSample {
+            mover_hand: hex(f[0])?,
+            other_hand: hex(f[1])?,
+            left: num(f[2])? as i8,
+            right: num(f[3])? as i8,
+            score: num(f[4])?,
+        })
+    }
+}
+
+/// Dataset generation settings.
+#[derive(Clone, Debug)]
+pub struct DatasetOptions {
+    pub positions: usize,
+    pub seed: u32,
+    /// Sampled positions have between `min_tiles` and `max_tiles` tiles left
+    /// in both hands.
+    pub min_tiles: i32,
+    pub max_tiles: i32,
+    /// Search budget per position (ms); unsolved positions are dropped
+    pub time_budget: f64,
+    pub rules: RuleSet,
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        DatasetOptions {
+            positions: 2000,
+            seed: 1,
+            min_tiles: 8,
+            max_tiles: 16,
+            time_budget: 2000.0,
+            rules: RuleSet::STANDARD,
+        }
+    }
+}
+
+/// Play random legal moves from seeded deals and label one position per
+/// deal with its solved value. `progress` receives each accepted sample.
+pub fn generate_dataset(opts: &DatasetOptions, mut progress: impl FnMut(&TuneSample)) -> Vec<TuneSample> {
+    let mut rng = Xorshift32::new(opts.seed.wrapping_mul(0x9E37_79B9) | 1);
+    let mut out = Vec::with_capacity(opts.positions);
+    let mut buf = [(0i8, 0i8); NUM_TILES];
+    let span = (opts.max_tiles - opts.min_tiles + 1).max(1) as u32;
+
+    // Bounded so impossible settings cannot loop forever
+    let max_deals = opts.positions * 20 + 100;
+    for _ in 0..max_deals {
+        if out.len() >= opts.positions {
+            break;
+        }
+        let [human, ai] = deal_from_rng(&mut rng);
+        let leader = (rng.next() & 1) as i8;
+        let stop_at = opts.min_tiles + (rng.next() % span) as i32;
+        let mut pos = HandState::new(human, ai, leader);
+
+        // Random playout down to the sampling point
+        while pos.tiles_left() > stop_at && pos.result_for(0, &opts.rules).is_none() {
+            let n = pos.legal_moves(&mut buf);
+            if n == 0 {
+                pos.pass();
+            } else {
+                let (t, e) = buf[(rng.next() % n as u32) as usize];
+                pos.place(t, e);
+            }
+        }
+        if pos.result_for(0, &opts.rules).is_some() || pos.legal_moves(&mut buf) < 2 {
+            continue;
+        }
+
+        let mover = pos.to_move;
+        let r = pos.search(mover, opts.time_budget, &opts.rules, Objective::Points, 0);
+        if r.depth < pos.tiles_left() {
+            continue;
+        }
+        let sample = TuneSample {
+            mover_hand: pos.hands[mover as usize],
+            other_hand: pos.hands[1 - mover as usize],
+            left: pos.left,
+            right: pos.right,
+            score: r.best_score,
+        };
+        progress(&sample);
+        out.push(sample);
+    }
+    out
+}
+
+/// Mean squared error of `params` over `data`.
+pub fn mse(params: &EvalParams, data: &[TuneSample], rules: &RuleSet) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = data.iter().map(|s| {
+        let e = evaluate_with::<DoubleSix>(params, s.mover_hand, s.other_hand, s.left, s.right, 0, rules)
+            - s.score as f64;
+        e * e
+    }).sum();
+    sum / data.len() as f64
+}
+
+/// Parameters `fit` leaves alone (reference phase bucket, match multipliers
+/// the dataset has no match score for).
+fn is_frozen(name: &str) -> bool {
+    name.starts_with("midgame_") || name.starts_with("leading_") || name.starts_with("trailing_")
+}
+
+/// Coordinate descent: try ±step on every free parameter, keep
+/// improvements, halve the steps after a pass without one. Stops after
+/// `max_passes` passes or when every step is below 1e-3. `progress` gets
+/// (pass, mse) after each pass.
+pub fn fit(
+    start: &EvalParams, data: &[TuneSample], rules: &RuleSet, max_passes: u32,
+    mut progress: impl FnMut(u32, f64),
+) -> EvalParams {
+    let mut best = *start;
+    let mut best_err = mse(&best, data, rules);
+    let mut steps = [0.0f64; NUM_EVAL_PARAMS];
+    for (i, (_, v)) in best.fields_mut().into_iter().enumerate() {
+        steps[i] = (v.abs() * 0.25).max(0.25);
+    }
+
+    for pass in 1..=max_passes {
+        let mut improved = false;
+        for (i, &step) in steps.iter().enumerate() {
+            if is_frozen(best.fields_mut()[i].0) {
+                continue;
+            }
+            for dir in [1.0, -1.0] {
+                let mut cand = best;
+                *cand.fields_mut()[i].1 += dir * step;
+                let err = mse(&cand, data, rules);
+                if err < best_err {
+                    best = cand;
+                    best_err = err;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        progress(pass, best_err);
+        if !improved {
+            for s in steps.iter_mut() {
+                *s *= 0.5;
+            }
+            if steps.iter().all(|&s| s < 1e-3) {
+                break;
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_line_round_trip() {
+        let s = TuneSample { mover_hand: 0x0abc123, other_hand: 0x5400000, left: 3, right: 6, score: -17 };
+        assert_eq!(TuneSample::parse_line(&s.to_line()).unwrap(), s);
+        assert!(TuneSample::parse_line("1 2 3").is_err());
+    }
+
+    #[test]
+    fn test_generate_dataset_is_solved() {
+        let opts = DatasetOptions {
+            positions: 4,
+            min_tiles: 6,
+            max_tiles: 8,
+            time_budget: 1000.0,
+            ..Default::default()
+        };
+        let data = generate_dataset(&opts, |_| {});
+        assert_eq!(data.len(), 4);
+        for s in &data {
+            let tiles = (s.mover_hand.count_ones() + s.other_hand.count_ones()) as i32;
+            assert!((6..=8).contains(&tiles));
+            assert_eq!(s.mover_hand & s.other_hand, 0);
+        }
+        // Same seed, same dataset
+        assert_eq!(generate_dataset(&opts, |_| {}), data);
+    }
+
+    #[test]
+    fn test_fit_recovers_weights() {
+        // Labels from a known parameter set: fitting from the defaults must
+        // move toward it and cut the error.
+        let target = EvalParams { w_pip: 3.0, w_tile: 8.0, ..EvalParams::DEFAULT };
+        let rules = RuleSet::STANDARD;
+        let mut rng = Xorshift32::new(99);
+        let data: Vec<TuneSample> = (0..300).map(|_| {
+            let [a, b] = deal_from_rng(&mut rng);
+            // Drop a random subset so hand sizes (and phases) vary
+            let keep = rng.next() as i32 | rng.next() as i32;
+            let (a, b) = (a & keep, b & (keep | (rng.next() as i32)));
+            let ends = (rng.next() % 7) as i8;
+            TuneSample {
+                mover_hand: a,
+                other_hand: b,
+                left: ends,
+                right: (ends + 2) % 7,
+                score: evaluate_with::<DoubleSix>(&target, a, b, ends, (ends + 2) % 7, 0, &rules).round() as i32,
+            }
+        }).collect();
+        let before = mse(&EvalParams::DEFAULT, &data, &rules);
+        let fitted = fit(&EvalParams::DEFAULT, &data, &rules, 60, |_, _| {});
+        let after = mse(&fitted, &data, &rules);
+        assert!(after < before * 0.05, "{} -> {}", before, after);
+        assert_eq!(fitted.midgame, EvalParams::DEFAULT.midgame);
+    }
+}
@@ -1,18 +1,22 @@
-/// Zobrist hashing — must produce bit-identical values to the JS engine.
-/// Uses xorshift32 PRNG with seed 0x12345678.
+//! Zobrist hashing — must produce bit-identical values to the JS engine.
+//! Uses xorshift32 PRNG with seed 0x12345678. Every set draws its keys from
+//! the same stream, so the double-six keys are the JS engine's.
+
+use crate::lookup::MAX_END_STRIDE;
+use crate::set::{bits, DominoSet, MAX_SET_TILES};
 
 /// Xorshift32 PRNG state. Must be called in the same order as JS to produce identical hashes.
-struct Xorshift32 {
+pub(crate) struct Xorshift32 {
     state: u32,
 }
 
 impl Xorshift32 {
-    fn new(seed: u32) -> Self {
+    pub(crate) fn new(seed: u32) -> Self {
         Self { state: seed }
     }
 
     /// Matches JS: seed ^= seed << 13; seed ^= seed >>> 17; seed ^= seed << 5; return seed >>> 0;
-    fn next(&mut self) -> u32 {
+    pub(crate) fn next(&mut self) -> u32 {
         self.state ^= self.state << 13;
         self.state ^= self.state >> 17; // JS >>> 17 on u32 is logical right shift = Rust >>
         self.state ^= self.state << 5;
@@ -20,18 +24,21 @@ impl Xorshift32 {
     }
 }
 
-/// Generate all Zobrist hash tables at compile time.
-/// Order must match JS: 28 tiles × 2 hands, then 8 left, 8 right, 1 side, 2 conspass.
-
-struct ZobristTables {
-    pub tile_hash: [[u32; 2]; 28],
-    pub left_hash: [u32; 8],
-    pub right_hash: [u32; 8],
+/// Zobrist keys for one set. Arrays are sized for the largest set.
+pub struct ZobristTables {
+    pub tile_hash: [[u32; 2]; MAX_SET_TILES],
+    pub left_hash: [u32; MAX_END_STRIDE],
+    pub right_hash: [u32; MAX_END_STRIDE],
     pub side_hash: u32,
     pub conspass_hash: [u32; 2],
 }
 
-const fn generate_zobrist() -> ZobristTables {
+/// Generate the Zobrist tables for the set with highest pip `max_pip` at
+/// compile time. Order must match JS: tiles × 2 hands, then left ends,
+/// right ends (empty board included), 1 side, 2 conspass.
+const fn generate_zobrist(max_pip: usize) -> ZobristTables {
+    let num_tiles = (max_pip + 1) * (max_pip + 2) / 2;
+    let stride = max_pip + 2;
     let mut rng_state: u32 = 0x12345678;
 
     // Inline xorshift32 for const fn
@@ -44,24 +51,24 @@ const fn generate_zobrist() -> ZobristTables {
         }};
     }
 
-    let mut tile_hash = [[0u32; 2]; 28];
+    let mut tile_hash = [[0u32; 2]; MAX_SET_TILES];
     let mut i = 0;
-    while i < 28 {
+    while i < num_tiles {
         tile_hash[i][0] = next_rng!();
         tile_hash[i][1] = next_rng!();
         i += 1;
     }
 
-    let mut left_hash = [0u32; 8];
+    let mut left_hash = [0u32; MAX_END_STRIDE];
     let mut i = 0;
-    while i < 8 {
+    while i < stride {
         left_hash[i] = next_rng!();
         i += 1;
     }
 
-    let mut right_hash = [0u32; 8];
+    let mut right_hash = [0u32; MAX_END_STRIDE];
     let mut i = 0;
-    while i < 8 {
+    while i < stride {
         right_hash[i] = next_rng!();
         i += 1;
     }
@@ -81,66 +88,65 @@ const fn generate_zobrist() -> ZobristTables {
     }
 }
 
-static ZOBRIST: ZobristTables = generate_zobrist();
+/// Double-six keys: 28 tiles, 8 end values (identical to the JS engine).
+pub static DOUBLE_SIX: ZobristTables = generate_zobrist(6);
+pub static DOUBLE_NINE: ZobristTables = generate_zobrist(9);
+pub static DOUBLE_TWELVE: ZobristTables = generate_zobrist(12);
 
-pub fn tile_hash(tile_idx: usize, hand: usize) -> i32 {
-    ZOBRIST.tile_hash[tile_idx][hand] as i32
+#[inline(always)]
+pub fn tile_hash<S: DominoSet>(tile_idx: usize, hand: usize) -> i32 {
+    S::zobrist().tile_hash[tile_idx][hand] as i32
 }
 
-pub fn left_hash(val: usize) -> i32 {
-    ZOBRIST.left_hash[val] as i32
+#[inline(always)]
+pub fn left_hash<S: DominoSet>(val: usize) -> i32 {
+    S::zobrist().left_hash[val] as i32
 }
 
-pub fn right_hash(val: usize) -> i32 {
-    ZOBRIST.right_hash[val] as i32
+#[inline(always)]
+pub fn right_hash<S: DominoSet>(val: usize) -> i32 {
+    S::zobrist().right_hash[val] as i32
 }
 
-pub fn side_hash() -> i32 {
-    ZOBRIST.side_hash as i32
+#[inline(always)]
+pub fn side_hash<S: DominoSet>() -> i32 {
+    S::zobrist().side_hash as i32
 }
 
-pub fn conspass_hash(idx: usize) -> i32 {
-    ZOBRIST.conspass_hash[idx] as i32
+#[inline(always)]
+pub fn conspass_hash<S: DominoSet>(idx: usize) -> i32 {
+    S::zobrist().conspass_hash[idx] as i32
 }
 
 /// Compute root hash from scratch (matches JS computeRootHash).
-pub fn compute_root_hash(
-    ai_hand: i32,
-    human_hand: i32,
+pub fn compute_root_hash<S: DominoSet>(
+    ai_hand: S::Bits,
+    human_hand: S::Bits,
     left: i8,
     right: i8,
     is_ai: bool,
     cons_pass: i32,
 ) -> i32 {
+    let z = S::zobrist();
     let mut h: u32 = 0;
 
-    // XOR in all AI tiles as hand=0
-    let mut hand = ai_hand as u32;
-    while hand != 0 {
-        let bit = hand & hand.wrapping_neg();
-        let idx = bit.trailing_zeros() as usize;
-        h ^= ZOBRIST.tile_hash[idx][0];
-        hand ^= bit;
+    // XOR in all AI tiles as hand=0, all human tiles as hand=1
+    for idx in bits(ai_hand) {
+        h ^= z.tile_hash[idx][0];
     }
-
-    // XOR in all human tiles as hand=1
-    let mut hand = human_hand as u32;
-    while hand != 0 {
-        let bit = hand & hand.wrapping_neg();
-        let idx = bit.trailing_zeros() as usize;
-        h ^= ZOBRIST.tile_hash[idx][1];
-        hand ^= bit;
+    for idx in bits(human_hand) {
+        h ^= z.tile_hash[idx][1];
     }
 
-    h ^= ZOBRIST.left_hash[left as usize];
-    h ^= ZOBRIST.right_hash[right as usize];
+    h ^= z.left_hash[left as usize];
+    h ^= z.right_hash[right as usize];
 
     if !is_ai {
-        h ^= ZOBRIST.side_hash;
+        h ^= z.side_hash;
     }
 
     if cons_pass > 0 {
-        h ^= ZOBRIST.conspass_hash[1];
+        h ^= z.conspass_hash[1];
     }
 
     h as i32
@@ -149,6 +155,7 @@ pub fn compute_root_hash(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::set::{DoubleSix, DoubleTwelve};
 
     #[test]
     fn test_xorshift32_first_values() {
@@ -166,23 +173,34 @@ mod tests {
     fn test_zobrist_tables_populated() {
         // All tile hashes should be non-zero
         for i in 0..28 {
-            assert_ne!(ZOBRIST.tile_hash[i][0], 0);
-            assert_ne!(ZOBRIST.tile_hash[i][1], 0);
+            assert_ne!(DOUBLE_SIX.tile_hash[i][0], 0);
+            assert_ne!(DOUBLE_SIX.tile_hash[i][1], 0);
         }
-        assert_ne!(ZOBRIST.side_hash, 0);
+        assert_ne!(DOUBLE_SIX.side_hash, 0);
     }
 
     #[test]
     fn test_root_hash_deterministic() {
-        let h1 = compute_root_hash(0b111, 0b111000, 3, 5, true, 0);
-        let h2 = compute_root_hash(0b111, 0b111000, 3, 5, true, 0);
+        let h1 = compute_root_hash::<DoubleSix>(0b111, 0b111000, 3, 5, true, 0);
+        let h2 = compute_root_hash::<DoubleSix>(0b111, 0b111000, 3, 5, true, 0);
         assert_eq!(h1, h2);
     }
 
     #[test]
     fn test_root_hash_side_matters() {
-        let h_ai = compute_root_hash(0b111, 0b111000, 3, 5, true, 0);
-        let h_human = compute_root_hash(0b111, 0b111000, 3, 5, false, 0);
+        let h_ai = compute_root_hash::<DoubleSix>(0b111, 0b111000, 3, 5, true, 0);
+        let h_human = compute_root_hash::<DoubleSix>(0b111, 0b111000, 3, 5, false, 0);
         assert_ne!(h_ai, h_human);
     }
+
+    #[test]
+    fn test_sets_share_the_stream() {
+        // A larger set draws the same first keys, then its own end keys
+        for i in 0..28 {
+            assert_eq!(DOUBLE_TWELVE.tile_hash[i], DOUBLE_SIX.tile_hash[i]);
+        }
+        assert_ne!(DOUBLE_TWELVE.left_hash[0], DOUBLE_SIX.left_hash[0]);
+        assert_ne!(tile_hash::<DoubleTwelve>(90, 1), 0);
+        assert_ne!(right_hash::<DoubleTwelve>(13), 0);
+    }
 }